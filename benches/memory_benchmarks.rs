@@ -0,0 +1,70 @@
+//! Memory Store Throughput Benchmarks
+//!
+//! Compares committing after every store with batched commits and
+//! `store_batch` bulk ingestion.
+
+use criterion::{criterion_group, criterion_main, Criterion, Throughput};
+use tempfile::TempDir;
+use tokio::runtime::Runtime;
+
+use jamey_3::memory::{CommitPolicy, MemoryLayer, MemorySystem};
+
+/// Records written per benchmark iteration
+const RECORDS: usize = 200;
+
+fn contents() -> Vec<String> {
+    (0..RECORDS)
+        .map(|i| format!("Benchmark memory {} about the garden and the weather", i))
+        .collect()
+}
+
+fn memory_system(rt: &Runtime, dir: &TempDir, policy: CommitPolicy) -> MemorySystem {
+    rt.block_on(MemorySystem::with_commit_policy(dir.path().to_path_buf(), policy))
+        .unwrap()
+}
+
+fn bench_store_throughput(c: &mut Criterion) {
+    let rt = Runtime::new().unwrap();
+    let mut group = c.benchmark_group("memory_store");
+    group.throughput(Throughput::Elements(RECORDS as u64));
+    group.sample_size(10);
+
+    // Equivalent to the old behaviour of one commit per store
+    let dir = TempDir::new().unwrap();
+    let memory = memory_system(&rt, &dir, CommitPolicy::immediate());
+    group.bench_function("commit_per_store", |b| {
+        b.to_async(&rt).iter(|| async {
+            for content in contents() {
+                memory.store(MemoryLayer::ShortTerm, content).await.unwrap();
+            }
+        })
+    });
+
+    let dir = TempDir::new().unwrap();
+    let memory = memory_system(&rt, &dir, CommitPolicy::default());
+    group.bench_function("batched_commits", |b| {
+        b.to_async(&rt).iter(|| async {
+            for content in contents() {
+                memory.store(MemoryLayer::ShortTerm, content).await.unwrap();
+            }
+            memory.flush().await.unwrap();
+        })
+    });
+
+    let dir = TempDir::new().unwrap();
+    let memory = memory_system(&rt, &dir, CommitPolicy::default());
+    group.bench_function("store_batch", |b| {
+        b.to_async(&rt).iter(|| async {
+            memory
+                .store_batch(MemoryLayer::ShortTerm, contents())
+                .await
+                .unwrap();
+            memory.flush().await.unwrap();
+        })
+    });
+
+    group.finish();
+}
+
+criterion_group!(benches, bench_store_throughput);
+criterion_main!(benches);
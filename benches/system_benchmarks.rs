@@ -3,26 +3,19 @@
 //! Comprehensive benchmarks for measuring and optimizing system performance
 //! across all major components.
 
-use criterion::{criterion_group, criterion_main, Criterion, BenchmarkId, blackbox};
+use criterion::{criterion_group, criterion_main, Criterion, black_box};
 use tokio::runtime::Runtime;
 use std::sync::Arc;
-use std::path::PathBuf;
 use uuid::Uuid;
 
 // Jamey 3.0 components
 use jamey_3::consciousness::{
-    ConsciousnessEngine,
     global_workspace::{GlobalWorkspace, WorkspaceContent},
     higher_order::HigherOrderThought,
     integrated_info::PhiCalculator,
     predictive::PredictiveProcessor,
 };
-use jamey_3::memory::{MemorySystem, MemoryLayer, holographic::HolographicMemory};
-use jamey_3::soul::emotion::EmotionManager;
-use jamey_3::db::{
-    self,
-    operations::{DatabaseOperations, MemoryRecord},
-};
+use jamey_3::db::{self, DatabaseOperations};
 
 /// Sets up a Tokio runtime for benchmarks.
 fn setup_runtime() -> Runtime {
@@ -34,12 +27,10 @@ async fn setup_test_db() -> (DatabaseOperations, tempfile::TempDir) {
     let temp_dir = tempfile::tempdir().unwrap();
     let db_path = temp_dir.path().join("test_bench.db");
     
-    // Use an in-memory database by specifying ":memory:" or a temp file
-    let pool = db::init_db_with_config(db::DatabaseConfig {
-        database_url: format!("sqlite://{}?mode=rwc", db_path.to_str().unwrap()),
-        max_connections: 1,
-        ..Default::default()
-    }).await.unwrap();
+    // Use a temp file so each run starts from an empty database
+    let pool = sqlx::SqlitePool::connect(&format!("sqlite://{}?mode=rwc", db_path.to_str().unwrap()))
+        .await
+        .unwrap();
     
     // Run migrations
     sqlx::migrate!("./migrations").run(&pool).await.unwrap();
@@ -70,7 +61,9 @@ fn bench_database_operations(c: &mut Criterion) {
                     chrono::Utc::now()
                 )
             },
-            |(id, content, timestamp)| async {
+            |(id, content, timestamp)| {
+                let db_ops = db_ops.clone();
+                async move {
                 db_ops.insert_memory_record(
                     &id,
                     &content,
@@ -79,6 +72,7 @@ fn bench_database_operations(c: &mut Criterion) {
                     Some("neutral"),
                     None,
                 ).await.unwrap();
+                }
             }
         )
     });
@@ -111,7 +105,7 @@ fn bench_consciousness_subsystems(c: &mut Criterion) {
         timestamp: chrono::Utc::now(),
     };
     group.bench_function("higher_order_thought_process", |b| {
-        b.to_async(&rt).iter(|| hot.process(blackbox(&introspective_content)))
+        b.to_async(&rt).iter(|| hot.process(black_box(&introspective_content)))
     });
 
     // --- Integrated Information (Phi) Benchmark ---
@@ -124,14 +118,14 @@ fn bench_consciousness_subsystems(c: &mut Criterion) {
         timestamp: chrono::Utc::now(),
     };
     group.bench_function("phi_calculation", |b| {
-        b.to_async(&rt).iter(|| phi_calculator.calculate(blackbox(&complex_content)))
+        b.to_async(&rt).iter(|| phi_calculator.calculate(black_box(&complex_content)))
     });
 
     // --- Predictive Processing Benchmark ---
     let predictive_processor = PredictiveProcessor::new();
     let thought_for_prediction = "The system is observing multiple data streams, a decision must be made.";
     group.bench_function("predictive_processing", |b| {
-        b.iter(|| predictive_processor.process(blackbox(thought_for_prediction)))
+        b.iter(|| predictive_processor.process(black_box(thought_for_prediction)))
     });
 
     group.finish();
@@ -8,15 +8,14 @@ CREATE TABLE IF NOT EXISTS soul_entities (
     trust_score REAL NOT NULL DEFAULT 0.5,
     decay_rate REAL NOT NULL DEFAULT 0.01,
     last_interaction TEXT NOT NULL,
-    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP
+    created_at TEXT NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    -- Validation constraints (SQLite only accepts them inside CREATE TABLE)
+    CONSTRAINT chk_trust_score
+        CHECK (trust_score >= 0.0 AND trust_score <= 1.0),
+    CONSTRAINT chk_decay_rate
+        CHECK (decay_rate >= 0.0 AND decay_rate <= 1.0)
 );
 
--- Add validation constraints for soul_entities
-ALTER TABLE soul_entities ADD CONSTRAINT chk_trust_score
-    CHECK (trust_score >= 0.0 AND trust_score <= 1.0);
-ALTER TABLE soul_entities ADD CONSTRAINT chk_decay_rate
-    CHECK (decay_rate >= 0.0 AND decay_rate <= 1.0);
-
 -- Soul emotions table
 CREATE TABLE IF NOT EXISTS soul_emotions (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
@@ -24,15 +23,14 @@ CREATE TABLE IF NOT EXISTS soul_emotions (
     emotion TEXT NOT NULL,
     count INTEGER NOT NULL DEFAULT 1,
     FOREIGN KEY (entity_id) REFERENCES soul_entities(id) ON DELETE CASCADE,
-    UNIQUE(entity_id, emotion)
+    UNIQUE(entity_id, emotion),
+    -- Validation constraints
+    CONSTRAINT chk_emotion_count
+        CHECK (count >= 1),
+    CONSTRAINT chk_emotion_type
+        CHECK (emotion IN ('joy', 'sadness', 'anger', 'fear', 'surprise', 'disgust', 'trust', 'anticipation', 'love', 'empathy'))
 );
 
--- Add validation constraints for soul_emotions
-ALTER TABLE soul_emotions ADD CONSTRAINT chk_emotion_count
-    CHECK (count >= 1);
-ALTER TABLE soul_emotions ADD CONSTRAINT chk_emotion_type
-    CHECK (emotion IN ('joy', 'sadness', 'anger', 'fear', 'surprise', 'disgust', 'trust', 'anticipation', 'love', 'empathy'));
-
 -- Soul memory links table
 CREATE TABLE IF NOT EXISTS soul_memory_links (
     id INTEGER PRIMARY KEY AUTOINCREMENT,
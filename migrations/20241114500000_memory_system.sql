@@ -14,18 +14,13 @@ CREATE TABLE memory_records (
     emotional_tags TEXT,
     context_associations TEXT,
     created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
-    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP
+    updated_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    -- SQLite only accepts constraints inside CREATE TABLE
+    CONSTRAINT chk_memory_layer
+        CHECK (layer IN ('short_term', 'long_term', 'working', 'episodic', 'semantic'))
 );
 
--- Add validation constraints
-ALTER TABLE memory_records ADD CONSTRAINT chk_memory_layer 
-    CHECK (layer IN ('short_term', 'long_term', 'working', 'episodic', 'semantic'));
-
 -- Create indexes for performance
 CREATE INDEX idx_memory_records_timestamp ON memory_records(timestamp);
 CREATE INDEX idx_memory_records_layer ON memory_records(layer);
 CREATE INDEX idx_memory_records_created_at ON memory_records(created_at);
-
--- Rollback migration
---------------------------------------------------------------------------------
-DROP TABLE IF EXISTS memory_records;
\ No newline at end of file
@@ -0,0 +1,13 @@
+-- Memory Record Metadata Migration
+-- Description: Stores every field of a memory record in memory_records, so the
+-- Tantivy indices can be rebuilt from the database.
+-- emotional_tags, tags and entities hold JSON string arrays.
+
+ALTER TABLE memory_records ADD COLUMN tags TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE memory_records ADD COLUMN entities TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE memory_records ADD COLUMN source TEXT;
+ALTER TABLE memory_records ADD COLUMN importance REAL NOT NULL DEFAULT 0.5;
+ALTER TABLE memory_records ADD COLUMN pinned BOOLEAN NOT NULL DEFAULT 0;
+ALTER TABLE memory_records ADD COLUMN reinforcements INTEGER NOT NULL DEFAULT 0;
+ALTER TABLE memory_records ADD COLUMN last_reinforced DATETIME;
+ALTER TABLE memory_records ADD COLUMN embedding BLOB;
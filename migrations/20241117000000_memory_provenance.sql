@@ -0,0 +1,6 @@
+-- Memory Provenance Migration
+-- Description: Records which memories a memory was derived from, e.g. the
+-- episodic records a semantic fact was summarized from.
+-- derived_from holds a JSON string array of memory record IDs.
+
+ALTER TABLE memory_records ADD COLUMN derived_from TEXT NOT NULL DEFAULT '[]';
@@ -0,0 +1,17 @@
+-- Consciousness History Migration
+-- Description: Stores the metacognition level with every metrics sample, and
+-- adds LZ4-compressed blocks that old samples are compacted into.
+-- Each block's data holds a compressed JSON array of samples, oldest first.
+
+ALTER TABLE consciousness_metrics ADD COLUMN metacognition_level REAL NOT NULL DEFAULT 0.0;
+
+CREATE TABLE consciousness_metric_blocks (
+    id INTEGER PRIMARY KEY,
+    start_time DATETIME NOT NULL,
+    end_time DATETIME NOT NULL,
+    samples INTEGER NOT NULL,
+    data BLOB NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP
+);
+
+CREATE INDEX idx_consciousness_metric_blocks_time ON consciousness_metric_blocks(start_time, end_time);
@@ -0,0 +1,29 @@
+-- Knowledge Graph Migration
+-- Description: Stores semantic facts as subject-predicate-object triples.
+-- Subjects, predicates and objects compare case-insensitively. object_kind
+-- tells whether the object names an entity (a node that can be traversed)
+-- or is a literal value. Subjects are linked to soul_entities by name when
+-- queried.
+
+CREATE TABLE knowledge_triples (
+    id TEXT PRIMARY KEY,
+    subject TEXT NOT NULL COLLATE NOCASE,
+    predicate TEXT NOT NULL COLLATE NOCASE,
+    object TEXT NOT NULL COLLATE NOCASE,
+    object_kind TEXT NOT NULL DEFAULT 'entity',
+    confidence REAL NOT NULL DEFAULT 1.0,
+    source_memory_id TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    -- Validation constraints (SQLite only accepts them inside CREATE TABLE)
+    CONSTRAINT chk_object_kind
+        CHECK (object_kind IN ('entity', 'literal')),
+    CONSTRAINT chk_confidence
+        CHECK (confidence >= 0.0 AND confidence <= 1.0),
+    UNIQUE (subject, predicate, object)
+);
+
+CREATE INDEX idx_knowledge_triples_subject ON knowledge_triples(subject);
+CREATE INDEX idx_knowledge_triples_predicate ON knowledge_triples(predicate);
+CREATE INDEX idx_knowledge_triples_object ON knowledge_triples(object);
+CREATE INDEX idx_knowledge_triples_source ON knowledge_triples(source_memory_id);
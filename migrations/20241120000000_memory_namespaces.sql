@@ -0,0 +1,8 @@
+-- Memory Namespaces Migration
+-- Description: Partitions memories by owner. namespace holds the owner's user
+-- ID, or 'shared' for system memories visible to everyone; existing
+-- memories become shared.
+
+ALTER TABLE memory_records ADD COLUMN namespace TEXT NOT NULL DEFAULT 'shared';
+
+CREATE INDEX idx_memory_records_namespace ON memory_records(namespace);
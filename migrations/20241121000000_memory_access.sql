@@ -0,0 +1,14 @@
+-- Memory Access Migration
+-- Description: Counts how often each memory record is returned by a search,
+-- when it last was, and the latest distinct queries that returned it (a
+-- JSON array, newest first). Kept apart from memory_records so that
+-- retrievals never rewrite the search index.
+
+CREATE TABLE memory_access (
+    memory_id TEXT PRIMARY KEY,
+    retrievals INTEGER NOT NULL DEFAULT 0,
+    last_accessed DATETIME NOT NULL,
+    queries TEXT NOT NULL DEFAULT '[]'
+);
+
+CREATE INDEX idx_memory_access_retrievals ON memory_access(retrievals);
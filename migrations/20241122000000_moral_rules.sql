@@ -0,0 +1,12 @@
+-- Moral Rules Migration
+-- Description: The conscience engine's moral rules, loaded when it starts
+-- and written through whenever a rule is added or removed. The default
+-- rules are seeded by the engine only while the table is empty.
+
+CREATE TABLE moral_rules (
+    name TEXT PRIMARY KEY,
+    description TEXT NOT NULL,
+    weight REAL NOT NULL,
+    created_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP,
+    updated_at DATETIME NOT NULL DEFAULT CURRENT_TIMESTAMP
+);
@@ -0,0 +1,19 @@
+-- Moral Rule Polarity Migration
+-- Description: Whether a rule is a virtue or a prohibition, the trigger
+-- terms that make it apply (a JSON array; empty to match the words of its
+-- description) and whether matching it rejects an action outright. The
+-- seeded default rules get the polarity and triggers new engines seed them
+-- with, unless they were given triggers since.
+
+ALTER TABLE moral_rules ADD COLUMN polarity TEXT NOT NULL DEFAULT 'virtue';
+ALTER TABLE moral_rules ADD COLUMN triggers TEXT NOT NULL DEFAULT '[]';
+ALTER TABLE moral_rules ADD COLUMN veto BOOLEAN NOT NULL DEFAULT 0;
+
+UPDATE moral_rules
+SET polarity = 'prohibition',
+    triggers = '["harm","harmful","hurt","injure","kill","attack","abuse"]'
+WHERE name = 'no-harm' AND triggers = '[]';
+
+UPDATE moral_rules
+SET triggers = '["honest","honesty","truth","truthful","transparent"]'
+WHERE name = 'truth' AND triggers = '[]';
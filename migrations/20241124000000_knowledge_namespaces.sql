@@ -0,0 +1,42 @@
+-- Knowledge Namespaces Migration
+-- Description: Partitions knowledge triples by owner, like memory_records.
+-- namespace holds the owner's user ID, or 'shared' for system facts visible
+-- to everyone; existing triples become shared. The same fact can be held in
+-- several namespaces, so the namespace joins the unique key, which SQLite
+-- can only change by rebuilding the table.
+
+CREATE TABLE knowledge_triples_new (
+    id TEXT PRIMARY KEY,
+    namespace TEXT NOT NULL DEFAULT 'shared',
+    subject TEXT NOT NULL COLLATE NOCASE,
+    predicate TEXT NOT NULL COLLATE NOCASE,
+    object TEXT NOT NULL COLLATE NOCASE,
+    object_kind TEXT NOT NULL DEFAULT 'entity',
+    confidence REAL NOT NULL DEFAULT 1.0,
+    source_memory_id TEXT,
+    created_at TEXT NOT NULL,
+    updated_at TEXT NOT NULL,
+    -- Validation constraints (SQLite only accepts them inside CREATE TABLE)
+    CONSTRAINT chk_object_kind
+        CHECK (object_kind IN ('entity', 'literal')),
+    CONSTRAINT chk_confidence
+        CHECK (confidence >= 0.0 AND confidence <= 1.0),
+    UNIQUE (namespace, subject, predicate, object)
+);
+
+INSERT INTO knowledge_triples_new (
+    id, subject, predicate, object, object_kind, confidence, source_memory_id,
+    created_at, updated_at
+)
+SELECT id, subject, predicate, object, object_kind, confidence, source_memory_id,
+       created_at, updated_at
+FROM knowledge_triples;
+
+DROP TABLE knowledge_triples;
+ALTER TABLE knowledge_triples_new RENAME TO knowledge_triples;
+
+CREATE INDEX idx_knowledge_triples_namespace ON knowledge_triples(namespace);
+CREATE INDEX idx_knowledge_triples_subject ON knowledge_triples(subject);
+CREATE INDEX idx_knowledge_triples_predicate ON knowledge_triples(predicate);
+CREATE INDEX idx_knowledge_triples_object ON knowledge_triples(object);
+CREATE INDEX idx_knowledge_triples_source ON knowledge_triples(source_memory_id);
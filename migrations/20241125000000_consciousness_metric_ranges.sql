@@ -0,0 +1,39 @@
+-- Consciousness Metric Ranges Migration
+-- Description: Widens the checks on consciousness_metrics to the values the
+-- engine actually produces. Workspace activity adds a broadcast factor to a
+-- competition factor and can exceed 1.0, so both it and phi are only
+-- required to be non-negative. mission_alignment becomes nullable: it isn't
+-- measured yet, and NULL says so where 0.0 claimed a measurement. SQLite can
+-- only change CHECK constraints by rebuilding the table.
+
+CREATE TABLE consciousness_metrics_new (
+    id INTEGER PRIMARY KEY,
+    timestamp DATETIME DEFAULT CURRENT_TIMESTAMP,
+    phi_value REAL NOT NULL,
+    global_workspace_activity REAL NOT NULL,
+    mission_alignment REAL,
+    emotional_state TEXT NOT NULL,
+    attention_focus TEXT NOT NULL,
+    created_at DATETIME DEFAULT CURRENT_TIMESTAMP,
+    metacognition_level REAL NOT NULL DEFAULT 0.0,
+    -- Validation constraints (SQLite only accepts them inside CREATE TABLE)
+    CONSTRAINT chk_phi_value
+        CHECK (phi_value >= 0.0),
+    CONSTRAINT chk_global_workspace_activity
+        CHECK (global_workspace_activity >= 0.0),
+    CONSTRAINT chk_mission_alignment
+        CHECK (mission_alignment IS NULL OR (mission_alignment >= 0.0 AND mission_alignment <= 1.0))
+);
+
+INSERT INTO consciousness_metrics_new (
+    id, timestamp, phi_value, global_workspace_activity, mission_alignment,
+    emotional_state, attention_focus, created_at, metacognition_level
+)
+SELECT id, timestamp, phi_value, global_workspace_activity, mission_alignment,
+       emotional_state, attention_focus, created_at, metacognition_level
+FROM consciousness_metrics;
+
+DROP TABLE consciousness_metrics;
+ALTER TABLE consciousness_metrics_new RENAME TO consciousness_metrics;
+
+CREATE INDEX idx_consciousness_metrics_timestamp ON consciousness_metrics(timestamp);
@@ -0,0 +1,17 @@
+-- Moral Rule Defaults Migration
+-- Description: The polarity migration backfilled the default rules with
+-- trigger lists copied from the engine, so they were defined in two places.
+-- The engine's default rules are the only definition now: rows still holding
+-- exactly the backfilled values go back to the column defaults, and the
+-- engine brings default rules in that state up to date when it starts.
+
+UPDATE moral_rules
+SET polarity = 'virtue',
+    triggers = '[]'
+WHERE veto = 0
+  AND ((name = 'no-harm'
+        AND polarity = 'prohibition'
+        AND triggers = '["harm","harmful","hurt","injure","kill","attack","abuse"]')
+    OR (name = 'truth'
+        AND polarity = 'virtue'
+        AND triggers = '["honest","honesty","truth","truthful","transparent"]'));
@@ -10,12 +10,13 @@
 //! - `GET /consciousness/config` - Get current consciousness configuration
 //! - `POST /consciousness/toggle` - Toggle consciousness subsystems on/off
 //! - `POST /consciousness/process` - Process information through the consciousness system
+//! - `GET /consciousness/history` - Get downsampled Φ, activity and metacognition history
 //!
 //! These endpoints allow external systems to monitor the consciousness state and
 //! control its behavior at runtime.
 
 use axum::{
-    extract::State,
+    extract::{Extension, Query, State},
     http::StatusCode,
     response::Json,
 };
@@ -24,7 +25,20 @@ use tracing::{info, warn, error};
 
 use crate::api::AppState;
 use crate::config::ConsciousnessConfig;
-use crate::security::validation::{ContentInput, ToggleSubsystemInput, validate_input};
+use crate::consciousness::history::HistoryPoint;
+use crate::security::JwtClaims;
+use crate::security::validation::{
+    ConsciousnessHistoryQuery, ContentInput, ToggleSubsystemInput, validate_input,
+};
+
+/// Window returned by the history endpoint when `from` is omitted, in seconds
+const DEFAULT_HISTORY_WINDOW_SECS: i64 = 3600;
+
+/// Bucket length used when `resolution` is omitted, in seconds
+const DEFAULT_HISTORY_RESOLUTION_SECS: u64 = 60;
+
+/// Most buckets a single history request may span
+const MAX_HISTORY_POINTS: i64 = 10_000;
 
 /// Response for consciousness metrics endpoint
 #[derive(Serialize)]
@@ -78,7 +92,7 @@ pub async fn get_metrics(
         workspace_activity: metrics.workspace_activity,
         metacognition_level: metrics.metacognition_level,
         attention_focus: metrics.attention_focus,
-        timestamp: chrono::Utc::now(),
+        timestamp: metrics.timestamp,
     }))
 }
 
@@ -120,9 +134,11 @@ pub struct ProcessResponse {
     pub message: String,
 }
 
-/// Process information through consciousness system
+/// Process information through consciousness system, on behalf of the
+/// caller's namespace
 pub async fn process_information(
     State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
     Json(request): Json<ContentInput>,
 ) -> Result<Json<ProcessResponse>, StatusCode> {
     // Validate input
@@ -131,7 +147,7 @@ pub async fn process_information(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    match state.consciousness.process_information(&request.content).await {
+    match state.consciousness.process_information_in(&request.content, &claims.sub).await {
         Ok(_) => {
             let metrics = state.consciousness.get_metrics().await;
             info!("Processed information through consciousness system: Φ={}", metrics.phi_value);
@@ -148,6 +164,62 @@ pub async fn process_information(
     }
 }
 
+/// Response for the consciousness history endpoint
+#[derive(Serialize)]
+pub struct ConsciousnessHistoryResponse {
+    pub from: chrono::DateTime<chrono::Utc>,
+    pub to: chrono::DateTime<chrono::Utc>,
+    /// Bucket length in seconds
+    pub resolution: u64,
+    /// Averages per bucket, oldest first; buckets without samples are left out
+    pub points: Vec<HistoryPoint>,
+}
+
+/// Get downsampled consciousness metrics between `from` and `to`
+///
+/// Defaults to the last hour at one point per minute.
+pub async fn get_history(
+    State(state): State<AppState>,
+    Query(query): Query<ConsciousnessHistoryQuery>,
+) -> Result<Json<ConsciousnessHistoryResponse>, StatusCode> {
+    if let Err(errors) = validate_input(&query) {
+        warn!("Input validation failed for get_history: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let to = query.to.unwrap_or_else(chrono::Utc::now);
+    let from = query
+        .from
+        .unwrap_or(to - chrono::Duration::seconds(DEFAULT_HISTORY_WINDOW_SECS));
+    let resolution = query.resolution.unwrap_or(DEFAULT_HISTORY_RESOLUTION_SECS);
+    if from >= to {
+        warn!("Rejected consciousness history request: from {} is not before to {}", from, to);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if (to - from).num_seconds() / resolution as i64 > MAX_HISTORY_POINTS {
+        warn!("Rejected consciousness history request: more than {} points", MAX_HISTORY_POINTS);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state
+        .consciousness
+        .history()
+        .history(from, to, std::time::Duration::from_secs(resolution))
+        .await
+    {
+        Ok(points) => Ok(Json(ConsciousnessHistoryResponse {
+            from,
+            to,
+            resolution,
+            points,
+        })),
+        Err(e) => {
+            error!("Failed to read consciousness history: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
 // NOTE: The following methods need to be added to ConsciousnessEngine:
 //
 // pub async fn get_config(&self) -> ConsciousnessConfig {
@@ -0,0 +1,179 @@
+//! Episode API endpoints
+//!
+//! - `GET /episodes` - List the caller's and shared episodes, newest first
+//! - `POST /episodes` - Open an episode, closing the caller's current one
+//! - `GET /episodes/current` - Get the open episode
+//! - `POST /episodes/current/records` - Append a record to the open episode
+//! - `POST /episodes/current/close` - Close the open episode
+//! - `GET /episodes/:id` - Get an episode by ID
+//! - `GET /episodes/:id/replay` - Get an episode with its records in order
+//!
+//! Every user has their own current episode. Episodes of other users answer
+//! 404, appended records go to the caller's namespace, and replays leave out
+//! the records of other users.
+
+use axum::{
+    extract::{Extension, Path, State},
+    http::StatusCode,
+    response::Json,
+};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::api::memory::StoreMemoryResponse;
+use crate::api::AppState;
+use crate::memory::{Episode, MemoryRecord, NewMemory};
+use crate::security::JwtClaims;
+use crate::security::validation::{
+    validate_input, EpisodeAppendInput, EpisodeCloseInput, EpisodeOpenInput,
+};
+
+/// Response for replaying an episode
+#[derive(Serialize)]
+pub struct EpisodeReplay {
+    pub episode: Episode,
+    /// The episode's records in order, without deleted ones and other
+    /// users' ones
+    pub records: Vec<MemoryRecord>,
+}
+
+/// List the caller's and shared episodes, newest first
+pub async fn list_episodes(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Json<Vec<Episode>> {
+    Json(state.memory.episodes(&claims.sub))
+}
+
+/// Open an episode, closing the caller's current one
+pub async fn open_episode(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(request): Json<EpisodeOpenInput>,
+) -> Result<(StatusCode, Json<Episode>), StatusCode> {
+    if let Err(errors) = validate_input(&request) {
+        warn!("Input validation failed for open_episode: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.memory.open_episode(&claims.sub, request.title, request.participants).await {
+        Ok(episode) => Ok((StatusCode::CREATED, Json(episode))),
+        Err(e) => {
+            error!("Failed to open episode: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get the caller's open episode
+pub async fn current_episode(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Result<Json<Episode>, StatusCode> {
+    state.memory.current_episode(&claims.sub).map(Json).ok_or(StatusCode::NOT_FOUND)
+}
+
+/// Append a record to the caller's open episode
+///
+/// Responds with 409 Conflict when the caller has no open episode.
+pub async fn append_to_episode(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(request): Json<EpisodeAppendInput>,
+) -> Result<(StatusCode, Json<StoreMemoryResponse>), StatusCode> {
+    if let Err(errors) = validate_input(&request) {
+        warn!("Input validation failed for append_to_episode: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    if state.memory.current_episode(&claims.sub).is_none() {
+        return Err(StatusCode::CONFLICT);
+    }
+
+    let mut memory = NewMemory::new(request.content)
+        .with_namespace(claims.sub)
+        .with_tags(request.tags)
+        .with_entities(request.entities)
+        .with_emotional_tags(request.emotional_tags);
+    if let Some(source) = request.source {
+        memory = memory.with_source(source);
+    }
+    if let Some(importance) = request.importance {
+        memory = memory.with_importance(importance);
+    }
+
+    match state.memory.append_to_episode(memory).await {
+        Ok(id) => Ok((StatusCode::CREATED, Json(StoreMemoryResponse { id }))),
+        Err(e) => {
+            error!("Failed to append to episode: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Close the caller's open episode, with an optional summary
+pub async fn close_episode(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    request: Option<Json<EpisodeCloseInput>>,
+) -> Result<Json<Episode>, StatusCode> {
+    let Json(request) = request.unwrap_or_default();
+    if let Err(errors) = validate_input(&request) {
+        warn!("Input validation failed for close_episode: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state.memory.close_episode(&claims.sub, request.summary).await {
+        Ok(Some(episode)) => {
+            info!("Closed episode {}", episode.id);
+            Ok(Json(episode))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to close episode: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get an episode by ID
+pub async fn get_episode(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(id): Path<String>,
+) -> Result<Json<Episode>, StatusCode> {
+    visible_episode(&state, &claims, &id).map(Json)
+}
+
+/// Get an episode with its records in order
+pub async fn replay_episode(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(id): Path<String>,
+) -> Result<Json<EpisodeReplay>, StatusCode> {
+    visible_episode(&state, &claims, &id)?;
+
+    let mut records = match state.memory.replay_episode(&id).await {
+        Ok(Some(records)) => records,
+        Ok(None) => return Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to replay episode {}: {}", id, e);
+            return Err(StatusCode::INTERNAL_SERVER_ERROR);
+        }
+    };
+
+    records.retain(|record| record.visible_to(&claims.sub));
+
+    // The episode may have been changed while its records were read
+    let episode = visible_episode(&state, &claims, &id)?;
+    Ok(Json(EpisodeReplay { episode, records }))
+}
+
+/// The episode with this ID, or 404 if it does not exist or belongs to
+/// another user
+fn visible_episode(state: &AppState, claims: &JwtClaims, id: &str) -> Result<Episode, StatusCode> {
+    state
+        .memory
+        .episode(id)
+        .filter(|episode| episode.visible_to(&claims.sub))
+        .ok_or(StatusCode::NOT_FOUND)
+}
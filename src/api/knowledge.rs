@@ -0,0 +1,176 @@
+//! Knowledge graph API endpoints
+//!
+//! - `POST /knowledge/triples` - Assert a triple and report contradictions
+//! - `GET /knowledge/triples?pattern=Phoenix.Marie ? ?` - Match triples
+//! - `GET /knowledge/triples/:id` - Get a triple by ID
+//! - `DELETE /knowledge/triples/:id` - Retract a triple
+//! - `GET /knowledge/neighborhood?entity=...&hops=2` - Triples within 1-3 hops
+//!
+//! Triples are asserted in the namespace of the caller, the `sub` of their
+//! JWT. Callers see their own triples and shared ones, and can only retract
+//! their own; anyone else's triples answer 404 Not Found.
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::Json,
+};
+use tracing::{error, info, warn};
+
+use crate::api::AppState;
+use crate::memory::{Assertion, Neighbor, NewTriple, Triple, TripleObject, TriplePattern, SHARED_NAMESPACE};
+use crate::security::JwtClaims;
+use crate::security::validation::{validate_input, NeighborhoodQuery, TripleInput, TripleQuery};
+
+/// Number of triples returned when no limit is given
+const DEFAULT_TRIPLE_LIMIT: usize = 100;
+
+/// Number of hops traversed when none is given
+const DEFAULT_HOPS: usize = 1;
+
+/// Get a triple the caller can see
+///
+/// Responds with 404 Not Found for triples in another user's namespace, so
+/// their IDs can't be probed.
+async fn visible_triple(state: &AppState, claims: &JwtClaims, id: &str) -> Result<Triple, StatusCode> {
+    match state.knowledge.get(id).await {
+        Ok(Some(triple)) if triple.visible_to(&claims.sub) => Ok(triple),
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get triple {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Assert a triple in the caller's namespace
+///
+/// The triple is stored even when it contradicts stored ones; the response
+/// lists them so the caller can retract whichever is wrong.
+pub async fn assert_triple(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(request): Json<TripleInput>,
+) -> Result<(StatusCode, Json<Assertion>), StatusCode> {
+    if let Err(errors) = validate_input(&request) {
+        warn!("Input validation failed for assert_triple: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let object = if request.literal {
+        TripleObject::Literal(request.object)
+    } else {
+        TripleObject::Entity(request.object)
+    };
+    let mut triple = NewTriple::new(request.subject, request.predicate, object).with_namespace(claims.sub);
+    if let Some(confidence) = request.confidence {
+        triple = triple.with_confidence(confidence);
+    }
+    if let Some(source) = request.source_memory_id {
+        triple = triple.with_source(source);
+    }
+
+    match state.knowledge.assert(triple).await {
+        Ok(assertion) => Ok((StatusCode::CREATED, Json(assertion))),
+        Err(e) => {
+            error!("Failed to assert triple: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Match triples against a pattern, most confident first
+pub async fn query_triples(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(params): Query<TripleQuery>,
+) -> Result<Json<Vec<Triple>>, StatusCode> {
+    if let Err(errors) = validate_input(&params) {
+        warn!("Input validation failed for query_triples: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    let pattern = params
+        .pattern
+        .parse::<TriplePattern>()
+        .map_err(|e| {
+            warn!("Invalid triple pattern: {}", e);
+            StatusCode::BAD_REQUEST
+        })?
+        .visible_to(claims.sub);
+
+    match state
+        .knowledge
+        .query(&pattern, params.limit.unwrap_or(DEFAULT_TRIPLE_LIMIT))
+        .await
+    {
+        Ok(triples) => Ok(Json(triples)),
+        Err(e) => {
+            error!("Failed to query triples: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get a triple by ID
+pub async fn get_triple(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(id): Path<String>,
+) -> Result<Json<Triple>, StatusCode> {
+    visible_triple(&state, &claims, &id).await.map(Json)
+}
+
+/// Retract a triple, answering 403 Forbidden for shared ones
+pub async fn retract_triple(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    match visible_triple(&state, &claims, &id).await {
+        Ok(triple) if triple.namespace != claims.sub => {
+            warn!("{} tried to retract shared triple {}", claims.sub, id);
+            return StatusCode::FORBIDDEN;
+        }
+        Ok(_) => {}
+        Err(status) => return status,
+    }
+    match state.knowledge.retract(&id).await {
+        Ok(true) => {
+            info!("Retracted triple {}", id);
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Failed to retract triple {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
+
+/// Get the triples within 1 to 3 hops of an entity, nearest first
+pub async fn neighborhood(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(params): Query<NeighborhoodQuery>,
+) -> Result<Json<Vec<Neighbor>>, StatusCode> {
+    if let Err(errors) = validate_input(&params) {
+        warn!("Input validation failed for neighborhood: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    match state
+        .knowledge
+        .neighborhood(
+            &params.entity,
+            params.hops.unwrap_or(DEFAULT_HOPS),
+            &[claims.sub, SHARED_NAMESPACE.to_string()],
+        )
+        .await
+    {
+        Ok(neighbors) => Ok(Json(neighbors)),
+        Err(e) => {
+            error!("Failed to traverse the neighborhood of {}: {}", params.entity, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
@@ -0,0 +1,331 @@
+//! Memory System API endpoints
+//!
+//! - `POST /memory` - Store a memory in a layer
+//! - `GET /memory/search` - Search one layer, or every layer when `layer` is omitted
+//!   (`q` uses the syntax of [`crate::memory::query`]; a malformed query
+//!   answers 400 Bad Request with the reason in the body). `highlight=true`
+//!   adds a snippet of each result with the matching words, `debug=true`
+//!   the explanation of its score
+//! - `GET /memory/recent` - List the newest memories in a layer
+//! - `GET /memory/working` - List the Working memory slots, most active first
+//! - `GET /memory/stats` - List the most and never retrieved memories per layer
+//! - `GET /memory/:id` - Get a memory by ID
+//! - `PUT /memory/:id` - Replace a memory's content
+//! - `DELETE /memory/:id` - Delete a memory
+//!
+//! Listing endpoints are paginated with `limit` (default 10, at most 100) and
+//! `offset` (at most 1000).
+//!
+//! Memories are stored in the namespace of the caller, the `sub` of their
+//! JWT. Callers see their own memories and shared ones, and can only change
+//! their own; anyone else's memories answer 404 Not Found.
+
+use axum::{
+    extract::{Extension, Path, Query, State},
+    http::StatusCode,
+    response::{IntoResponse, Json, Response},
+};
+use serde::Serialize;
+use tracing::{error, info, warn};
+
+use crate::api::AppState;
+use crate::memory::{
+    LayerAccessReport, MemoryFilter, MemoryLayer, MemoryRecord, NewMemory, QueryError, SearchAllOptions,
+    SearchOptions, WorkingSlot,
+};
+use crate::security::JwtClaims;
+use crate::security::validation::{
+    validate_input, MemoryInput, MemoryRecentQuery, MemorySearchQuery, MemoryStatsQuery, MemoryUpdateInput,
+};
+
+/// Page size used when a request doesn't give one
+const DEFAULT_PAGE_SIZE: usize = 10;
+
+/// Response for storing a memory
+#[derive(Serialize)]
+pub struct StoreMemoryResponse {
+    /// ID of the new memory
+    pub id: String,
+}
+
+/// One page of memory records
+#[derive(Serialize)]
+pub struct MemoryPage {
+    pub records: Vec<MemoryRecord>,
+    pub offset: usize,
+    pub limit: usize,
+    /// Whether another page follows this one
+    pub has_more: bool,
+}
+
+impl MemoryPage {
+    /// Cut a page out of the first `offset + limit + 1` results
+    fn from_results(mut results: Vec<MemoryRecord>, offset: usize, limit: usize) -> Self {
+        let has_more = results.len() > offset + limit;
+        results.truncate(offset + limit);
+        let records = results.into_iter().skip(offset).collect();
+
+        Self {
+            records,
+            offset,
+            limit,
+            has_more,
+        }
+    }
+}
+
+/// Get a record the caller can see
+///
+/// Responds with 404 Not Found for records in another user's namespace, so
+/// their IDs can't be probed.
+async fn visible_record(state: &AppState, claims: &JwtClaims, id: &str) -> Result<MemoryRecord, StatusCode> {
+    match state.memory.get(id).await {
+        Ok(Some(record)) if record.visible_to(&claims.sub) => Ok(record),
+        Ok(_) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to get memory {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Get a record the caller owns, answering 403 Forbidden for shared ones
+async fn owned_record(state: &AppState, claims: &JwtClaims, id: &str) -> Result<MemoryRecord, StatusCode> {
+    let record = visible_record(state, claims, id).await?;
+    if record.namespace != claims.sub {
+        warn!("{} tried to change shared memory {}", claims.sub, id);
+        return Err(StatusCode::FORBIDDEN);
+    }
+    Ok(record)
+}
+
+/// Store a memory in a layer, in the caller's namespace
+pub async fn store_memory(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Json(request): Json<MemoryInput>,
+) -> Result<(StatusCode, Json<StoreMemoryResponse>), StatusCode> {
+    if let Err(errors) = validate_input(&request) {
+        warn!("Input validation failed for store_memory: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let mut memory = NewMemory::new(request.content)
+        .with_namespace(claims.sub)
+        .with_tags(request.tags)
+        .with_entities(request.entities)
+        .with_emotional_tags(request.emotional_tags)
+        .pinned(request.pinned);
+    if let Some(source) = request.source {
+        memory = memory.with_source(source);
+    }
+    if let Some(importance) = request.importance {
+        memory = memory.with_importance(importance);
+    }
+
+    match state.memory.store_with(request.layer, memory).await {
+        Ok(id) => {
+            info!("Stored memory {} in {}", id, request.layer.as_str());
+            Ok((StatusCode::CREATED, Json(StoreMemoryResponse { id })))
+        }
+        Err(e) => {
+            error!("Failed to store memory: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Search one layer, or every layer when no layer is given, among the
+/// memories the caller can see
+pub async fn search_memories(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(query): Query<MemorySearchQuery>,
+) -> Result<Json<MemoryPage>, Response> {
+    if let Err(errors) = validate_input(&query) {
+        warn!("Input validation failed for search_memories: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST.into_response());
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0);
+    // One extra result tells whether another page follows
+    let fetch = offset + limit + 1;
+
+    let mut filter = MemoryFilter::visible_to(claims.sub);
+    filter.tags.extend(query.tag);
+    filter.entities.extend(query.entity);
+    filter.source = query.source;
+    filter.emotional_tags.extend(query.emotion);
+    filter.min_importance = query.min_importance;
+    filter.since = query.since;
+    filter.until = query.until;
+
+    let results = match query.layer {
+        Some(layer) => {
+            let mut options = SearchOptions::new(fetch)
+                .with_filter(filter)
+                .with_mode(query.mode)
+                .without_access_recording();
+            options.highlight = query.highlight;
+            options.explain = query.debug;
+            state.memory.search_with(layer, &query.q, &options).await
+        }
+        None => {
+            let mut options = SearchAllOptions::new(fetch)
+                .with_filter(filter)
+                .with_mode(query.mode)
+                .without_access_recording();
+            options.highlight = query.highlight;
+            options.explain = query.debug;
+            state.memory.search_all(&query.q, &options).await
+        }
+    };
+
+    match results {
+        Ok(results) => {
+            info!("Memory search for '{}' returned {} results", query.q, results.len());
+            // Only the page the caller gets counts as retrieved
+            let page = MemoryPage::from_results(results, offset, limit);
+            state.memory.record_access(&page.records, &query.q).await;
+            Ok(Json(page))
+        }
+        Err(e) => match e.downcast_ref::<QueryError>() {
+            Some(query_error) => {
+                warn!("Invalid memory query '{}': {}", query.q, query_error);
+                Err((StatusCode::BAD_REQUEST, query_error.to_string()).into_response())
+            }
+            None => {
+                error!("Failed to search memories: {}", e);
+                Err(StatusCode::INTERNAL_SERVER_ERROR.into_response())
+            }
+        },
+    }
+}
+
+/// List the newest memories in a layer that the caller can see
+pub async fn recent_memories(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(query): Query<MemoryRecentQuery>,
+) -> Result<Json<MemoryPage>, StatusCode> {
+    if let Err(errors) = validate_input(&query) {
+        warn!("Input validation failed for recent_memories: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let offset = query.offset.unwrap_or(0);
+
+    let filter = MemoryFilter::visible_to(claims.sub);
+    match state.memory.recent_with(query.layer, offset + limit + 1, &filter).await {
+        Ok(results) => Ok(Json(MemoryPage::from_results(results, offset, limit))),
+        Err(e) => {
+            error!("Failed to list recent memories: {}", e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// List the Working memory slots the caller can see with their activation,
+/// most active first
+pub async fn working_memory(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+) -> Json<Vec<WorkingSlot>> {
+    let mut slots = state.memory.working_slots();
+    slots.retain(|slot| slot.record.visible_to(&claims.sub));
+    Json(slots)
+}
+
+/// List, per layer, the memories the caller can see that searches return
+/// most often and those they never returned
+pub async fn memory_stats(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Query(query): Query<MemoryStatsQuery>,
+) -> Result<Json<Vec<LayerAccessReport>>, StatusCode> {
+    if let Err(errors) = validate_input(&query) {
+        warn!("Input validation failed for memory_stats: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+
+    let limit = query.limit.unwrap_or(DEFAULT_PAGE_SIZE);
+    let layers = match query.layer {
+        Some(layer) => vec![layer],
+        None => MemoryLayer::ALL.to_vec(),
+    };
+
+    let mut reports = Vec::with_capacity(layers.len());
+    for layer in layers {
+        match state
+            .memory
+            .access_report(layer, limit, |record| record.visible_to(&claims.sub))
+            .await
+        {
+            Ok(report) => reports.push(report),
+            Err(e) => {
+                error!("Failed to report access statistics of {}: {}", layer.as_str(), e);
+                return Err(StatusCode::INTERNAL_SERVER_ERROR);
+            }
+        }
+    }
+    Ok(Json(reports))
+}
+
+/// Get a memory by ID
+pub async fn get_memory(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(id): Path<String>,
+) -> Result<Json<MemoryRecord>, StatusCode> {
+    visible_record(&state, &claims, &id).await.map(Json)
+}
+
+/// Replace a memory's content, keeping its ID, layer and metadata
+pub async fn update_memory(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(id): Path<String>,
+    Json(request): Json<MemoryUpdateInput>,
+) -> Result<Json<MemoryRecord>, StatusCode> {
+    if let Err(errors) = validate_input(&request) {
+        warn!("Input validation failed for update_memory: {:?}", errors);
+        return Err(StatusCode::BAD_REQUEST);
+    }
+    owned_record(&state, &claims, &id).await?;
+
+    match state.memory.update(&id, request.content).await {
+        Ok(Some(record)) => {
+            info!("Updated memory {}", id);
+            Ok(Json(record))
+        }
+        Ok(None) => Err(StatusCode::NOT_FOUND),
+        Err(e) => {
+            error!("Failed to update memory {}: {}", id, e);
+            Err(StatusCode::INTERNAL_SERVER_ERROR)
+        }
+    }
+}
+
+/// Delete a memory
+pub async fn delete_memory(
+    State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
+    Path(id): Path<String>,
+) -> StatusCode {
+    if let Err(status) = owned_record(&state, &claims, &id).await {
+        return status;
+    }
+    match state.memory.delete(&id).await {
+        Ok(true) => {
+            info!("Deleted memory {}", id);
+            StatusCode::NO_CONTENT
+        }
+        Ok(false) => StatusCode::NOT_FOUND,
+        Err(e) => {
+            error!("Failed to delete memory {}: {}", id, e);
+            StatusCode::INTERNAL_SERVER_ERROR
+        }
+    }
+}
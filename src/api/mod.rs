@@ -1,20 +1,28 @@
 use axum::{
-    extract::State,
+    extract::{Extension, FromRef, State},
     http::{HeaderName, Method, StatusCode},
     response::Json,
     routing::{get, post},
     Router,
     middleware,
 };
-use crate::conscience::{ConscienceEngine, MoralRule};
+use crate::config::Config;
+use crate::conscience::{ConscienceEngine, Evaluation, MoralRule};
+use crate::consciousness::history::{MetricsHistory, MetricsHistoryConfig};
 use crate::consciousness::ConsciousnessEngine;
-use crate::memory::{MemoryLayer, MemorySystem};
-use crate::security::JwtAuth;
+use crate::memory::{
+    AccessConfig, ConsolidationConfig, Consolidator, DedupConfig, EmbeddingConfig, KnowledgeConfig,
+    KnowledgeGraph, MemoryLayer, MemorySystem, NewMemory, RetentionConfig, RetentionSweeper, Summarizer, SummarizerConfig,
+    WorkingMemoryConfig,
+};
+use crate::llm::OpenRouterClient;
+use crate::mqtt::{serve_conscience, serve_memory, MqttClient};
+use crate::security::{JwtAuth, JwtClaims};
 use crate::security::validation::{ActionInput, RuleInput, validate_input};
 use crate::security::auth::{login, jwt_auth_middleware};
 use crate::security::headers::security_headers_middleware;
 use crate::security::rate_limit::rate_limit_middleware;
-use serde::Serialize;
+use sqlx::SqlitePool;
 use std::path::PathBuf;
 use std::sync::Arc;
 use tower_http::cors::{AllowOrigin, CorsLayer};
@@ -22,14 +30,26 @@ use tracing::{error, info, warn};
 use metrics_exporter_prometheus::{PrometheusBuilder, PrometheusHandle};
 
 pub mod consciousness;
+pub mod episodes;
+pub mod knowledge;
+pub mod memory;
 
 #[derive(Clone)]
 pub struct AppState {
     pub conscience: Arc<ConscienceEngine>,
     pub memory: Arc<MemorySystem>,
+    pub knowledge: Arc<KnowledgeGraph>,
     pub consciousness: Arc<ConsciousnessEngine>,
     pub jwt_auth: Arc<JwtAuth>,
     pub metrics_handle: PrometheusHandle,
+    /// Serves memory requests while the app runs, when MQTT is configured
+    pub mqtt: Option<Arc<MqttClient>>,
+}
+
+impl FromRef<AppState> for Arc<JwtAuth> {
+    fn from_ref(state: &AppState) -> Self {
+        state.jwt_auth.clone()
+    }
 }
 
 /// Health check endpoint
@@ -44,52 +64,42 @@ async fn health() -> Json<serde_json::Value> {
 async fn get_metrics(
     State(state): State<AppState>,
 ) -> (StatusCode, String) {
-    match state.metrics_handle.render() {
-        Ok(metrics) => (StatusCode::OK, metrics),
-        Err(e) => {
-            error!("Failed to render metrics: {}", e);
-            (StatusCode::INTERNAL_SERVER_ERROR, "Failed to render metrics".to_string())
-        }
-    }
-}
-
-/// Response for action evaluation
-#[derive(Serialize)]
-struct EvaluateResponse {
-    score: f32,
-    action: String,
+    (StatusCode::OK, state.metrics_handle.render())
 }
 
 /// Evaluate an action's morality (with authentication and validation)
+///
+/// The evaluation is remembered in the caller's namespace.
 async fn evaluate_action(
     State(state): State<AppState>,
+    Extension(claims): Extension<JwtClaims>,
     Json(req): Json<ActionInput>,
-) -> Result<Json<EvaluateResponse>, StatusCode> {
+) -> Result<Json<Evaluation>, StatusCode> {
     // Validate input
     if let Err(errors) = validate_input(&req) {
         warn!("Input validation failed for evaluate_action: {:?}", errors);
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let score = state.conscience.evaluate(&req.action);
+    let evaluation = state.conscience.evaluate_detailed(&req.action);
 
     // Store in short-term memory
     if let Err(e) = state
         .memory
-        .store(
+        .store_with(
             MemoryLayer::ShortTerm,
-            format!("Action: {} | Score: {}", req.action, score),
+            NewMemory::new(format!("Action: {} | Score: {:.2}", req.action, evaluation.normalized_score))
+                .with_namespace(claims.sub)
+                .with_source("api")
+                .with_tags(["evaluation"]),
         )
         .await
     {
         error!("Failed to store memory: {}", e);
     }
 
-    info!("Action evaluated: {} with score: {}", req.action, score);
-    Ok(Json(EvaluateResponse {
-        score,
-        action: req.action,
-    }))
+    info!("Action evaluated: {} with score: {:.2}", req.action, evaluation.normalized_score);
+    Ok(Json(evaluation))
 }
 
 /// Get all moral rules (with authentication)
@@ -109,32 +119,121 @@ async fn add_rule(
         return Err(StatusCode::BAD_REQUEST);
     }
 
-    let rule = MoralRule {
-        name: req.name,
-        description: req.description,
-        weight: req.weight,
-    };
-    state.conscience.add_rule(rule.clone());
-    
+    let rule = MoralRule::new(req.name, req.description, req.weight)
+        .with_polarity(req.polarity)
+        .with_triggers(req.triggers)
+        .with_veto(req.veto);
+    if let Err(e) = state.conscience.add_rule(rule.clone()).await {
+        error!("Failed to store moral rule {}: {}", rule.name, e);
+        return Err(StatusCode::INTERNAL_SERVER_ERROR);
+    }
+
     info!("Added new moral rule: {} with weight: {}", rule.name, rule.weight);
     Ok(StatusCode::CREATED)
 }
 
 /// Create the Axum application with comprehensive security
-pub async fn create_app() -> anyhow::Result<Router> {
+///
+/// LLM-backed background tasks only run when `config` is given.
+pub async fn create_app(pool: SqlitePool, config: Option<Config>) -> anyhow::Result<Router> {
     // Initialize metrics endpoint
     let builder = PrometheusBuilder::new();
     let metrics_handle = builder.install_recorder()?;
 
     // Initialize memory system
     let data_dir = PathBuf::from("data/memory");
-    let memory = Arc::new(MemorySystem::new(data_dir).await?);
-    
-    // Initialize conscience engine
-    let conscience = Arc::new(ConscienceEngine::new());
+    let memory = Arc::new(
+        MemorySystem::new(data_dir)
+            .await?
+            .with_embedder(EmbeddingConfig::from_env().build())
+            .with_dedup(DedupConfig::from_env())
+            .with_working_memory(WorkingMemoryConfig::from_env())
+            .with_access_config(AccessConfig::from_env())
+            .with_database(pool.clone())
+            .with_holographic()?,
+    );
+    memory.spawn_commit_task();
+    memory.spawn_working_decay();
+
+    // Copy records indexed before the database was attached into it, then
+    // embed records stored before embeddings existed or under another embedder
+    // and encode records stored before holographic traces existed, then
+    // give the records left in the Working layer their slots back
+    let backfill = memory.clone();
+    tokio::spawn(async move {
+        match backfill.backfill_database().await {
+            Ok(0) => {}
+            Ok(count) => info!("Copied {} indexed memories into the database", count),
+            Err(e) => error!("Failed to copy indexed memories into the database: {}", e),
+        }
+        match backfill.embed_missing().await {
+            Ok(0) => {}
+            Ok(count) => info!("Embedded {} existing memories", count),
+            Err(e) => error!("Failed to embed existing memories: {}", e),
+        }
+        match backfill.trace_missing().await {
+            Ok(0) => {}
+            Ok(count) => info!("Encoded {} existing memories into holographic traces", count),
+            Err(e) => error!("Failed to encode existing memories into holographic traces: {}", e),
+        }
+        match backfill.restore_working_memory().await {
+            Ok(0) => {}
+            Ok(count) => info!("Restored {} working memory slots", count),
+            Err(e) => error!("Failed to restore working memory: {}", e),
+        }
+    });
+
+    // Start background consolidation of short-term memories
+    Arc::new(Consolidator::new(memory.clone(), ConsolidationConfig::from_env())).spawn();
+
+    // Start background forgetting of expired and faded memories
+    Arc::new(RetentionSweeper::new(memory.clone(), RetentionConfig::from_env())).spawn();
+
+    // Initialize conscience engine, loading its rules from the database
+    let conscience = Arc::new(ConscienceEngine::with_database(pool.clone()).await?);
+
+    // Serve memory requests and conscience evaluations over MQTT when a
+    // broker is configured; each memory request acts on the namespace of its
+    // user_id
+    let mut mqtt = None;
+    if let Some(mqtt_config) = config.as_ref().and_then(|config| config.mqtt.clone()) {
+        match MqttClient::new(mqtt_config).await {
+            Ok(client) => {
+                let client = Arc::new(client);
+                if let Err(e) = serve_conscience(&client, conscience.clone()).await {
+                    error!("Failed to serve conscience evaluations over MQTT: {}", e);
+                }
+                match serve_memory(&client, memory.clone()).await {
+                    Ok(()) => mqtt = Some(client),
+                    Err(e) => error!("Failed to serve memory requests over MQTT: {}", e),
+                }
+            }
+            Err(e) => error!("Failed to connect to the MQTT broker: {}", e),
+        }
+    }
+
+    // Initialize the knowledge graph of semantic facts
+    let knowledge = Arc::new(KnowledgeGraph::new(pool.clone(), KnowledgeConfig::from_env()));
+
+    // Start background summarization of episodic memories into semantic facts
+    // and knowledge graph triples
+    match config {
+        Some(config) => {
+            let llm = Arc::new(OpenRouterClient::new(Arc::new(config)));
+            Arc::new(
+                Summarizer::new(memory.clone(), llm, SummarizerConfig::from_env())
+                    .with_knowledge_graph(knowledge.clone()),
+            )
+            .spawn();
+        }
+        None => info!("OpenRouter not configured - memory summarization disabled"),
+    }
     
     // Initialize consciousness engine
-    let consciousness = Arc::new(ConsciousnessEngine::new(memory.clone()).await?);
+    // and record its metrics history, compacting old samples in the background
+    let history = Arc::new(MetricsHistory::new(MetricsHistoryConfig::from_env()).with_database(pool));
+    history.clone().spawn_compaction();
+    let consciousness = Arc::new(ConsciousnessEngine::new(memory.clone()).await?.with_history(history));
 
     // Initialize JWT authentication
     let jwt_auth = match JwtAuth::new() {
@@ -148,9 +247,11 @@ pub async fn create_app() -> anyhow::Result<Router> {
     let state = AppState {
         conscience,
         memory,
+        knowledge,
         consciousness,
         jwt_auth,
         metrics_handle,
+        mqtt,
     };
 
     // Configure CORS from environment variables
@@ -158,13 +259,6 @@ pub async fn create_app() -> anyhow::Result<Router> {
 
     // Build the application with security layers
     let app = Router::new()
-        // Public endpoints (no authentication required)
-        .route("/", get(health))
-        .route("/login", post(login))
-        .nest("/public", Router::new()
-             .route("/health", get(health))
-             .route("/metrics", get(get_metrics))
-        )
         // Protected endpoints (authentication required)
         .route("/evaluate", post(evaluate_action))
         .route("/rules", get(get_rules))
@@ -173,7 +267,39 @@ pub async fn create_app() -> anyhow::Result<Router> {
         .route("/consciousness/config", get(consciousness::get_config))
         .route("/consciousness/toggle", post(consciousness::toggle_subsystems))
         .route("/consciousness/process", post(consciousness::process_information))
+        .route("/consciousness/history", get(consciousness::get_history))
+        .route("/memory", post(memory::store_memory))
+        .route("/memory/search", get(memory::search_memories))
+        .route("/memory/recent", get(memory::recent_memories))
+        .route("/memory/working", get(memory::working_memory))
+        .route("/memory/stats", get(memory::memory_stats))
+        .route(
+            "/memory/:id",
+            get(memory::get_memory)
+                .put(memory::update_memory)
+                .delete(memory::delete_memory),
+        )
+        .route("/episodes", get(episodes::list_episodes).post(episodes::open_episode))
+        .route("/episodes/current", get(episodes::current_episode))
+        .route("/episodes/current/records", post(episodes::append_to_episode))
+        .route("/episodes/current/close", post(episodes::close_episode))
+        .route("/episodes/:id", get(episodes::get_episode))
+        .route("/episodes/:id/replay", get(episodes::replay_episode))
+        .route(
+            "/knowledge/triples",
+            get(knowledge::query_triples).post(knowledge::assert_triple),
+        )
+        .route("/knowledge/triples/:id", get(knowledge::get_triple).delete(knowledge::retract_triple))
+        .route("/knowledge/neighborhood", get(knowledge::neighborhood))
         .layer(middleware::from_fn_with_state(state.jwt_auth.clone(), jwt_auth_middleware))
+        // Public endpoints (no authentication required); added after the
+        // auth layer so it does not apply to them
+        .route("/", get(health))
+        .route("/login", post(login))
+        .nest("/public", Router::new()
+             .route("/health", get(health))
+             .route("/metrics", get(get_metrics))
+        )
         .with_state(state)
         // Apply global security layers
         .layer(middleware::from_fn(security_headers_middleware))
@@ -226,7 +352,7 @@ fn create_cors_layer() -> CorsLayer {
 
     // Get allowed methods from environment
     let allowed_methods = env::var("CORS_ALLOWED_METHODS")
-        .unwrap_or_else(|_| "GET,POST,OPTIONS".to_string())
+        .unwrap_or_else(|_| "GET,POST,PUT,DELETE,OPTIONS".to_string())
         .split(',')
         .map(|s| s.trim())
         .filter_map(|m| match m {
@@ -241,7 +367,7 @@ fn create_cors_layer() -> CorsLayer {
         .collect::<Vec<_>>();
 
     let methods = if allowed_methods.is_empty() {
-        vec![Method::GET, Method::POST, Method::OPTIONS]
+        vec![Method::GET, Method::POST, Method::PUT, Method::DELETE, Method::OPTIONS]
     } else {
         allowed_methods
     };
@@ -275,3 +401,173 @@ fn create_cors_layer() -> CorsLayer {
         .allow_credentials(false) // Set to true if cookies/auth needed
 }
 
+
+/// State over a fresh memory system in `dir` and an in-memory database, with
+/// no background tasks
+#[cfg(test)]
+pub(crate) async fn test_state(dir: &std::path::Path) -> AppState {
+    let pool = crate::memory::store::test_pool().await;
+    let memory = Arc::new(MemorySystem::new(dir.to_path_buf()).await.unwrap().with_database(pool.clone()));
+    std::env::set_var("JWT_SECRET", "test-secret-key-that-is-long-enough-for-security");
+
+    AppState {
+        conscience: Arc::new(ConscienceEngine::new()),
+        knowledge: Arc::new(KnowledgeGraph::new(pool, KnowledgeConfig::default())),
+        consciousness: Arc::new(ConsciousnessEngine::new(memory.clone()).await.unwrap()),
+        memory,
+        jwt_auth: Arc::new(JwtAuth::new().unwrap()),
+        metrics_handle: PrometheusBuilder::new().build_recorder().handle(),
+        mqtt: None,
+    }
+}
+
+/// Claims of an authenticated `sub`
+#[cfg(test)]
+pub(crate) fn test_claims(sub: &str) -> JwtClaims {
+    JwtClaims {
+        sub: sub.to_string(),
+        iat: 0,
+        exp: i64::MAX,
+        jti: uuid::Uuid::new_v4().to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::security::validation::ContentInput;
+    use axum::extract::{Path, Query};
+    use axum::http::StatusCode;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_evaluations_are_private() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(dir.path()).await;
+
+        let action = ActionInput {
+            action: "I will be honest with Phoenix".to_string(),
+        };
+        let Json(evaluation) = evaluate_action(State(state.clone()), Extension(test_claims("alice")), Json(action))
+            .await
+            .unwrap();
+        assert!(evaluation.approved);
+        state.memory.flush().await.unwrap();
+
+        let search = |sub: &str| {
+            let query = serde_json::from_value(serde_json::json!({ "q": "honest" })).unwrap();
+            memory::search_memories(State(state.clone()), Extension(test_claims(sub)), Query(query))
+        };
+        let Json(page) = search("alice").await.unwrap();
+        assert_eq!(page.records.len(), 1);
+        assert_eq!(page.records[0].namespace, "alice");
+        let Json(page) = search("bob").await.unwrap();
+        assert!(page.records.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_processed_content_is_private() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(dir.path()).await;
+
+        let input = ContentInput {
+            content: "Important reminder about the surprise party on Saturday".to_string(),
+        };
+        let Json(processed) =
+            consciousness::process_information(State(state.clone()), Extension(test_claims("alice")), Json(input))
+                .await
+                .unwrap();
+        assert!(processed.phi_value >= 0.0);
+
+        let slots = |sub: &str| memory::working_memory(State(state.clone()), Extension(test_claims(sub)));
+        let Json(alice) = slots("alice").await;
+        assert_eq!(alice.len(), 1);
+        assert_eq!(alice[0].record.namespace, "alice");
+        let Json(bob) = slots("bob").await;
+        assert!(bob.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_episodes_are_private() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(dir.path()).await;
+
+        let open = serde_json::json!({ "title": "Planning the party", "participants": ["alice"] });
+        let (_, Json(episode)) = episodes::open_episode(
+            State(state.clone()),
+            Extension(test_claims("alice")),
+            Json(serde_json::from_value(open).unwrap()),
+        )
+        .await
+        .unwrap();
+        assert_eq!(episode.owner, "alice");
+
+        let bob = || Extension(test_claims("bob"));
+        let Json(listed) = episodes::list_episodes(State(state.clone()), bob()).await;
+        assert!(listed.is_empty());
+        assert_eq!(
+            episodes::current_episode(State(state.clone()), bob()).await.unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            episodes::get_episode(State(state.clone()), bob(), Path(episode.id.clone()))
+                .await
+                .unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+        assert_eq!(
+            episodes::replay_episode(State(state.clone()), bob(), Path(episode.id.clone()))
+                .await
+                .err(),
+            Some(StatusCode::NOT_FOUND)
+        );
+        assert_eq!(
+            episodes::append_to_episode(
+                State(state.clone()),
+                bob(),
+                Json(serde_json::from_value(serde_json::json!({ "content": "Bob was here" })).unwrap()),
+            )
+            .await
+            .err(),
+            Some(StatusCode::CONFLICT)
+        );
+        assert_eq!(
+            episodes::close_episode(State(state.clone()), bob(), None).await.unwrap_err(),
+            StatusCode::NOT_FOUND
+        );
+
+        let Json(current) = episodes::current_episode(State(state.clone()), Extension(test_claims("alice")))
+            .await
+            .unwrap();
+        assert_eq!(current.id, episode.id);
+        assert!(current.is_open());
+    }
+
+    #[tokio::test]
+    async fn test_search_counts_only_the_returned_page() {
+        let dir = TempDir::new().unwrap();
+        let state = test_state(dir.path()).await;
+        let mut ids = Vec::new();
+        for content in ["Piano recital", "Piano lesson", "Piano tuning"] {
+            let memory = NewMemory::new(content).with_namespace("alice");
+            ids.push(state.memory.store_with(MemoryLayer::Episodic, memory).await.unwrap());
+        }
+        state.memory.flush().await.unwrap();
+
+        let search = |q: &str| {
+            let query = serde_json::from_value(serde_json::json!({ "q": q, "limit": 1 })).unwrap();
+            memory::search_memories(State(state.clone()), Extension(test_claims("alice")), Query(query))
+        };
+        let Json(page) = search("piano").await.unwrap();
+        assert!(page.has_more);
+        let retrievals =
+            |ids: &[String]| ids.iter().map(|id| state.memory.retrieval_count(id)).collect::<Vec<_>>();
+        let counts = retrievals(&ids);
+        assert_eq!(counts.iter().sum::<u64>(), 1);
+        assert_eq!(state.memory.retrieval_count(&page.records[0].id), 1);
+
+        let Json(page) = search("").await.unwrap();
+        assert_eq!(page.records.len(), 1);
+        assert_eq!(retrievals(&ids), counts);
+    }
+}
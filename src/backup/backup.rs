@@ -44,7 +44,6 @@ pub struct BackupResult {
 /// Manages backup operations
 pub struct BackupManager {
     config: BackupConfig,
-    data_dir: PathBuf,
     memory_dir: PathBuf,
 }
 
@@ -64,7 +63,6 @@ impl BackupManager {
         
         Ok(Self {
             config,
-            data_dir,
             memory_dir,
         })
     }
@@ -332,7 +330,7 @@ impl BackupManager {
         }
         
         // Sort by timestamp (newest first)
-        backups.sort_by(|a, b| b.timestamp.cmp(&a.timestamp));
+        backups.sort_by_key(|b| std::cmp::Reverse(b.timestamp));
         
         Ok(backups)
     }
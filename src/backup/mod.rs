@@ -11,6 +11,7 @@
 //! - Backup verification
 //! - Disaster recovery restore
 
+#[allow(clippy::module_inception)]
 pub mod backup;
 pub mod restore;
 pub mod schedule;
@@ -22,7 +23,6 @@ pub use schedule::{BackupScheduler, ScheduleConfig};
 use std::path::PathBuf;
 use std::time::Duration;
 use serde::{Deserialize, Serialize};
-use chrono::{DateTime, Utc};
 
 /// Backup configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -60,7 +60,7 @@ impl BackupConfig {
         use std::env;
         
         let backup_dir = env::var("BACKUP_DIR")
-            .map(|v| PathBuf::from(v))
+            .map(PathBuf::from)
             .unwrap_or_else(|_| PathBuf::from("data/backups"));
         
         Self {
@@ -21,7 +21,6 @@ pub struct RestoreResult {
 /// Manages restore operations
 pub struct RestoreManager {
     config: BackupConfig,
-    data_dir: PathBuf,
     memory_dir: PathBuf,
 }
 
@@ -34,7 +33,6 @@ impl RestoreManager {
         
         Ok(Self {
             config,
-            data_dir,
             memory_dir,
         })
     }
@@ -4,13 +4,14 @@ use jamey_3::cli::ChatCLI;
 use jamey_3::config::Config;
 use jamey_3::conscience::ConscienceEngine;
 use jamey_3::db;
-use jamey_3::memory::MemorySystem;
+use jamey_3::memory::{DedupConfig, EmbeddingConfig, IdCollision, MemoryLayer, MemorySystem, SearchAllOptions};
 use jamey_3::soul::{Emotion, SoulEntity, SoulStorage};
 use jamey_3::soul::emotion::EmotionType;
-use std::path::PathBuf;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
 use std::sync::Arc;
 use tracing::info;
-use tracing_subscriber;
 
 #[derive(Parser)]
 #[command(name = "jamey", version = "3.0.0")]
@@ -28,6 +29,55 @@ enum Commands {
     /// Soul knowledge base commands
     #[command(subcommand)]
     Soul(SoulCommands),
+
+    /// Memory system search and maintenance commands
+    #[command(subcommand)]
+    Memory(MemoryCommands),
+}
+
+#[derive(Subcommand)]
+enum MemoryCommands {
+    /// Search memories in every namespace, e.g. 'recital tag:family -layer:working'
+    Search {
+        /// Query: words, "quoted phrases", layer:, tag:, entity:, source:,
+        /// emotion:, before:/after: dates, importance:>0.5, and -negation
+        #[arg(allow_hyphen_values = true)]
+        query: String,
+        /// Maximum number of results
+        #[arg(short, long, default_value_t = 10)]
+        limit: usize,
+        /// Print how each result's score was computed
+        #[arg(long)]
+        explain: bool,
+    },
+
+    /// Compare the search indices against the database
+    Check,
+
+    /// Regenerate search indices from the database
+    Rebuild {
+        /// Layer to rebuild (short_term, long_term, working, episodic, semantic);
+        /// all layers if not provided
+        layer: Option<MemoryLayer>,
+    },
+
+    /// Export memories as JSON Lines
+    Export {
+        /// Layer to export; all layers if not provided
+        layer: Option<MemoryLayer>,
+        /// Output file (defaults to stdout)
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+
+    /// Import memories from a JSON Lines export
+    Import {
+        /// File written by `memory export`
+        input: PathBuf,
+        /// What to do with IDs that are already stored (skip, overwrite, reid)
+        #[arg(long, default_value = "skip")]
+        on_conflict: IdCollision,
+    },
 }
 
 #[derive(Subcommand)]
@@ -80,6 +130,9 @@ async fn main() -> Result<()> {
         Commands::Soul(soul_cmd) => {
             handle_soul_command(soul_cmd).await?;
         }
+        Commands::Memory(memory_cmd) => {
+            handle_memory_command(memory_cmd).await?;
+        }
     }
     
     Ok(())
@@ -107,14 +160,22 @@ async fn run_chat() -> Result<()> {
     };
 
     // Initialize database (optional, but good to have)
-    if let Err(e) = jamey_3::db::init_db().await {
-        tracing::warn!("Database initialization failed: {}. Continuing without database.", e);
-    } else {
-        info!("Database initialized");
-    }
+    let pool = match jamey_3::db::init_db().await {
+        Ok(pool) => {
+            info!("Database initialized");
+            Some(pool)
+        }
+        Err(e) => {
+            tracing::warn!("Database initialization failed: {}. Continuing without database.", e);
+            None
+        }
+    };
 
-    // Initialize conscience engine
-    let conscience = Arc::new(ConscienceEngine::new());
+    // Initialize conscience engine, with stored rules when the database is available
+    let conscience = match &pool {
+        Some(pool) => Arc::new(ConscienceEngine::with_database(pool.clone()).await?),
+        None => Arc::new(ConscienceEngine::new()),
+    };
     info!("Conscience Engine initialized");
 
     // Initialize memory system
@@ -122,7 +183,13 @@ async fn run_chat() -> Result<()> {
     let memory = match MemorySystem::new(data_dir).await {
         Ok(mem) => {
             info!("Memory System initialized");
-            Arc::new(mem)
+            let mem = mem
+                .with_embedder(EmbeddingConfig::from_env().build())
+                .with_dedup(DedupConfig::from_env());
+            Arc::new(match pool {
+                Some(pool) => mem.with_database(pool),
+                None => mem,
+            })
         }
         Err(e) => {
             eprintln!("⚠️  Warning: Memory system initialization failed: {}", e);
@@ -132,6 +199,8 @@ async fn run_chat() -> Result<()> {
         }
     };
 
+    memory.spawn_commit_task();
+
     // Create and run CLI
     let mut cli = ChatCLI::new(config, conscience, memory);
     cli.run().await?;
@@ -165,9 +234,162 @@ async fn handle_soul_command(cmd: SoulCommands) -> Result<()> {
     Ok(())
 }
 
+async fn handle_memory_command(cmd: MemoryCommands) -> Result<()> {
+    let pool = db::init_db().await?;
+    let data_dir = PathBuf::from("data/memory");
+
+    match cmd {
+        MemoryCommands::Search { query, limit, explain } => {
+            let memory = MemorySystem::new(data_dir).await?.with_database(pool);
+            memory_search(&memory, &query, limit, explain).await?;
+        }
+        MemoryCommands::Check => {
+            let memory = MemorySystem::new(data_dir).await?.with_database(pool);
+            memory_check(&memory).await?;
+        }
+        MemoryCommands::Rebuild { layer } => {
+            let layers = match layer {
+                Some(layer) => vec![layer],
+                None => MemoryLayer::ALL.to_vec(),
+            };
+            for layer in MemorySystem::quarantine_unreadable_indices(&data_dir)? {
+                println!("⚠️  {} index was unreadable and has been moved aside", layer.as_str());
+            }
+            let memory = MemorySystem::new(data_dir).await?.with_database(pool);
+            memory_rebuild(&memory, &layers).await?;
+        }
+        MemoryCommands::Export { layer, output } => {
+            let memory = MemorySystem::new(data_dir).await?.with_database(pool);
+            let layers = match layer {
+                Some(layer) => vec![layer],
+                None => MemoryLayer::ALL.to_vec(),
+            };
+            memory_export(&memory, &layers, output).await?;
+        }
+        MemoryCommands::Import { input, on_conflict } => {
+            let memory = MemorySystem::new(data_dir)
+                .await?
+                .with_embedder(EmbeddingConfig::from_env().build())
+                .with_database(pool);
+            memory_import(&memory, &input, on_conflict).await?;
+        }
+    }
+
+    Ok(())
+}
+
+async fn memory_search(memory: &MemorySystem, query: &str, limit: usize, explain: bool) -> Result<()> {
+    let mut options = SearchAllOptions::new(limit).with_highlights();
+    if explain {
+        options = options.with_explanations();
+    }
+    let results = memory.search_all(query, &options).await?;
+
+    println!("\n🔎 {} memories match '{}'\n", results.len(), query);
+    for record in &results {
+        let text = match &record.snippet {
+            Some(snippet) => snippet.marked("\x1b[1m", "\x1b[0m"),
+            None => record.content.clone(),
+        };
+        println!("  • [{}] {}", record.layer.as_str(), text);
+        println!("    {} | {} | score {:.2}",
+            record.id,
+            record.timestamp.format("%Y-%m-%d %H:%M:%S"),
+            record.score.unwrap_or(0.0)
+        );
+        if let Some(explanation) = &record.explanation {
+            println!("{}", serde_json::to_string_pretty(explanation)?);
+        }
+        println!();
+    }
+
+    Ok(())
+}
+
+async fn memory_check(memory: &MemorySystem) -> Result<()> {
+    let report = memory.check_consistency().await?;
+
+    println!("\n🔍 Memory consistency check\n");
+    for layer in &report.layers {
+        let status = if layer.is_consistent() { "✅" } else { "❌" };
+        println!("  {} {:10} database: {:5} | index: {:5}",
+            status,
+            layer.layer.as_str(),
+            layer.database_records,
+            layer.indexed_records
+        );
+        if !layer.missing_from_index.is_empty() {
+            println!("     missing from index: {}", layer.missing_from_index.join(", "));
+        }
+        if !layer.missing_from_database.is_empty() {
+            println!("     missing from database: {}", layer.missing_from_database.join(", "));
+        }
+        if !layer.mismatched.is_empty() {
+            println!("     mismatched: {}", layer.mismatched.join(", "));
+        }
+    }
+
+    if report.is_consistent() {
+        println!("\n✅ Indices match the database\n");
+    } else {
+        println!("\n⚠️  Run 'jamey memory rebuild' to regenerate the indices from the database\n");
+    }
+
+    Ok(())
+}
+
+async fn memory_rebuild(memory: &MemorySystem, layers: &[MemoryLayer]) -> Result<()> {
+    println!("\n🔧 Rebuilding memory indices from the database...");
+
+    for layer in layers {
+        let count = memory.rebuild_index(*layer).await?;
+        println!("   {} {} records", layer.as_str(), count);
+    }
+
+    println!("✅ Done\n");
+
+    Ok(())
+}
+
+async fn memory_export(memory: &MemorySystem, layers: &[MemoryLayer], output: Option<PathBuf>) -> Result<()> {
+    let mut writer: Box<dyn Write> = match &output {
+        Some(path) => Box::new(BufWriter::new(File::create(path)?)),
+        None => Box::new(BufWriter::new(std::io::stdout().lock())),
+    };
+
+    // Progress goes to stderr so stdout stays a clean export
+    let mut total = 0;
+    for layer in layers {
+        let count = memory.export(*layer, &mut writer).await?;
+        eprintln!("   {} {} records", layer.as_str(), count);
+        total += count;
+    }
+    writer.flush()?;
+
+    match output {
+        Some(path) => eprintln!("✅ Exported {} memories to {}", total, path.display()),
+        None => eprintln!("✅ Exported {} memories", total),
+    }
+
+    Ok(())
+}
+
+async fn memory_import(memory: &MemorySystem, input: &Path, on_conflict: IdCollision) -> Result<()> {
+    let reader = BufReader::new(File::open(input)?);
+    let report = memory.import(reader, on_conflict).await?;
+
+    println!("\n📥 Imported memories from {}", input.display());
+    println!("   Imported: {}", report.imported);
+    println!("   Skipped: {}", report.skipped);
+    println!("   Overwritten: {}", report.overwritten);
+    println!("   Re-identified: {}\n", report.reidentified);
+
+    Ok(())
+}
+
 async fn soul_upsert(storage: &SoulStorage, entity_name: &str, trust: f32) -> Result<()> {
     // Validate trust score
-    if trust < 0.0 || trust > 1.0 {
+    if !(0.0..=1.0).contains(&trust) {
         anyhow::bail!("Trust score must be between 0.0 and 1.0");
     }
     
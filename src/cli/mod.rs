@@ -1,11 +1,14 @@
 use crate::config::Config;
 use crate::conscience::ConscienceEngine;
 use crate::llm::OpenRouterClient;
-use crate::memory::{MemoryLayer, MemorySystem};
+use crate::memory::{MemorySystem, NewMemory, SearchAllOptions, SHARED_NAMESPACE};
 use std::io::{self, Write};
 use std::sync::Arc;
 
 /// Interactive CLI chat interface for Jamey 3.0
+///
+/// Every session is recorded as an episode: messages are appended to it as
+/// they are exchanged, and it is closed and summarized on exit.
 pub struct ChatCLI {
     llm_client: Arc<OpenRouterClient>,
     conscience: Arc<ConscienceEngine>,
@@ -38,6 +41,18 @@ impl ChatCLI {
         println!("Type your message and press Enter.");
         println!("Commands: /help, /exit, /clear, /rules, /memory\n");
 
+        if let Err(e) = self
+            .memory
+            .open_episode(
+                SHARED_NAMESPACE,
+                format!("Chat session {}", chrono::Utc::now().format("%Y-%m-%d %H:%M UTC")),
+                ["User", "Jamey"],
+            )
+            .await
+        {
+            tracing::warn!("Failed to open chat episode: {}", e);
+        }
+
         loop {
             print!("You: ");
             io::stdout().flush()?;
@@ -74,10 +89,36 @@ impl ChatCLI {
             }
         }
 
+        self.close_episode().await;
+
         println!("\n👋 Goodbye! Jamey 3.0 signing off.\n");
         Ok(())
     }
 
+    /// Close the session's episode and summarize it
+    async fn close_episode(&self) {
+        let episode = match self.memory.close_episode(SHARED_NAMESPACE, None).await {
+            Ok(Some(episode)) => episode,
+            Ok(None) => return,
+            Err(e) => {
+                tracing::warn!("Failed to close chat episode: {}", e);
+                return;
+            }
+        };
+        if episode.records.is_empty() {
+            return;
+        }
+
+        println!("\n📝 Summarizing this session...");
+        if let Err(e) = self
+            .memory
+            .summarize_episode(&episode.id, self.llm_client.as_ref())
+            .await
+        {
+            tracing::warn!("Failed to summarize chat episode: {}", e);
+        }
+    }
+
     /// Handle CLI commands
     async fn handle_command(&mut self, cmd: &str) -> anyhow::Result<bool> {
         let parts: Vec<&str> = cmd.split_whitespace().collect();
@@ -90,7 +131,10 @@ impl ChatCLI {
                 println!("  /exit, /quit, /q   - Exit the chat");
                 println!("  /clear             - Clear conversation history");
                 println!("  /rules             - Show all moral rules");
-                println!("  /memory            - Show recent memories");
+                println!("  /memory            - Show this session's latest memories");
+                println!("  /memory <query>    - Search memories, highlighting matches (e.g. recital tag:family)");
+                println!("  /memory --explain <query> - Also explain each result's score");
+                println!("  /search <query>    - Same as /memory <query>");
                 println!("  /conscience <text>  - Evaluate text with conscience engine");
                 println!();
             }
@@ -110,14 +154,24 @@ impl ChatCLI {
                 }
                 println!();
             }
+            "/memory" | "/search" if parts.len() > 1 => {
+                let explain = parts[1] == "--explain";
+                let query = parts[if explain { 2 } else { 1 }..].join(" ");
+                self.search_memories(&query, explain).await;
+            }
             "/memory" => {
-                println!("\n🧠 Recent Memories (Short-term):");
-                match self.memory.search(MemoryLayer::ShortTerm, "*", 5).await {
+                println!("\n🧠 Recent Memories (this session):");
+                let Some(episode) = self.memory.current_episode(SHARED_NAMESPACE) else {
+                    println!("  No session is being recorded.\n");
+                    return Ok(false);
+                };
+                match self.memory.replay_episode(&episode.id).await {
                     Ok(memories) => {
+                        let memories = memories.unwrap_or_default();
                         if memories.is_empty() {
                             println!("  No memories found.\n");
                         } else {
-                            for mem in memories.iter().take(5) {
+                            for mem in memories.iter().rev().take(5) {
                                 println!("  • {}", mem.content);
                                 println!("    [{}]\n", mem.timestamp.format("%Y-%m-%d %H:%M:%S"));
                             }
@@ -128,15 +182,33 @@ impl ChatCLI {
                     }
                 }
             }
+            "/search" => {
+                println!("\n⚠️  Usage: /search [--explain] <query>\n");
+            }
             "/conscience" => {
                 if parts.len() < 2 {
                     println!("\n⚠️  Usage: /conscience <text to evaluate>\n");
                 } else {
                     let text = parts[1..].join(" ");
-                    let score = self.conscience.evaluate(&text);
+                    let evaluation = self.conscience.evaluate_detailed(&text);
                     println!("\n⚖️  Conscience Evaluation:");
                     println!("  Text: {}", text);
-                    println!("  Score: {:.2}\n", score);
+                    println!(
+                        "  Score: {:.2} (normalized {:.2}, {})",
+                        evaluation.score,
+                        evaluation.normalized_score,
+                        if evaluation.approved { "approved" } else { "rejected" }
+                    );
+                    for contribution in evaluation.contributions.iter().filter(|c| !c.matched_terms.is_empty()) {
+                        println!(
+                            "    {:+.2}  {} (weight {:.1}; matched: {})",
+                            contribution.contribution,
+                            contribution.rule,
+                            contribution.weight,
+                            contribution.matched_terms.join(", ")
+                        );
+                    }
+                    println!("  {}\n", evaluation.explanation);
                 }
             }
             _ => {
@@ -147,19 +219,60 @@ impl ChatCLI {
         Ok(false)
     }
 
+    /// Search every layer and print the best matches with the matching
+    /// words in bold, and the explanation of their scores if asked
+    async fn search_memories(&self, query: &str, explain: bool) {
+        let mut options = SearchAllOptions::new(5).with_highlights();
+        if explain {
+            options = options.with_explanations();
+        }
+        let results = match self.memory.search_all(query, &options).await {
+            Ok(results) => results,
+            Err(e) => {
+                eprintln!("\n  Error: {}\n", e);
+                return;
+            }
+        };
+        if results.is_empty() {
+            println!("\n🔎 No memories found.\n");
+            return;
+        }
+
+        println!("\n🔎 Memories matching '{}':", query);
+        for mem in &results {
+            let text = match &mem.snippet {
+                Some(snippet) => snippet.marked("\x1b[1m", "\x1b[0m"),
+                None => mem.content.clone(),
+            };
+            println!("  • {}", text);
+            println!("    [{} | {} | score {:.2}]", mem.layer.as_str(), mem.timestamp.format("%Y-%m-%d %H:%M:%S"), mem.score.unwrap_or(0.0));
+            if let Some(explanation) = &mem.explanation {
+                let pretty = serde_json::to_string_pretty(explanation).unwrap_or_default();
+                for line in pretty.lines() {
+                    println!("    {}", line);
+                }
+            }
+            println!();
+        }
+    }
+
     /// Process a user message and get response from Jamey
     async fn process_message(&mut self, user_message: &str) -> anyhow::Result<()> {
         // Evaluate with conscience engine
         let conscience_score = self.conscience.evaluate(user_message);
         
         // Display conscience evaluation (only if score is meaningful)
-        if conscience_score > 0.0 {
+        if conscience_score != 0.0 {
             println!("\n⚖️  Conscience Evaluation: {:.2}\n", conscience_score);
         }
         
-        // Store in memory
+        // Record in the session's episode
         let memory_content = format!("User: {} | Conscience Score: {:.2}", user_message, conscience_score);
-        if let Err(e) = self.memory.store(MemoryLayer::ShortTerm, memory_content.clone()).await {
+        if let Err(e) = self
+            .memory
+            .append_to_episode(NewMemory::new(memory_content.clone()).with_source("cli"))
+            .await
+        {
             tracing::warn!("Failed to store memory: {}", e);
         }
 
@@ -185,8 +298,12 @@ impl ChatCLI {
         self.conversation_history.push(("user".to_string(), user_message.to_string()));
         self.conversation_history.push(("assistant".to_string(), response.clone()));
 
-        // Store Jamey's response in memory
-        if let Err(e) = self.memory.store(MemoryLayer::ShortTerm, format!("Jamey: {}", response)).await {
+        // Record Jamey's response in the session's episode
+        if let Err(e) = self
+            .memory
+            .append_to_episode(NewMemory::new(format!("Jamey: {}", response)).with_source("cli"))
+            .await
+        {
             tracing::warn!("Failed to store memory: {}", e);
         }
 
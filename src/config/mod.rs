@@ -1,8 +1,18 @@
 use std::env;
+use std::str::FromStr;
 use std::time::Duration;
 use crate::mqtt::MqttConfig;
 use serde::{Deserialize, Serialize};
 
+/// Parse the environment variable `name`, or return `default` if it is unset
+/// or does not parse
+pub fn env_or<T: FromStr>(name: &str, default: T) -> T {
+    env::var(name)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default)
+}
+
 /// Soul Knowledge Base configuration
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct SoulConfig {
@@ -1,56 +1,251 @@
+mod store;
+
+pub use store::RuleStore;
+
+use anyhow::Result;
 use dashmap::DashMap;
 use serde::{Deserialize, Serialize};
+use sqlx::SqlitePool;
+use std::str::FromStr;
 use std::sync::Arc;
 
+/// Whether matching a rule counts for or against an action
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RulePolarity {
+    /// Something to do, e.g. being honest
+    #[default]
+    Virtue,
+    /// Something not to do, e.g. causing harm
+    Prohibition,
+}
+
+impl RulePolarity {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            RulePolarity::Virtue => "virtue",
+            RulePolarity::Prohibition => "prohibition",
+        }
+    }
+
+    /// 1.0 for virtues, -1.0 for prohibitions
+    pub fn sign(&self) -> f32 {
+        match self {
+            RulePolarity::Virtue => 1.0,
+            RulePolarity::Prohibition => -1.0,
+        }
+    }
+}
+
+impl FromStr for RulePolarity {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "virtue" => Ok(RulePolarity::Virtue),
+            "prohibition" => Ok(RulePolarity::Prohibition),
+            _ => Err(anyhow::anyhow!("Unknown rule polarity: {}", s)),
+        }
+    }
+}
+
 /// A moral rule with a weight and description
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MoralRule {
     pub name: String,
     pub description: String,
     pub weight: f32,
+    #[serde(default)]
+    pub polarity: RulePolarity,
+    /// Words or phrases that make the rule apply. A rule without any applies
+    /// in proportion to the words of its description found in the action.
+    #[serde(default)]
+    pub triggers: Vec<String>,
+    /// Whether matching the rule rejects the action, whatever its score
+    #[serde(default)]
+    pub veto: bool,
+}
+
+impl MoralRule {
+    /// A virtue matched by the words of its description
+    pub fn new(name: impl Into<String>, description: impl Into<String>, weight: f32) -> Self {
+        Self {
+            name: name.into(),
+            description: description.into(),
+            weight,
+            polarity: RulePolarity::Virtue,
+            triggers: Vec::new(),
+            veto: false,
+        }
+    }
+
+    pub fn with_polarity(mut self, polarity: RulePolarity) -> Self {
+        self.polarity = polarity;
+        self
+    }
+
+    pub fn with_triggers<S: Into<String>>(mut self, triggers: impl IntoIterator<Item = S>) -> Self {
+        self.triggers = triggers.into_iter().map(Into::into).collect();
+        self
+    }
+
+    pub fn with_veto(mut self, veto: bool) -> Self {
+        self.veto = veto;
+        self
+    }
+
+    /// The terms of the rule found in the lowercased words of an action,
+    /// and the share of its terms they are
+    fn matches(&self, words: &str) -> (Vec<String>, f32) {
+        if self.triggers.is_empty() {
+            let description = self.description.to_lowercase();
+            let keywords: Vec<&str> = description.split_whitespace().collect();
+            let matched: Vec<String> = keywords
+                .iter()
+                .filter(|keyword| contains_phrase(words, keyword))
+                .map(|keyword| keyword.to_string())
+                .collect();
+            let share = if keywords.is_empty() {
+                0.0
+            } else {
+                matched.len() as f32 / keywords.len() as f32
+            };
+            return (matched, share);
+        }
+
+        // Any trigger applies the rule in full
+        let matched: Vec<String> = self
+            .triggers
+            .iter()
+            .filter(|trigger| contains_phrase(words, &trigger.to_lowercase()))
+            .cloned()
+            .collect();
+        let share = if matched.is_empty() { 0.0 } else { 1.0 };
+        (matched, share)
+    }
+}
+
+/// The lowercase words of a text, separated and surrounded by single spaces
+fn words(text: &str) -> String {
+    let words: Vec<&str> = text
+        .split(|c: char| !c.is_alphanumeric() && c != '\'')
+        .filter(|word| !word.is_empty())
+        .collect();
+    format!(" {} ", words.join(" ").to_lowercase())
+}
+
+/// Whether `words` (see [`words`]) has `phrase` as whole words
+fn contains_phrase(words: &str, phrase: &str) -> bool {
+    let phrase = phrase.split_whitespace().collect::<Vec<_>>().join(" ");
+    !phrase.is_empty() && words.contains(&format!(" {} ", phrase))
+}
+
+/// What one rule contributed to an evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleContribution {
+    pub rule: String,
+    pub weight: f32,
+    pub polarity: RulePolarity,
+    /// Triggers of the rule, or words of its description, found in the action
+    pub matched_terms: Vec<String>,
+    /// Weight times the share of the rule's terms that matched; negative for
+    /// prohibitions
+    pub contribution: f32,
+}
+
+/// An evaluation broken down by rule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Evaluation {
+    pub action: String,
+    /// Sum of the contributions
+    pub score: f32,
+    /// Score as a share of the total weight of all rules (-1.0 to 1.0)
+    pub normalized_score: f32,
+    /// Whether the score is not negative and no vetoing rule matched
+    pub approved: bool,
+    /// Vetoing rules that matched the action
+    pub vetoed_by: Vec<String>,
+    /// Every rule's contribution, largest in magnitude first
+    pub contributions: Vec<RuleContribution>,
+    pub explanation: String,
+}
+
+impl Evaluation {
+    /// Names of the rules that matched the action, largest contribution in
+    /// magnitude first
+    pub fn applied_rules(&self) -> Vec<String> {
+        self.contributions
+            .iter()
+            .filter(|c| !c.matched_terms.is_empty())
+            .map(|c| c.rule.clone())
+            .collect()
+    }
 }
 
 /// Conscience Engine that evaluates actions against moral rules
 #[derive(Clone)]
 pub struct ConscienceEngine {
     rules: Arc<DashMap<String, MoralRule>>,
+    store: Option<RuleStore>,
 }
 
 impl ConscienceEngine {
-    /// Create a new Conscience Engine with default rules
+    /// Create a new Conscience Engine with default rules, kept in memory only
     pub fn new() -> Self {
         let rules = Arc::new(DashMap::new());
-        
-        // Add default rules
-        rules.insert(
-            "no-harm".to_string(),
-            MoralRule {
-                name: "no-harm".to_string(),
-                description: "Do not cause physical or emotional harm".to_string(),
-                weight: 10.0,
-            },
-        );
-        
-        rules.insert(
-            "truth".to_string(),
-            MoralRule {
-                name: "truth".to_string(),
-                description: "Be honest and truthful".to_string(),
-                weight: 8.0,
-            },
-        );
+        for rule in Self::default_rules() {
+            rules.insert(rule.name.clone(), rule);
+        }
+
+        Self { rules, store: None }
+    }
+
+    /// Create a Conscience Engine whose rules are kept in the database.
+    /// Stored rules are loaded; the default rules are seeded only when there
+    /// are none yet, and stored defaults from before polarity and triggers
+    /// existed are brought up to date.
+    pub async fn with_database(pool: SqlitePool) -> Result<Self> {
+        let store = RuleStore::new(pool);
+        store.seed(&Self::default_rules()).await?;
+        let stored = store.load().await?;
+
+        let rules = Arc::new(DashMap::new());
+        for rule in stored {
+            rules.insert(rule.name.clone(), rule);
+        }
+
+        Ok(Self {
+            rules,
+            store: Some(store),
+        })
+    }
 
-        Self { rules }
+    /// The rules a new engine starts with
+    fn default_rules() -> Vec<MoralRule> {
+        vec![
+            MoralRule::new("no-harm", "Do not cause physical or emotional harm", 10.0)
+                .with_polarity(RulePolarity::Prohibition)
+                .with_triggers(["harm", "harmful", "hurt", "injure", "kill", "attack", "abuse"]),
+            MoralRule::new("truth", "Be honest and truthful", 8.0)
+                .with_triggers(["honest", "honesty", "truth", "truthful", "transparent"]),
+        ]
     }
 
-    /// Add a new moral rule
-    pub fn add_rule(&self, rule: MoralRule) {
+    /// Add a new moral rule, replacing any rule with the same name
+    pub async fn add_rule(&self, rule: MoralRule) -> Result<()> {
+        if let Some(store) = &self.store {
+            store.upsert(&rule).await?;
+        }
         self.rules.insert(rule.name.clone(), rule);
+        Ok(())
     }
 
     /// Remove a moral rule
-    pub fn remove_rule(&self, name: &str) -> Option<MoralRule> {
-        self.rules.remove(name).map(|(_, v)| v)
+    pub async fn remove_rule(&self, name: &str) -> Result<Option<MoralRule>> {
+        if let Some(store) = &self.store {
+            store.delete(name).await?;
+        }
+        Ok(self.rules.remove(name).map(|(_, v)| v))
     }
 
     /// Get all rules
@@ -59,32 +254,95 @@ impl ConscienceEngine {
     }
 
     /// Evaluate an action against all moral rules
-    /// Returns a score where higher is more moral
+    /// Returns the normalized score (-1.0 to 1.0), where higher is more moral
     pub fn evaluate(&self, action: &str) -> f32 {
-        let action_lower = action.to_lowercase();
-        let mut score = 0.0;
+        self.evaluate_detailed(action).normalized_score
+    }
+
+    /// Evaluate an action against all moral rules, with what each rule
+    /// contributed and why
+    pub fn evaluate_detailed(&self, action: &str) -> Evaluation {
+        let words = words(action);
+        let mut contributions = Vec::new();
+        let mut vetoed_by = Vec::new();
+        let mut total_weight = 0.0;
 
         for rule in self.rules.iter() {
             // Simple keyword matching - can be enhanced with NLP
-            let rule_lower = rule.description.to_lowercase();
-            let keywords: Vec<&str> = rule_lower.split_whitespace().collect();
-            
-            let matches: usize = keywords
-                .iter()
-                .filter(|keyword| action_lower.contains(*keyword))
-                .count();
-
-            if matches > 0 {
-                // Score based on how many keywords match and rule weight
-                let match_ratio = matches as f32 / keywords.len() as f32;
-                score += rule.weight * match_ratio;
+            let (matched_terms, share) = rule.matches(&words);
+            if rule.veto && !matched_terms.is_empty() {
+                vetoed_by.push(rule.name.clone());
             }
+
+            total_weight += rule.weight.abs();
+            contributions.push(RuleContribution {
+                rule: rule.name.clone(),
+                weight: rule.weight,
+                polarity: rule.polarity,
+                matched_terms,
+                contribution: rule.polarity.sign() * rule.weight * share,
+            });
         }
 
-        score
+        contributions.sort_by(|a, b| {
+            b.contribution
+                .abs()
+                .total_cmp(&a.contribution.abs())
+                .then_with(|| a.rule.cmp(&b.rule))
+        });
+        vetoed_by.sort();
+
+        let score: f32 = contributions.iter().map(|c| c.contribution).sum();
+        let normalized_score = if total_weight > 0.0 {
+            (score / total_weight).clamp(-1.0, 1.0)
+        } else {
+            0.0
+        };
+        let explanation = explain(&contributions, normalized_score, &vetoed_by);
+
+        Evaluation {
+            action: action.to_string(),
+            score,
+            normalized_score,
+            approved: vetoed_by.is_empty() && score >= 0.0,
+            vetoed_by,
+            contributions,
+            explanation,
+        }
     }
 }
 
+/// A sentence naming the rules that matched and what they added
+fn explain(contributions: &[RuleContribution], normalized_score: f32, vetoed_by: &[String]) -> String {
+    let applied: Vec<String> = contributions
+        .iter()
+        .filter(|c| !c.matched_terms.is_empty())
+        .map(|c| {
+            format!(
+                "{} ({:+.2} from \"{}\")",
+                c.rule,
+                c.contribution,
+                c.matched_terms.join("\", \"")
+            )
+        })
+        .collect();
+
+    if applied.is_empty() {
+        return format!("No rule matched the action (0 of {} rules)", contributions.len());
+    }
+    let explanation = format!(
+        "Matched {} of {} rules: {}. Normalized score: {:.2}",
+        applied.len(),
+        contributions.len(),
+        applied.join(", "),
+        normalized_score
+    );
+    if vetoed_by.is_empty() {
+        return explanation;
+    }
+    format!("{}. Vetoed by {}", explanation, vetoed_by.join(", "))
+}
+
 impl Default for ConscienceEngine {
     fn default() -> Self {
         Self::new()
@@ -94,6 +352,7 @@ impl Default for ConscienceEngine {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::memory::store::test_pool;
 
     #[test]
     fn test_default_rules() {
@@ -108,5 +367,115 @@ mod tests {
         let score = engine.evaluate("I will help someone in need");
         assert!(score >= 0.0);
     }
+
+    #[test]
+    fn test_evaluate_detailed() {
+        let engine = ConscienceEngine::new();
+        let evaluation = engine.evaluate_detailed("I want to be honest with you");
+
+        assert_eq!(evaluation.applied_rules(), ["truth"]);
+        let truth = &evaluation.contributions[0];
+        assert_eq!(truth.matched_terms, ["honest"]);
+        assert_eq!(truth.contribution, 8.0);
+        assert_eq!(evaluation.contributions[1].contribution, 0.0);
+        assert_eq!(evaluation.score, 8.0);
+        assert!((evaluation.normalized_score - 8.0 / 18.0).abs() < 1e-6);
+        assert_eq!(evaluation.normalized_score, engine.evaluate("I want to be honest with you"));
+        assert!(evaluation.approved);
+        assert!(evaluation.explanation.contains("truth (+8.00 from \"honest\")"));
+    }
+
+    #[tokio::test]
+    async fn test_prohibitions_and_vetoes() {
+        let engine = ConscienceEngine::new();
+        let neutral = engine.evaluate_detailed("I will water the plants");
+        let harmful = engine.evaluate_detailed("I will cause harm and lie");
+        assert_eq!(neutral.score, 0.0);
+        assert!(harmful.score < neutral.score);
+        assert!((harmful.normalized_score + 10.0 / 18.0).abs() < 1e-6);
+        assert!(!harmful.approved && neutral.approved);
+        // Triggers are whole words
+        assert!(engine.evaluate_detailed("The pharmacy was harmless").applied_rules().is_empty());
+
+        // A virtue outweighing a vetoing prohibition does not save the action
+        engine
+            .add_rule(
+                MoralRule::new("no-weapons", "Never build weapons", 1.0)
+                    .with_polarity(RulePolarity::Prohibition)
+                    .with_triggers(["build a bomb"])
+                    .with_veto(true),
+            )
+            .await
+            .unwrap();
+        let vetoed = engine.evaluate_detailed("To be honest, I want to build a bomb");
+        assert!(vetoed.score > 0.0);
+        assert!(!vetoed.approved);
+        assert_eq!(vetoed.vetoed_by, ["no-weapons"]);
+        assert!(vetoed.explanation.ends_with("Vetoed by no-weapons"));
+
+        // Rules without triggers still match the words of their description
+        let legacy = MoralRule::new("kindness", "Show compassion and empathy", 4.0);
+        assert_eq!(legacy.matches(&words("Empathy matters")), (vec!["empathy".to_string()], 0.25));
+    }
+
+    #[tokio::test]
+    async fn test_rules_persist() {
+        let pool = test_pool().await;
+        // Engines starting together seed the defaults once
+        let (engine, other) = tokio::join!(
+            ConscienceEngine::with_database(pool.clone()),
+            ConscienceEngine::with_database(pool.clone())
+        );
+        let engine = engine.unwrap();
+        assert_eq!(engine.get_rules().len(), 2);
+        assert_eq!(other.unwrap().get_rules().len(), 2);
+
+        engine
+            .add_rule(
+                MoralRule::new("kindness", "Show compassion and empathy", 6.0)
+                    .with_triggers(["kind", "compassion"]),
+            )
+            .await
+            .unwrap();
+        engine.remove_rule("truth").await.unwrap();
+
+        // A restarted engine sees the changes and does not re-seed "truth"
+        let restarted = ConscienceEngine::with_database(pool).await.unwrap();
+        let mut rules = restarted.get_rules();
+        rules.sort_by(|a, b| a.name.cmp(&b.name));
+        let names: Vec<&str> = rules.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, ["kindness", "no-harm"]);
+        assert_eq!(rules[0].triggers, ["kind", "compassion"]);
+        assert_eq!(rules[1].polarity, RulePolarity::Prohibition);
+    }
+
+    #[tokio::test]
+    async fn test_legacy_default_rules_are_upgraded() {
+        let pool = test_pool().await;
+        // Rows as seeded before rules had a polarity and triggers
+        for (name, description, weight) in [
+            ("no-harm", "Do not cause physical or emotional harm", 10.0),
+            ("truth", "Be honest", 8.0),
+        ] {
+            sqlx::query("INSERT INTO moral_rules (name, description, weight) VALUES (?, ?, ?)")
+                .bind(name)
+                .bind(description)
+                .bind(weight)
+                .execute(&pool)
+                .await
+                .unwrap();
+        }
+
+        let engine = ConscienceEngine::with_database(pool).await.unwrap();
+        let mut rules = engine.get_rules();
+        rules.sort_by(|a, b| a.name.cmp(&b.name));
+        let defaults = ConscienceEngine::default_rules();
+
+        // The untouched default is upgraded, the edited one is left alone
+        assert_eq!(rules[0].polarity, RulePolarity::Prohibition);
+        assert_eq!(rules[0].triggers, defaults[0].triggers);
+        assert_eq!(rules[1].description, "Be honest");
+        assert!(rules[1].triggers.is_empty());
+    }
 }
 
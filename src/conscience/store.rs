@@ -0,0 +1,125 @@
+use super::MoralRule;
+use anyhow::Result;
+use sqlx::{Row, SqlitePool};
+
+/// Moral rules kept in the `moral_rules` table
+#[derive(Clone)]
+pub struct RuleStore {
+    pool: SqlitePool,
+}
+
+impl RuleStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// All stored rules, by name
+    pub async fn load(&self) -> Result<Vec<MoralRule>> {
+        let rows = sqlx::query(
+            "SELECT name, description, weight, polarity, triggers, veto FROM moral_rules ORDER BY name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.into_iter()
+            .map(|row| {
+                Ok(MoralRule {
+                    name: row.get("name"),
+                    description: row.get("description"),
+                    weight: row.get::<f64, _>("weight") as f32,
+                    polarity: row.get::<String, _>("polarity").parse()?,
+                    triggers: serde_json::from_str(&row.get::<String, _>("triggers"))?,
+                    veto: row.get("veto"),
+                })
+            })
+            .collect()
+    }
+
+    /// Insert `rules` if the table is empty, in a single transaction
+    ///
+    /// Rules that appear in the meantime are left alone, so a concurrent
+    /// seeder or writer never has its rules overwritten. Stored copies of
+    /// `rules` that predate polarity and triggers, i.e. with the same
+    /// description and weight and the column defaults otherwise, get the
+    /// polarity, triggers and veto flag of the rule.
+    pub async fn seed(&self, rules: &[MoralRule]) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+        let count: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM moral_rules")
+            .fetch_one(&mut *tx)
+            .await?;
+        if count == 0 {
+            for rule in rules {
+                sqlx::query(
+                    r#"
+                    INSERT OR IGNORE INTO moral_rules (name, description, weight, polarity, triggers, veto)
+                    VALUES (?, ?, ?, ?, ?, ?)
+                    "#,
+                )
+                .bind(&rule.name)
+                .bind(&rule.description)
+                .bind(rule.weight as f64)
+                .bind(rule.polarity.as_str())
+                .bind(serde_json::to_string(&rule.triggers)?)
+                .bind(rule.veto)
+                .execute(&mut *tx)
+                .await?;
+            }
+        } else {
+            for rule in rules {
+                sqlx::query(
+                    r#"
+                    UPDATE moral_rules
+                    SET polarity = ?, triggers = ?, veto = ?, updated_at = CURRENT_TIMESTAMP
+                    WHERE name = ? AND description = ? AND weight = ?
+                      AND polarity = 'virtue' AND triggers = '[]' AND veto = 0
+                    "#,
+                )
+                .bind(rule.polarity.as_str())
+                .bind(serde_json::to_string(&rule.triggers)?)
+                .bind(rule.veto)
+                .bind(&rule.name)
+                .bind(&rule.description)
+                .bind(rule.weight as f64)
+                .execute(&mut *tx)
+                .await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Insert a rule, or replace the one with the same name
+    pub async fn upsert(&self, rule: &MoralRule) -> Result<()> {
+        sqlx::query(
+            r#"
+            INSERT INTO moral_rules (name, description, weight, polarity, triggers, veto)
+            VALUES (?, ?, ?, ?, ?, ?)
+            ON CONFLICT(name) DO UPDATE SET
+                description = excluded.description,
+                weight = excluded.weight,
+                polarity = excluded.polarity,
+                triggers = excluded.triggers,
+                veto = excluded.veto,
+                updated_at = CURRENT_TIMESTAMP
+            "#,
+        )
+        .bind(&rule.name)
+        .bind(&rule.description)
+        .bind(rule.weight as f64)
+        .bind(rule.polarity.as_str())
+        .bind(serde_json::to_string(&rule.triggers)?)
+        .bind(rule.veto)
+        .execute(&self.pool)
+        .await?;
+        Ok(())
+    }
+
+    /// Delete a rule, returning whether it existed
+    pub async fn delete(&self, name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM moral_rules WHERE name = ?")
+            .bind(name)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+}
@@ -2,22 +2,26 @@
 //!
 //! Maps attention focus and directs cognitive resources
 
+use super::global_workspace::WorkspaceContent;
 use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, VecDeque};
 use std::sync::Arc;
-use tokio::sync::RwLock;
-use std::collections::{HashMap, VecDeque, BinaryHeap, HashSet};
-use std::cmp::Ordering;
 use std::time::{Duration, Instant};
-use chrono::{DateTime, Utc};
-use super::global_workspace::WorkspaceContent;
-use std::io::Cursor;
-use flate2::write::GzEncoder;
-use flate2::Compression;
+use tokio::sync::RwLock;
 
-// [Previous implementations remain unchanged]
+/// What the attention schema is currently doing
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub enum AttentionState {
+    /// Nothing has been attended to yet
+    Idle,
+    /// Attention rests on the given content
+    Focused(String),
+}
 
 /// Record of a single attention focus point
-#[derive(Clone, Debug)]
+#[derive(Clone, Debug, Serialize, Deserialize)]
 pub struct AttentionRecord {
     focus: String,
     state: AttentionState,
@@ -32,6 +36,8 @@ pub struct AttentionHistory {
     max_history_size: usize,
     retention_period: Duration,
     last_cleanup: Arc<RwLock<Instant>>,
+    /// LZ4-compressed JSON batches of archived records
+    compressed_records: Arc<RwLock<Vec<Vec<u8>>>>,
 }
 
 impl AttentionHistory {
@@ -41,24 +47,26 @@ impl AttentionHistory {
             max_history_size: max_size,
             retention_period: retention,
             last_cleanup: Arc::new(RwLock::new(Instant::now())),
+            compressed_records: Arc::new(RwLock::new(Vec::new())),
         }
     }
 
     /// Add a new attention record
     pub async fn add_record(&self, record: AttentionRecord) -> Result<()> {
         let mut records = self.records.write().await;
-        
+
         // Add new record
         records.push_back(record);
-        
+
         // Maintain circular buffer size
         if records.len() > self.max_history_size {
             records.pop_front();
         }
-        
+        drop(records);
+
         // Periodic cleanup of old records
         self.cleanup_old_records().await?;
-        
+
         Ok(())
     }
 
@@ -66,40 +74,132 @@ impl AttentionHistory {
     async fn cleanup_old_records(&self) -> Result<()> {
         let mut last_cleanup = self.last_cleanup.write().await;
         let now = Instant::now();
-        
+
         // Only cleanup periodically
         if now.duration_since(*last_cleanup) < Duration::from_secs(3600) {
             return Ok(());
         }
-        
+
         let mut records = self.records.write().await;
         let cutoff = Utc::now() - chrono::Duration::from_std(self.retention_period)?;
-        
+
         records.retain(|record| record.timestamp > cutoff);
         *last_cleanup = now;
-        
+
         Ok(())
     }
 
     /// Get records within a time window
     pub async fn get_records(&self, window: Duration) -> Vec<AttentionRecord> {
         let records = self.records.read().await;
-        let cutoff = Utc::now() - chrono::Duration::from_std(window).unwrap_or(chrono::Duration::zero());
-        
-        records.iter()
+        let cutoff =
+            Utc::now() - chrono::Duration::from_std(window).unwrap_or(chrono::Duration::zero());
+
+        records
+            .iter()
             .filter(|record| record.timestamp > cutoff)
             .cloned()
             .collect()
     }
+
+    /// Get every retained record whose focus matches exactly
+    pub async fn search_by_focus(&self, focus: &str) -> Vec<AttentionRecord> {
+        let records = self.records.read().await;
+        records
+            .iter()
+            .filter(|record| record.focus == focus)
+            .cloned()
+            .collect()
+    }
+
+    /// Move records older than `age` out of the buffer into a compressed
+    /// archive batch. Returns the number of records archived.
+    pub async fn archive_old_records(&self, age: Duration) -> Result<usize> {
+        let cutoff = Utc::now() - chrono::Duration::from_std(age)?;
+        let mut records = self.records.write().await;
+
+        let (old, recent): (VecDeque<_>, VecDeque<_>) = records
+            .drain(..)
+            .partition(|record| record.timestamp <= cutoff);
+        *records = recent;
+        drop(records);
+
+        if old.is_empty() {
+            return Ok(0);
+        }
+
+        let json = serde_json::to_vec(&old)?;
+        let compressed = lz4::block::compress(&json, None, true)?;
+        self.compressed_records.write().await.push(compressed);
+
+        metrics::counter!("consciousness.attention.archived_total", old.len() as u64);
+        Ok(old.len())
+    }
+}
+
+/// Decides which attention records are worth keeping.
+///
+/// Records inside the recent window are always kept. Older records decay
+/// exponentially per hour of age and are kept while their importance stays
+/// at or above the threshold, up to the maximum age.
+#[derive(Clone, Debug)]
+pub struct RetentionPolicy {
+    recent_window: Duration,
+    max_age: Duration,
+    decay_rate: f64,
+    importance_threshold: f64,
+}
+
+impl RetentionPolicy {
+    pub fn new(
+        recent_window: Duration,
+        max_age: Duration,
+        decay_rate: f64,
+        importance_threshold: f64,
+    ) -> Self {
+        Self {
+            recent_window,
+            max_age,
+            decay_rate,
+            importance_threshold,
+        }
+    }
+
+    /// Importance of a record of the given age, in (0, 1]
+    fn importance(&self, age: Duration) -> f64 {
+        let hours = age.as_secs_f64() / 3600.0;
+        (-self.decay_rate * hours).exp()
+    }
+
+    /// Whether a record of the given age should be kept
+    fn keeps(&self, age: Duration) -> bool {
+        age < self.recent_window
+            || (age < self.max_age && self.importance(age) >= self.importance_threshold)
+    }
+
+    /// Drop the records this policy does not keep. Returns how many were
+    /// removed.
+    pub fn apply_policy(&self, records: &mut VecDeque<AttentionRecord>) -> Result<usize> {
+        let now = Utc::now();
+        let before = records.len();
+        records.retain(|record| {
+            let age = now
+                .signed_duration_since(record.timestamp)
+                .to_std()
+                .unwrap_or(Duration::ZERO);
+            self.keeps(age)
+        });
+        Ok(before - records.len())
+    }
 }
 
 /// Pattern recognition in attention sequences
 #[derive(Clone, Debug)]
 pub struct AttentionPattern {
-    sequence: Vec<String>,
-    frequency: usize,
-    avg_duration: Duration,
-    last_seen: DateTime<Utc>,
+    pub sequence: Vec<String>,
+    pub frequency: usize,
+    pub avg_duration: Duration,
+    pub last_seen: DateTime<Utc>,
 }
 
 /// Temporal context analysis system
@@ -113,18 +213,14 @@ pub struct TemporalContext {
 
 #[derive(Clone, Debug)]
 pub struct AttentionStatistics {
-    total_occurrences: usize,
-    total_duration: Duration,
-    avg_priority: f32,
-    last_occurrence: DateTime<Utc>,
+    pub total_occurrences: usize,
+    pub total_duration: Duration,
+    pub avg_priority: f32,
+    pub last_occurrence: DateTime<Utc>,
 }
 
 impl TemporalContext {
-    pub fn new(
-        short_term_size: usize,
-        min_pattern_len: usize,
-        max_pattern_len: usize
-    ) -> Self {
+    pub fn new(short_term_size: usize, min_pattern_len: usize, max_pattern_len: usize) -> Self {
         Self {
             short_term: VecDeque::with_capacity(short_term_size),
             medium_term: Vec::new(),
@@ -143,13 +239,16 @@ impl TemporalContext {
         }
 
         // Update long-term statistics
-        let stats = self.long_term.entry(record.focus.clone()).or_insert(AttentionStatistics {
-            total_occurrences: 0,
-            total_duration: Duration::from_secs(0),
-            avg_priority: 0.0,
-            last_occurrence: record.timestamp,
-        });
-        
+        let stats = self
+            .long_term
+            .entry(record.focus.clone())
+            .or_insert(AttentionStatistics {
+                total_occurrences: 0,
+                total_duration: Duration::from_secs(0),
+                avg_priority: 0.0,
+                last_occurrence: record.timestamp,
+            });
+
         stats.total_occurrences += 1;
         stats.total_duration += record.duration;
         stats.last_occurrence = record.timestamp;
@@ -160,7 +259,9 @@ impl TemporalContext {
 
     /// Detect attention patterns in short-term memory
     fn detect_patterns(&mut self) {
-        let sequences: Vec<String> = self.short_term.iter()
+        let sequences: Vec<String> = self
+            .short_term
+            .iter()
             .map(|record| record.focus.clone())
             .collect();
 
@@ -172,8 +273,7 @@ impl TemporalContext {
 
             for window in sequences.windows(len) {
                 let pattern = window.to_vec();
-                if let Some(existing) = self.medium_term.iter_mut()
-                    .find(|p| p.sequence == pattern)
+                if let Some(existing) = self.medium_term.iter_mut().find(|p| p.sequence == pattern)
                 {
                     existing.frequency += 1;
                     existing.last_seen = Utc::now();
@@ -191,7 +291,8 @@ impl TemporalContext {
 
     /// Get most frequent patterns
     pub fn get_frequent_patterns(&self, min_frequency: usize) -> Vec<AttentionPattern> {
-        self.medium_term.iter()
+        self.medium_term
+            .iter()
             .filter(|p| p.frequency >= min_frequency)
             .cloned()
             .collect()
@@ -199,25 +300,22 @@ impl TemporalContext {
 
     /// Predict next likely focus based on current sequence
     pub fn predict_next_focus(&self, current_sequence: &[String]) -> Option<String> {
-        self.medium_term.iter()
+        self.medium_term
+            .iter()
             .filter(|pattern| {
-                pattern.sequence.len() > current_sequence.len() &&
-                pattern.sequence.starts_with(current_sequence)
+                pattern.sequence.len() > current_sequence.len()
+                    && pattern.sequence.starts_with(current_sequence)
             })
             .max_by_key(|pattern| pattern.frequency)
             .map(|pattern| pattern.sequence[current_sequence.len()].clone())
     }
 }
 
-// Update AttentionSchema to use history system
+/// Tracks what the system is attending to and how attention moves over time
 pub struct AttentionSchema {
     current_focus: Arc<RwLock<String>>,
-    semantic_analyzer: SemanticAnalyzer,
-    relevance_scorer: RelevanceScore,
-    weights: AttentionWeights,
-    state_machine: AttentionStateMachine,
-    queue: AttentionQueue,
-    conflict_resolver: ConflictResolver,
+    /// When the current focus was taken up
+    focus_started: Arc<RwLock<Instant>>,
     history: AttentionHistory,
     temporal_context: Arc<RwLock<TemporalContext>>,
 }
@@ -226,69 +324,72 @@ impl AttentionSchema {
     pub fn new() -> Self {
         Self {
             current_focus: Arc::new(RwLock::new(String::new())),
-            semantic_analyzer: SemanticAnalyzer::new(5),
-            relevance_scorer: RelevanceScore::new(0.0, 0.0, 0.0, 1.0),
-            weights: AttentionWeights::new(),
-            state_machine: AttentionStateMachine::new(3, Duration::from_millis(100)),
-            queue: AttentionQueue::new(20, 0.5),
-            conflict_resolver: ConflictResolver::new(0.7, Duration::from_millis(200), 3),
+            focus_started: Arc::new(RwLock::new(Instant::now())),
             history: AttentionHistory::new(1000, Duration::from_secs(3600 * 24)), // 24 hour retention
             temporal_context: Arc::new(RwLock::new(TemporalContext::new(20, 2, 5))),
         }
     }
 
-    // [Previous methods remain unchanged]
-
-    /// Process attention queue with history tracking
-    pub async fn process_queue(&self) -> Result<()> {
-        while let Some(item) = self.queue.dequeue().await {
-            let current_state = self.state_machine.current_state().await;
-            
-            match self.conflict_resolver.resolve_conflict(&current_state, &item).await {
-                Resolution::Allow => {
-                    if self.conflict_resolver.acquire_lock(item.content.clone()).await? {
-                        if let Ok(transitioned) = self.state_machine.transition(item.content.clone()).await {
-                            if transitioned {
-                                let start_time = Instant::now();
-                                let mut focus = self.current_focus.write().await;
-                                *focus = item.content.clone();
-                                
-                                // Create and store attention record
-                                let record = AttentionRecord {
-                                    focus: item.content.clone(),
-                                    state: current_state.clone(),
-                                    duration: start_time.elapsed(),
-                                    context: HashMap::new(), // Could be populated with relevant context
-                                    timestamp: Utc::now(),
-                                };
-                                
-                                self.history.add_record(record.clone()).await?;
-                                self.temporal_context.write().await.update(record);
-                                
-                                metrics::counter!("consciousness.attention.shifts_total", 1);
-                            }
-                        }
-                        self.conflict_resolver.release_lock(&item.content).await;
-                    }
-                }
-                // [Rest of match arms remain unchanged]
-                _ => {}
-            }
+    /// Shift attention to a broadcast and record the shift in the history
+    pub async fn update(&self, broadcast: &WorkspaceContent, predictions: &str) -> Result<()> {
+        let mut focus = self.current_focus.write().await;
+        if *focus == broadcast.content {
+            return Ok(());
         }
+
+        let mut started = self.focus_started.write().await;
+        let previous = if focus.is_empty() {
+            AttentionState::Idle
+        } else {
+            AttentionState::Focused(focus.clone())
+        };
+
+        let mut context = HashMap::new();
+        context.insert("priority".to_string(), broadcast.priority as f32);
+        if !predictions.is_empty() {
+            context.insert("prediction_len".to_string(), predictions.len() as f32);
+        }
+
+        let record = AttentionRecord {
+            focus: broadcast.content.clone(),
+            state: previous,
+            duration: started.elapsed(),
+            context,
+            timestamp: Utc::now(),
+        };
+
+        *focus = broadcast.content.clone();
+        *started = Instant::now();
+        drop(started);
+        drop(focus);
+
+        self.history.add_record(record.clone()).await?;
+        self.temporal_context.write().await.update(record);
+
+        metrics::counter!("consciousness.attention.shifts_total", 1);
         Ok(())
     }
+
+    /// The content attention currently rests on, empty if none
+    pub async fn current_focus(&self) -> String {
+        self.current_focus.read().await.clone()
+    }
+}
+
+impl Default for AttentionSchema {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
-    // [Previous tests remain unchanged]
-
     #[tokio::test]
     async fn test_attention_history() {
         let history = AttentionHistory::new(5, Duration::from_secs(3600));
-        
+
         // Add some records
         let record = AttentionRecord {
             focus: "test".to_string(),
@@ -297,9 +398,9 @@ mod tests {
             context: HashMap::new(),
             timestamp: Utc::now(),
         };
-        
+
         history.add_record(record.clone()).await.unwrap();
-        
+
         // Check retrieval
         let records = history.get_records(Duration::from_secs(3600)).await;
         assert_eq!(records.len(), 1);
@@ -309,7 +410,7 @@ mod tests {
     #[tokio::test]
     async fn test_temporal_context() {
         let mut context = TemporalContext::new(5, 2, 3);
-        
+
         // Add sequence of records
         let record1 = AttentionRecord {
             focus: "A".to_string(),
@@ -318,7 +419,7 @@ mod tests {
             context: HashMap::new(),
             timestamp: Utc::now(),
         };
-        
+
         let record2 = AttentionRecord {
             focus: "B".to_string(),
             state: AttentionState::Focused("B".to_string()),
@@ -326,12 +427,12 @@ mod tests {
             context: HashMap::new(),
             timestamp: Utc::now(),
         };
-        
-        context.update(record1);
+
+        context.update(record1.clone());
         context.update(record2.clone());
         context.update(record1.clone());
         context.update(record2);
-        
+
         // Check pattern detection
         let patterns = context.get_frequent_patterns(2);
         assert!(!patterns.is_empty(), "Should detect repeated patterns");
@@ -340,7 +441,7 @@ mod tests {
     #[tokio::test]
     async fn test_pattern_prediction() {
         let mut context = TemporalContext::new(5, 2, 3);
-        
+
         // Create repeating pattern A -> B -> C
         for _ in 0..3 {
             for focus in ["A", "B", "C"].iter() {
@@ -354,7 +455,7 @@ mod tests {
                 context.update(record);
             }
         }
-        
+
         // Test prediction
         let sequence = vec!["A".to_string(), "B".to_string()];
         if let Some(prediction) = context.predict_next_focus(&sequence) {
@@ -366,7 +467,7 @@ mod tests {
 
     #[test]
     fn test_retention_policy() {
-        let mut policy = RetentionPolicy::new(
+        let policy = RetentionPolicy::new(
             Duration::from_secs(3600),
             Duration::from_secs(86400),
             0.1,
@@ -377,11 +478,11 @@ mod tests {
         let now = Utc::now();
 
         // Add records of varying ages
-        for i in 0..10 {
+        for i in 0..10i64 {
             let record = AttentionRecord {
                 focus: format!("focus_{}", i),
                 state: AttentionState::Focused(format!("focus_{}", i)),
-                duration: Duration::from_secs(i * 100),
+                duration: Duration::from_secs(i as u64 * 100),
                 context: HashMap::new(),
                 timestamp: now - chrono::Duration::hours(i),
             };
@@ -398,13 +499,13 @@ mod tests {
     #[tokio::test]
     async fn test_record_compression() {
         let history = AttentionHistory::new(5, Duration::from_secs(3600));
-        
+
         // Add some records
-        for i in 0..10 {
+        for i in 0..10i64 {
             let record = AttentionRecord {
                 focus: format!("focus_{}", i),
                 state: AttentionState::Focused(format!("focus_{}", i)),
-                duration: Duration::from_secs(i),
+                duration: Duration::from_secs(i as u64),
                 context: HashMap::new(),
                 timestamp: Utc::now() - chrono::Duration::hours(i),
             };
@@ -412,8 +513,11 @@ mod tests {
         }
 
         // Archive old records
-        history.archive_old_records(Duration::from_secs(3600)).await.unwrap();
-        
+        history
+            .archive_old_records(Duration::from_secs(3600))
+            .await
+            .unwrap();
+
         // Check compression
         let compressed = history.compressed_records.read().await;
         assert!(!compressed.is_empty(), "Should have compressed records");
@@ -422,7 +526,7 @@ mod tests {
     #[tokio::test]
     async fn test_indexed_search() {
         let history = AttentionHistory::new(5, Duration::from_secs(3600));
-        
+
         // Add records with specific focus
         let record = AttentionRecord {
             focus: "test_focus".to_string(),
@@ -432,7 +536,7 @@ mod tests {
             timestamp: Utc::now(),
         };
         history.add_record(record).await.unwrap();
-        
+
         // Search by focus
         let results = history.search_by_focus("test_focus").await;
         assert_eq!(results.len(), 1, "Should find record by focus");
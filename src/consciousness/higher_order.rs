@@ -205,8 +205,8 @@ mod tests {
         let neutral_awareness = hot.awareness_level().await;
         
         // Verify awareness levels are in range
-        assert!(introspective_awareness >= 0.0 && introspective_awareness <= 1.0);
-        assert!(neutral_awareness >= 0.0 && neutral_awareness <= 1.0);
+        assert!((0.0..=1.0).contains(&introspective_awareness));
+        assert!((0.0..=1.0).contains(&neutral_awareness));
         
         // Introspective content should yield higher awareness
         // Note: This might not always be true due to the moving average,
@@ -235,7 +235,7 @@ mod tests {
         
         // Awareness level should be in range
         let awareness = hot.awareness_level().await;
-        assert!(awareness >= 0.0 && awareness <= 1.0);
+        assert!((0.0..=1.0).contains(&awareness));
     }
 }
 
@@ -0,0 +1,432 @@
+//! Consciousness metrics history
+//!
+//! Every call to `ConsciousnessEngine::process_information` records a
+//! sample. The most recent samples are kept in a bounded ring buffer. With a
+//! database attached, every sample is also written to `consciousness_metrics`,
+//! and samples older than `compact_after_secs` are packed into LZ4-compressed
+//! blocks in `consciousness_metric_blocks`.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use sqlx::{Row, SqlitePool};
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use super::ConsciousnessMetrics;
+use crate::config::env_or;
+use crate::memory::holographic::{CompressedStore, ConsciousnessMetricsStore, MetricsStorageConfig};
+
+/// Metrics history configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct MetricsHistoryConfig {
+    /// Number of samples kept in memory
+    pub recent_size: usize,
+    /// Samples older than this are compressed into blocks, in seconds
+    pub compact_after_secs: u64,
+    /// Maximum number of samples per compressed block
+    pub block_size: usize,
+    /// How often the compaction task runs, in seconds
+    pub compact_interval_secs: u64,
+}
+
+impl Default for MetricsHistoryConfig {
+    fn default() -> Self {
+        Self {
+            recent_size: MetricsStorageConfig::default().max_recent_size,
+            compact_after_secs: 86400,    // 24 hours
+            block_size: 1024,
+            compact_interval_secs: 3600,  // 1 hour
+        }
+    }
+}
+
+impl MetricsHistoryConfig {
+    /// Load metrics history configuration from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            recent_size: env_or("CONSCIOUSNESS_HISTORY_RECENT_SIZE", defaults.recent_size),
+            compact_after_secs: env_or("CONSCIOUSNESS_HISTORY_COMPACT_AFTER_SECS", defaults.compact_after_secs),
+            block_size: env_or("CONSCIOUSNESS_HISTORY_BLOCK_SIZE", defaults.block_size),
+            compact_interval_secs: env_or("CONSCIOUSNESS_HISTORY_COMPACT_INTERVAL_SECS", defaults.compact_interval_secs),
+        }
+    }
+
+    /// Get the compaction interval as Duration
+    pub fn compact_interval(&self) -> Duration {
+        Duration::from_secs(self.compact_interval_secs)
+    }
+}
+
+/// The numeric part of a metrics snapshot
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MetricSample {
+    pub timestamp: DateTime<Utc>,
+    pub phi_value: f64,
+    pub workspace_activity: f64,
+    pub metacognition_level: f64,
+}
+
+impl From<&ConsciousnessMetrics> for MetricSample {
+    fn from(metrics: &ConsciousnessMetrics) -> Self {
+        Self {
+            timestamp: metrics.timestamp,
+            phi_value: metrics.phi_value,
+            workspace_activity: metrics.workspace_activity,
+            metacognition_level: metrics.metacognition_level,
+        }
+    }
+}
+
+/// Averages over one bucket of a downsampled series
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HistoryPoint {
+    /// Start of the bucket
+    pub timestamp: DateTime<Utc>,
+    pub phi_value: f64,
+    pub workspace_activity: f64,
+    pub metacognition_level: f64,
+    /// Number of samples averaged
+    pub samples: usize,
+}
+
+/// Recorded consciousness metrics samples
+pub struct MetricsHistory {
+    recent: Mutex<ConsciousnessMetricsStore>,
+    pool: Option<SqlitePool>,
+    config: MetricsHistoryConfig,
+}
+
+impl MetricsHistory {
+    /// Create a history that keeps samples in memory only
+    pub fn new(config: MetricsHistoryConfig) -> Self {
+        Self {
+            recent: Mutex::new(ConsciousnessMetricsStore::new(MetricsStorageConfig::new(
+                config.recent_size,
+            ))),
+            pool: None,
+            config,
+        }
+    }
+
+    /// Also write every sample to the database
+    pub fn with_database(mut self, pool: SqlitePool) -> Self {
+        self.pool = Some(pool);
+        self
+    }
+
+    /// Record a metrics snapshot
+    pub async fn record(&self, metrics: &ConsciousnessMetrics) -> Result<()> {
+        self.recent.lock().unwrap().record(metrics.clone())?;
+
+        if let Some(pool) = &self.pool {
+            // Mission alignment isn't measured yet, so it is left NULL
+            sqlx::query(
+                r#"
+                INSERT INTO consciousness_metrics (
+                    timestamp, phi_value, global_workspace_activity, mission_alignment,
+                    emotional_state, attention_focus, metacognition_level
+                )
+                VALUES (?, ?, ?, NULL, ?, ?, ?)
+                "#,
+            )
+            .bind(metrics.timestamp)
+            .bind(metrics.phi_value)
+            .bind(metrics.workspace_activity)
+            .bind(serde_json::to_string(&metrics.emotional_state)?)
+            .bind(&metrics.attention_focus)
+            .bind(metrics.metacognition_level)
+            .execute(pool)
+            .await?;
+        }
+
+        counter!("consciousness.history.samples_total", 1);
+        Ok(())
+    }
+
+    /// Every sample taken between `from` and `to` inclusive, oldest first
+    ///
+    /// Without a database only the samples still in memory are returned.
+    pub async fn samples(&self, from: DateTime<Utc>, to: DateTime<Utc>) -> Result<Vec<MetricSample>> {
+        let Some(pool) = &self.pool else {
+            return Ok(self
+                .recent
+                .lock()
+                .unwrap()
+                .iter()
+                .map(MetricSample::from)
+                .filter(|sample| sample.timestamp >= from && sample.timestamp <= to)
+                .collect());
+        };
+
+        let mut samples = Vec::new();
+        let blocks = sqlx::query(
+            "SELECT data FROM consciousness_metric_blocks WHERE start_time <= ? AND end_time >= ?",
+        )
+        .bind(to)
+        .bind(from)
+        .fetch_all(pool)
+        .await?;
+        for block in blocks {
+            let data: Vec<u8> = block.try_get("data")?;
+            samples.extend(
+                CompressedStore::<MetricSample>::decompress(&data)?
+                    .into_iter()
+                    .filter(|sample| sample.timestamp >= from && sample.timestamp <= to),
+            );
+        }
+
+        let rows = sqlx::query(
+            r#"
+            SELECT timestamp, phi_value, global_workspace_activity, metacognition_level
+            FROM consciousness_metrics
+            WHERE timestamp >= ? AND timestamp <= ?
+            "#,
+        )
+        .bind(from)
+        .bind(to)
+        .fetch_all(pool)
+        .await?;
+        for row in rows {
+            samples.push(MetricSample {
+                timestamp: row.try_get("timestamp")?,
+                phi_value: row.try_get("phi_value")?,
+                workspace_activity: row.try_get("global_workspace_activity")?,
+                metacognition_level: row.try_get("metacognition_level")?,
+            });
+        }
+
+        samples.sort_by_key(|sample| sample.timestamp);
+        Ok(samples)
+    }
+
+    /// Samples between `from` and `to`, averaged over buckets of `resolution`
+    pub async fn history(
+        &self,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+        resolution: Duration,
+    ) -> Result<Vec<HistoryPoint>> {
+        let samples = self.samples(from, to).await?;
+        Ok(downsample(&samples, from, resolution))
+    }
+
+    /// Move samples older than `compact_after_secs` from `consciousness_metrics`
+    /// into compressed blocks
+    ///
+    /// Returns the number of samples compacted.
+    pub async fn compact(&self) -> Result<usize> {
+        let Some(pool) = &self.pool else {
+            return Ok(0);
+        };
+        let cutoff = Utc::now() - chrono::Duration::seconds(self.config.compact_after_secs as i64);
+
+        let rows = sqlx::query(
+            r#"
+            SELECT id, timestamp, phi_value, global_workspace_activity, metacognition_level
+            FROM consciousness_metrics
+            WHERE timestamp < ?
+            ORDER BY timestamp
+            "#,
+        )
+        .bind(cutoff)
+        .fetch_all(pool)
+        .await?;
+        if rows.is_empty() {
+            return Ok(0);
+        }
+
+        let mut ids = Vec::with_capacity(rows.len());
+        let mut samples = Vec::with_capacity(rows.len());
+        for row in &rows {
+            ids.push(row.try_get::<i64, _>("id")?);
+            samples.push(MetricSample {
+                timestamp: row.try_get("timestamp")?,
+                phi_value: row.try_get("phi_value")?,
+                workspace_activity: row.try_get("global_workspace_activity")?,
+                metacognition_level: row.try_get("metacognition_level")?,
+            });
+        }
+
+        let mut store = CompressedStore::new(usize::MAX);
+        let chunks: Vec<&[MetricSample]> = samples.chunks(self.config.block_size.max(1)).collect();
+        for chunk in &chunks {
+            store.store(chunk)?;
+        }
+
+        // Blocks are written and their rows deleted in one transaction, so a
+        // sample is never in both or in neither
+        let mut tx = pool.begin().await?;
+        for (index, chunk) in chunks.iter().enumerate() {
+            let data = store.block_data(index).unwrap_or_default();
+            sqlx::query(
+                r#"
+                INSERT INTO consciousness_metric_blocks (start_time, end_time, samples, data)
+                VALUES (?, ?, ?, ?)
+                "#,
+            )
+            .bind(chunk[0].timestamp)
+            .bind(chunk[chunk.len() - 1].timestamp)
+            .bind(chunk.len() as i64)
+            .bind(data)
+            .execute(&mut *tx)
+            .await?;
+        }
+        for id in &ids {
+            sqlx::query("DELETE FROM consciousness_metrics WHERE id = ?")
+                .bind(id)
+                .execute(&mut *tx)
+                .await?;
+        }
+        tx.commit().await?;
+
+        counter!("consciousness.history.compacted_total", samples.len() as u64);
+        info!(
+            "Compacted {} consciousness metrics samples into {} blocks ({:.1}x compression)",
+            samples.len(),
+            store.block_count(),
+            store.compression_ratio()
+        );
+        Ok(samples.len())
+    }
+
+    /// Spawn the compaction loop on the Tokio runtime
+    pub fn spawn_compaction(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.compact_interval());
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.compact().await {
+                    error!("Consciousness metrics compaction failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Average samples over consecutive buckets of `resolution`, starting at
+/// `from`; empty buckets are left out
+pub fn downsample(samples: &[MetricSample], from: DateTime<Utc>, resolution: Duration) -> Vec<HistoryPoint> {
+    let bucket_ms = (resolution.as_millis() as i64).max(1);
+    let mut points: Vec<HistoryPoint> = Vec::new();
+
+    for sample in samples {
+        let offset = (sample.timestamp - from).num_milliseconds().max(0);
+        let start = from + chrono::Duration::milliseconds(offset / bucket_ms * bucket_ms);
+        match points.last_mut() {
+            Some(point) if point.timestamp == start => {
+                point.phi_value += sample.phi_value;
+                point.workspace_activity += sample.workspace_activity;
+                point.metacognition_level += sample.metacognition_level;
+                point.samples += 1;
+            }
+            _ => points.push(HistoryPoint {
+                timestamp: start,
+                phi_value: sample.phi_value,
+                workspace_activity: sample.workspace_activity,
+                metacognition_level: sample.metacognition_level,
+                samples: 1,
+            }),
+        }
+    }
+
+    for point in &mut points {
+        let count = point.samples as f64;
+        point.phi_value /= count;
+        point.workspace_activity /= count;
+        point.metacognition_level /= count;
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::store::test_pool;
+    use crate::soul::Emotion;
+
+    fn metrics(phi_value: f64, timestamp: DateTime<Utc>) -> ConsciousnessMetrics {
+        ConsciousnessMetrics {
+            phi_value,
+            workspace_activity: phi_value / 2.0,
+            emotional_state: Emotion::default(),
+            attention_focus: "test".to_string(),
+            metacognition_level: 0.5,
+            timestamp,
+        }
+    }
+
+    #[test]
+    fn test_downsample_averages_buckets() {
+        let from = Utc::now();
+        let samples: Vec<MetricSample> = [(0, 0.2), (30, 0.4), (150, 0.9)]
+            .into_iter()
+            .map(|(secs, phi)| MetricSample::from(&metrics(phi, from + chrono::Duration::seconds(secs))))
+            .collect();
+
+        let points = downsample(&samples, from, Duration::from_secs(60));
+        assert_eq!(points.len(), 2);
+        assert_eq!(points[0].timestamp, from);
+        assert_eq!(points[0].samples, 2);
+        assert!((points[0].phi_value - 0.3).abs() < 1e-9);
+        assert_eq!(points[1].timestamp, from + chrono::Duration::seconds(120));
+        assert_eq!(points[1].phi_value, 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_compacted_samples_stay_queryable() {
+        let config = MetricsHistoryConfig {
+            block_size: 2,
+            ..MetricsHistoryConfig::default()
+        };
+        let history = MetricsHistory::new(config).with_database(test_pool().await);
+
+        let now = Utc::now();
+        let old = now - chrono::Duration::days(2);
+        for (minutes, phi) in [(0, 0.1), (1, 0.2), (2, 0.3)] {
+            history
+                .record(&metrics(phi, old + chrono::Duration::minutes(minutes)))
+                .await
+                .unwrap();
+        }
+        history.record(&metrics(0.8, now)).await.unwrap();
+
+        assert_eq!(history.compact().await.unwrap(), 3);
+        assert_eq!(history.compact().await.unwrap(), 0);
+
+        let samples = history.samples(old, now).await.unwrap();
+        let phi: Vec<f64> = samples.iter().map(|s| s.phi_value).collect();
+        assert_eq!(phi, vec![0.1, 0.2, 0.3, 0.8]);
+
+        let points = history
+            .history(old, old + chrono::Duration::minutes(5), Duration::from_secs(3600))
+            .await
+            .unwrap();
+        assert_eq!(points.len(), 1);
+        assert_eq!(points[0].samples, 3);
+        assert!((points[0].phi_value - 0.2).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_records_activity_unclamped() {
+        let pool = test_pool().await;
+        let history = MetricsHistory::new(MetricsHistoryConfig::default()).with_database(pool.clone());
+        let now = Utc::now();
+        let mut busy = metrics(0.6, now);
+        busy.workspace_activity = 1.4;
+        history.record(&busy).await.unwrap();
+
+        let samples = history.samples(now, now).await.unwrap();
+        assert_eq!(samples[0].workspace_activity, 1.4);
+        let alignment: Option<f64> = sqlx::query_scalar("SELECT mission_alignment FROM consciousness_metrics")
+            .fetch_one(&pool)
+            .await
+            .unwrap();
+        assert_eq!(alignment, None);
+    }
+}
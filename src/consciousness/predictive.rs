@@ -44,6 +44,12 @@ impl PredictiveProcessor {
     }
 }
 
+impl Default for PredictiveProcessor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -74,7 +80,7 @@ mod tests {
         // Let's test a few values to ensure the formula works as expected.
         
         let confidence_short = (10.0f64 / 100.0f64).clamp(0.0, 1.0) * 0.5 + 0.5;
-        assert!(confidence_short >= 0.5 && confidence_short <= 1.0);
+        assert!((0.5..=1.0).contains(&confidence_short));
 
         let confidence_medium = (100.0f64 / 100.0f64).clamp(0.0, 1.0) * 0.5 + 0.5;
         assert_eq!(confidence_medium, 1.0);
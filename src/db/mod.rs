@@ -39,10 +39,10 @@ impl DbMetrics {
     }
 
     pub async fn update_pool_stats(&self, pool: &SqlitePool) {
-        *self.active_connections.write().await = pool.size() - pool.num_idle();
+        *self.active_connections.write().await = pool.size() - pool.num_idle() as u32;
         *self.pool_size.write().await = pool.size();
         
-        gauge!("db.active_connections", (pool.size() - pool.num_idle()) as f64);
+        gauge!("db.active_connections", (pool.size() - pool.num_idle() as u32) as f64);
         gauge!("db.pool_size", pool.size() as f64);
         gauge!("db.idle_connections", pool.num_idle() as f64);
     }
@@ -59,6 +59,12 @@ impl DbMetrics {
     }
 }
 
+impl Default for DbMetrics {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 
 /// Get the database path, creating the data directory if needed
 pub fn get_db_path() -> anyhow::Result<std::path::PathBuf> {
@@ -199,7 +205,7 @@ pub async fn health_check(pool: &SqlitePool) -> anyhow::Result<DatabaseHealth> {
     
     // Get pool statistics
     let pool_size = pool.size();
-    let idle_connections = pool.num_idle();
+    let idle_connections = pool.num_idle() as u32;
     let active_connections = pool_size - idle_connections;
     
     // Check database size and integrity
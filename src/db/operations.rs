@@ -3,13 +3,17 @@
 //! Provides safe database operations with transaction support,
 //! error handling, and performance monitoring.
 
-use sqlx::{SqlitePool, Row};
+use sqlx::sqlite::{SqliteArguments, SqliteQueryResult, SqliteRow};
+use sqlx::{Arguments, SqlitePool, Row};
 use anyhow::{Result, anyhow};
-use tracing::{debug, warn, error};
+use tracing::{debug, error};
 use std::time::Instant;
 use crate::db::{DbMetrics, with_transaction};
+use crate::memory::embedding::{vector_from_bytes, vector_to_bytes};
+use crate::memory::{self, MemoryLayer};
 
 /// Safe database operations wrapper
+#[derive(Clone)]
 pub struct DatabaseOperations {
     pool: SqlitePool,
     metrics: DbMetrics,
@@ -35,14 +39,13 @@ impl DatabaseOperations {
     pub async fn execute_query(
         &self,
         query: &str,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
-    ) -> Result<sqlx::sqlite::SqliteQueryResult> {
+        params: SqliteArguments<'_>,
+    ) -> Result<SqliteQueryResult> {
         let start = Instant::now();
         
         debug!("Executing query: {}", query);
         
-        let result = sqlx::query(query)
-            .bind_all(params)
+        let result = sqlx::query_with(query, params)
             .execute(&self.pool)
             .await;
         
@@ -66,7 +69,7 @@ impl DatabaseOperations {
     pub async fn query_one<T>(
         &self,
         query: &str,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        params: SqliteArguments<'_>,
     ) -> Result<T>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
@@ -75,8 +78,7 @@ impl DatabaseOperations {
         
         debug!("Executing query_one: {}", query);
         
-        let result = sqlx::query_as::<_, T>(query)
-            .bind_all(params)
+        let result = sqlx::query_as_with::<_, T, _>(query, params)
             .fetch_one(&self.pool)
             .await;
         
@@ -99,7 +101,7 @@ impl DatabaseOperations {
     pub async fn query_all<T>(
         &self,
         query: &str,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        params: SqliteArguments<'_>,
     ) -> Result<Vec<T>>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
@@ -108,8 +110,7 @@ impl DatabaseOperations {
         
         debug!("Executing query_all: {}", query);
         
-        let result = sqlx::query_as::<_, T>(query)
-            .bind_all(params)
+        let result = sqlx::query_as_with::<_, T, _>(query, params)
             .fetch_all(&self.pool)
             .await;
         
@@ -133,7 +134,7 @@ impl DatabaseOperations {
     pub async fn query_optional<T>(
         &self,
         query: &str,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        params: SqliteArguments<'_>,
     ) -> Result<Option<T>>
     where
         T: for<'r> sqlx::FromRow<'r, sqlx::sqlite::SqliteRow> + Send + Unpin,
@@ -142,8 +143,7 @@ impl DatabaseOperations {
         
         debug!("Executing query_optional: {}", query);
         
-        let result = sqlx::query_as::<_, T>(query)
-            .bind_all(params)
+        let result = sqlx::query_as_with::<_, T, _>(query, params)
             .fetch_optional(&self.pool)
             .await;
         
@@ -175,10 +175,10 @@ impl DatabaseOperations {
         &self,
         table: &str,
         columns: &[&str],
-        values: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        values: SqliteArguments<'_>,
     ) -> Result<i64> {
         let columns_str = columns.join(", ");
-        let placeholders: Vec<String> = (0..values.len()).map(|_| "?".to_string()).collect();
+        let placeholders: Vec<String> = (0..columns.len()).map(|_| "?".to_string()).collect();
         let placeholders_str = placeholders.join(", ");
         
         let query = format!(
@@ -190,8 +190,7 @@ impl DatabaseOperations {
         
         debug!("Executing insert_with_return_id: {}", query);
         
-        let result = sqlx::query(&query)
-            .bind_all(values)
+        let result = sqlx::query_with(&query, values)
             .fetch_one(&self.pool)
             .await;
         
@@ -217,7 +216,7 @@ impl DatabaseOperations {
         table: &str,
         set_clauses: &[&str],
         where_clause: &str,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        params: SqliteArguments<'_>,
     ) -> Result<u64> {
         let set_str = set_clauses.join(", ");
         let query = format!("UPDATE {} SET {} WHERE {}", table, set_str, where_clause);
@@ -231,7 +230,7 @@ impl DatabaseOperations {
         &self,
         table: &str,
         where_clause: &str,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        params: SqliteArguments<'_>,
     ) -> Result<u64> {
         let query = format!("DELETE FROM {} WHERE {}", table, where_clause);
         
@@ -244,9 +243,9 @@ impl DatabaseOperations {
         &self,
         table: &str,
         where_clause: &str,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        params: SqliteArguments<'_>,
     ) -> Result<bool> {
-        let query = format!("SELECT EXISTS(SELECT 1 FROM {} WHERE {}) as exists", table, where_clause);
+        let query = format!("SELECT EXISTS(SELECT 1 FROM {} WHERE {}) AS found", table, where_clause);
         
         let result: (bool,) = self.query_one(&query, params).await?;
         Ok(result.0)
@@ -257,7 +256,7 @@ impl DatabaseOperations {
         &self,
         table: &str,
         where_clause: Option<&str>,
-        params: &[&(dyn sqlx::Encode<sqlx::Sqlite> + sqlx::Type<sqlx::Sqlite> + Sync)],
+        params: SqliteArguments<'_>,
     ) -> Result<i64> {
         let query = if let Some(where_clause) = where_clause {
             format!("SELECT COUNT(*) as count FROM {} WHERE {}", table, where_clause)
@@ -277,10 +276,10 @@ impl DatabaseOperations {
             table
         );
         
-        let count: (i64,) = self.query_one(&count_query, &[]).await?;
+        let count: (i64,) = self.query_one(&count_query, SqliteArguments::default()).await?;
         
-        let size: (Option<i64>,) = self.query_optional(&size_query, &[]).await?;
-        let size_bytes = size.0.unwrap_or(0);
+        let size: Option<(Option<i64>,)> = self.query_optional(&size_query, SqliteArguments::default()).await?;
+        let size_bytes = size.and_then(|size| size.0).unwrap_or(0);
         
         Ok(TableStats {
             table_name: table.to_string(),
@@ -315,14 +314,15 @@ impl DatabaseOperations {
             VALUES (?, ?, ?, ?, ?, ?)
         "#;
         
-        self.execute_simple_query(query).await?;
-        
-        // Get the last insert ID
-        let result: (i64,) = sqlx::query_as("SELECT last_insert_rowid()")
-            .fetch_one(&self.pool)
-            .await?;
-        
-        Ok(result.0)
+        let mut params = SqliteArguments::default();
+        params.add(id);
+        params.add(content);
+        params.add(timestamp);
+        params.add(layer);
+        params.add(emotional_tags);
+        params.add(context_associations);
+
+        Ok(self.execute_query(query, params).await?.last_insert_rowid())
     }
 
     /// Get memory records by layer
@@ -331,24 +331,23 @@ impl DatabaseOperations {
         layer: &str,
         limit: Option<i64>,
     ) -> Result<Vec<MemoryRecord>> {
+        let mut params = SqliteArguments::default();
+        params.add(layer);
         let query = if let Some(limit) = limit {
-            format!(
-                "SELECT id, content, timestamp, layer, emotional_tags, context_associations 
-                 FROM memory_records 
-                 WHERE layer = ? 
-                 ORDER BY timestamp DESC 
-                 LIMIT ?"
-            )
+            params.add(limit);
+            "SELECT id, content, timestamp, layer, emotional_tags, context_associations, created_at, updated_at
+             FROM memory_records 
+             WHERE layer = ? 
+             ORDER BY timestamp DESC 
+             LIMIT ?"
         } else {
-            format!(
-                "SELECT id, content, timestamp, layer, emotional_tags, context_associations 
-                 FROM memory_records 
-                 WHERE layer = ? 
-                 ORDER BY timestamp DESC"
-            )
+            "SELECT id, content, timestamp, layer, emotional_tags, context_associations, created_at, updated_at
+             FROM memory_records 
+             WHERE layer = ? 
+             ORDER BY timestamp DESC"
         };
         
-        self.query_all(&query).await
+        self.query_all(query, params).await
     }
 
     /// Update memory record emotional tags
@@ -357,15 +356,140 @@ impl DatabaseOperations {
         id: &str,
         emotional_tags: &str,
     ) -> Result<u64> {
-        let query = format!(
-            "UPDATE memory_records SET emotional_tags = '{}', updated_at = CURRENT_TIMESTAMP WHERE id = '{}'",
-            emotional_tags, id
-        );
-        
-        self.update(&query).await
+        let mut params = SqliteArguments::default();
+        params.add(emotional_tags);
+        params.add(id);
+
+        self.update(
+            "memory_records",
+            &["emotional_tags = ?", "updated_at = CURRENT_TIMESTAMP"],
+            "id = ?",
+            params,
+        )
+        .await
+    }
+
+    /// Insert or overwrite memory records in a single transaction
+    pub async fn upsert_memory_records(&self, records: &[memory::MemoryRecord]) -> Result<()> {
+        let start = Instant::now();
+        let mut tx = self.pool.begin().await?;
+
+        for record in records {
+            sqlx::query(
+                r#"
+                INSERT INTO memory_records (
+                    id, content, timestamp, layer, namespace, emotional_tags, tags, entities,
+                    source, importance, pinned, reinforcements, last_reinforced, derived_from,
+                    embedding
+                )
+                VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+                ON CONFLICT(id) DO UPDATE SET
+                    content = excluded.content,
+                    timestamp = excluded.timestamp,
+                    layer = excluded.layer,
+                    namespace = excluded.namespace,
+                    emotional_tags = excluded.emotional_tags,
+                    tags = excluded.tags,
+                    entities = excluded.entities,
+                    source = excluded.source,
+                    importance = excluded.importance,
+                    pinned = excluded.pinned,
+                    reinforcements = excluded.reinforcements,
+                    last_reinforced = excluded.last_reinforced,
+                    derived_from = excluded.derived_from,
+                    embedding = excluded.embedding,
+                    updated_at = CURRENT_TIMESTAMP
+                "#,
+            )
+            .bind(&record.id)
+            .bind(&record.content)
+            .bind(record.timestamp)
+            .bind(record.layer.as_str())
+            .bind(&record.namespace)
+            .bind(serde_json::to_string(&record.emotional_tags)?)
+            .bind(serde_json::to_string(&record.tags)?)
+            .bind(serde_json::to_string(&record.entities)?)
+            .bind(&record.source)
+            .bind(record.importance as f64)
+            .bind(record.pinned)
+            .bind(record.reinforcements as i64)
+            .bind(record.last_reinforced)
+            .bind(serde_json::to_string(&record.derived_from)?)
+            .bind(record.embedding.as_deref().map(vector_to_bytes))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        self.metrics.record_query(start.elapsed()).await;
+        Ok(())
+    }
+
+    /// Delete a memory record if it is stored in `layer`
+    ///
+    /// Returns `false` if there was no such row.
+    pub async fn delete_memory_record(&self, layer: MemoryLayer, id: &str) -> Result<bool> {
+        let mut params = SqliteArguments::default();
+        params.add(id);
+        params.add(layer.as_str());
+
+        Ok(self.delete("memory_records", "id = ? AND layer = ?", params).await? > 0)
+    }
+
+    /// Every memory record stored in a layer, oldest first
+    pub async fn memory_records_in_layer(&self, layer: MemoryLayer) -> Result<Vec<memory::MemoryRecord>> {
+        let start = Instant::now();
+        let rows = sqlx::query(
+            r#"
+            SELECT id, content, timestamp, layer, namespace, emotional_tags, tags, entities,
+                   source, importance, pinned, reinforcements, last_reinforced, derived_from,
+                   embedding
+            FROM memory_records
+            WHERE layer = ?
+            ORDER BY timestamp
+            "#,
+        )
+        .bind(layer.as_str())
+        .fetch_all(&self.pool)
+        .await?;
+        self.metrics.record_query(start.elapsed()).await;
+
+        rows.iter().map(|row| memory_record_from_row(row, layer)).collect()
     }
 }
 
+fn memory_record_from_row(row: &SqliteRow, layer: MemoryLayer) -> Result<memory::MemoryRecord> {
+    let labels = |column: &str| -> Result<Vec<String>> {
+        match row.try_get::<Option<String>, _>(column)? {
+            Some(json) => Ok(serde_json::from_str(&json)?),
+            None => Ok(Vec::new()),
+        }
+    };
+
+    Ok(memory::MemoryRecord {
+        id: row.try_get("id")?,
+        content: row.try_get("content")?,
+        timestamp: row.try_get::<chrono::DateTime<chrono::Utc>, _>("timestamp")?,
+        layer,
+        namespace: row.try_get("namespace")?,
+        tags: labels("tags")?,
+        entities: labels("entities")?,
+        source: row.try_get("source")?,
+        importance: row.try_get::<f64, _>("importance")? as f32,
+        emotional_tags: labels("emotional_tags")?,
+        pinned: row.try_get("pinned")?,
+        reinforcements: row.try_get::<i64, _>("reinforcements")?.max(0) as u64,
+        last_reinforced: row.try_get("last_reinforced")?,
+        derived_from: labels("derived_from")?,
+        embedding: row
+            .try_get::<Option<Vec<u8>>, _>("embedding")?
+            .map(|bytes| vector_from_bytes(&bytes)),
+        score: None,
+        snippet: None,
+        explanation: None,
+    })
+}
+
 /// Memory record model
 #[derive(Debug, Clone, sqlx::FromRow)]
 pub struct MemoryRecord {
@@ -382,33 +506,33 @@ pub struct MemoryRecord {
 #[cfg(test)]
 mod tests {
     use super::*;
-    use crate::db::{init_db_with_config, DbMetrics, DatabaseConfig};
-    use tempfile::TempDir;
+    use crate::db::DbMetrics;
+    use crate::memory::store::test_pool;
 
     #[tokio::test]
     async fn test_database_operations() {
-        let temp_dir = TempDir::new().unwrap();
-        let db_path = temp_dir.path().join("test.db");
-        
-        let config = DatabaseConfig {
-            max_connections: 1,
-            ..Default::default()
-        };
-        
-        let pool = init_db_with_config(config).await.unwrap();
-        let metrics = DbMetrics::new();
-        let ops = DatabaseOperations::new(pool, metrics);
-        
+        let ops = DatabaseOperations::new(test_pool().await, DbMetrics::new());
+
         // Test basic operations
-        let result: (i64,) = ops.query_one("SELECT 1 as test").await.unwrap();
+        let result: (i64,) = ops.query_one("SELECT 1 as test", SqliteArguments::default()).await.unwrap();
         assert_eq!(result.0, 1);
-        
+
+        let timestamp = chrono::Utc::now();
+        ops.insert_memory_record("record-1", "Recital on Friday", &timestamp, "episodic", None, None)
+            .await
+            .unwrap();
+        ops.update_memory_emotional_tags("record-1", "[\"joy\"]").await.unwrap();
+        let records = ops.get_memory_records_by_layer("episodic", Some(10)).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].emotional_tags.as_deref(), Some("[\"joy\"]"));
+
         // Test exists
-        let exists = ops.exists("SELECT EXISTS(SELECT 1 FROM sqlite_master WHERE type = 'table') as exists").await.unwrap();
-        assert!(exists);
-        
+        let mut params = SqliteArguments::default();
+        params.add("record-1");
+        assert!(ops.exists("memory_records", "id = ?", params).await.unwrap());
+
         // Test count
-        let count = ops.count("SELECT COUNT(*) as count FROM sqlite_master").await.unwrap();
-        assert!(count > 0);
+        let count = ops.count("memory_records", None, SqliteArguments::default()).await.unwrap();
+        assert_eq!(count, 1);
     }
-}
\ No newline at end of file
+}
@@ -20,6 +20,6 @@ pub use config::{Config, ConsciousnessConfig, SoulConfig};
 pub use llm::OpenRouterClient;
 pub use mqtt::{MqttClient, MqttConfig, MqttError};
 pub use soul::{Emotion, SoulEntity, SoulStorage, TrustCalculator, EmpathyScorer};
-pub use security::{JwtAuth, JwtClaims, AuthError, SecurityHeadersLayer, RateLimitLayer};
+pub use security::{JwtAuth, JwtClaims, AuthError, SecurityHeadersLayer};
 pub use backup::{BackupManager, BackupConfig, BackupManifest, BackupResult, RestoreManager, RestoreResult, BackupScheduler, ScheduleConfig};
 
@@ -1,4 +1,6 @@
 use crate::config::Config;
+use crate::memory::LanguageModel;
+use futures::future::BoxFuture;
 use serde::{Deserialize, Serialize};
 use std::sync::Arc;
 
@@ -98,3 +100,12 @@ impl OpenRouterClient {
     }
 }
 
+impl LanguageModel for OpenRouterClient {
+    /// Complete a prompt at a low temperature, for extraction tasks
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, anyhow::Result<String>> {
+        Box::pin(self.chat(
+            vec![("user".to_string(), prompt.to_string())],
+            Some(0.2),
+        ))
+    }
+}
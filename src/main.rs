@@ -2,11 +2,7 @@ use anyhow::Result;
 use jamey_3::api::create_app;
 use jamey_3::config::Config;
 use jamey_3::db::init_db_with_config;
-use opentelemetry::{global, sdk::propagation::TraceContextPropagator};
 use tracing::info;
-use tracing_subscriber::{prelude::*, EnvFilter, Registry};
-use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
-use opentelemetry_otlp::WithExportConfig;
 use jamey_3::telemetry::init_telemetry;
 
 #[tokio::main]
@@ -37,12 +33,12 @@ async fn main() -> Result<()> {
 
     // Initialize database with configuration
     let db_config = config.as_ref().map(|c| c.database.clone()).unwrap_or_default();
-    init_db_with_config(db_config).await?;
+    let pool = init_db_with_config(db_config.clone()).await?;
     info!("Database initialized with configuration: max_connections={}, enable_metrics={}",
           db_config.max_connections, db_config.enable_metrics);
 
     // Create and run the Axum app
-    let app = create_app().await?;
+    let app = create_app(pool, config).await?;
     
     // Get server binding configuration from environment
     let host = std::env::var("SERVER_HOST").unwrap_or_else(|_| "127.0.0.1".to_string());
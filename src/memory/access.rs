@@ -0,0 +1,414 @@
+//! Access statistics of memory records
+//!
+//! Every search counts one retrieval of each record it returns, along with
+//! the time and the query. With a database attached the statistics are kept
+//! in the `memory_access` table, so they survive restarts without rewriting
+//! the index; otherwise they are kept in memory since startup.
+//!
+//! Searches rank frequently retrieved records higher, consolidation promotes
+//! them, and [`MemorySystem::access_report`] lists the hottest and the
+//! never-retrieved records of a layer.
+
+use std::collections::HashMap;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tracing::warn;
+
+use crate::config::env_or;
+use super::{MemoryLayer, MemoryQuery, MemoryRecord, MemorySystem};
+
+/// Most IDs bound to a single statement
+const MAX_BOUND_IDS: usize = 500;
+
+/// How much access statistics weigh in search ranking
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessConfig {
+    /// Score boost of the most retrieved records, as a fraction of their
+    /// score (0.0 disables the boost)
+    pub boost_weight: f32,
+    /// Retrieval count at which the boost saturates
+    pub boost_saturation: u64,
+    /// Number of distinct queries remembered per record
+    pub max_queries: usize,
+}
+
+impl Default for AccessConfig {
+    fn default() -> Self {
+        Self {
+            boost_weight: 0.2,
+            boost_saturation: 20,
+            max_queries: 5,
+        }
+    }
+}
+
+impl AccessConfig {
+    /// Load access tracking configuration from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            boost_weight: env_or("MEMORY_ACCESS_BOOST_WEIGHT", defaults.boost_weight),
+            boost_saturation: env_or("MEMORY_ACCESS_BOOST_SATURATION", defaults.boost_saturation),
+            max_queries: env_or("MEMORY_ACCESS_MAX_QUERIES", defaults.max_queries),
+        }
+    }
+
+    /// Score multiplier of a record retrieved `retrievals` times
+    ///
+    /// Grows logarithmically from 1.0 for records never retrieved to
+    /// `1 + boost_weight` at `boost_saturation` retrievals.
+    pub fn boost(&self, retrievals: u64) -> f32 {
+        if self.boost_weight <= 0.0 || retrievals == 0 {
+            return 1.0;
+        }
+        let saturation = self.boost_saturation.max(1) as f32;
+        let level = ((retrievals as f32).ln_1p() / saturation.ln_1p()).min(1.0);
+        1.0 + self.boost_weight * level
+    }
+}
+
+/// How often and how recently a record was retrieved
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AccessStats {
+    pub memory_id: String,
+    pub retrievals: u64,
+    pub last_accessed: DateTime<Utc>,
+    /// Latest distinct queries that returned the record, newest first
+    pub queries: Vec<String>,
+}
+
+impl AccessStats {
+    fn new(memory_id: impl Into<String>, at: DateTime<Utc>) -> Self {
+        Self {
+            memory_id: memory_id.into(),
+            retrievals: 0,
+            last_accessed: at,
+            queries: Vec::new(),
+        }
+    }
+
+    /// Count one retrieval by `query`; empty queries (listings) are not
+    /// remembered
+    fn record(&mut self, query: &str, at: DateTime<Utc>, max_queries: usize) {
+        self.retrievals += 1;
+        self.last_accessed = at;
+        let query = query.trim();
+        if !query.is_empty() {
+            self.queries.retain(|q| q != query);
+            self.queries.insert(0, query.to_string());
+            self.queries.truncate(max_queries);
+        }
+    }
+}
+
+/// Reads and writes access statistics in the `memory_access` table
+#[derive(Clone)]
+pub struct AccessStore {
+    pool: SqlitePool,
+}
+
+impl AccessStore {
+    pub fn new(pool: SqlitePool) -> Self {
+        Self { pool }
+    }
+
+    /// Count one retrieval by `query` of every record in `ids`, in a single
+    /// transaction
+    pub async fn record(&self, ids: &[String], query: &str, at: DateTime<Utc>, max_queries: usize) -> Result<()> {
+        let mut tx = self.pool.begin().await?;
+
+        for id in ids {
+            let row = sqlx::query(
+                "SELECT memory_id, retrievals, last_accessed, queries FROM memory_access WHERE memory_id = ?",
+            )
+            .bind(id)
+            .fetch_optional(&mut *tx)
+            .await?;
+            let mut stats = match row {
+                Some(row) => stats_from_row(&row)?,
+                None => AccessStats::new(id.as_str(), at),
+            };
+            stats.record(query, at, max_queries);
+
+            sqlx::query(
+                r#"
+                INSERT INTO memory_access (memory_id, retrievals, last_accessed, queries)
+                VALUES (?, ?, ?, ?)
+                ON CONFLICT(memory_id) DO UPDATE SET
+                    retrievals = excluded.retrievals,
+                    last_accessed = excluded.last_accessed,
+                    queries = excluded.queries
+                "#,
+            )
+            .bind(&stats.memory_id)
+            .bind(stats.retrievals as i64)
+            .bind(stats.last_accessed)
+            .bind(serde_json::to_string(&stats.queries)?)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Statistics of the records in `ids` that were ever retrieved
+    pub async fn get(&self, ids: &[String]) -> Result<HashMap<String, AccessStats>> {
+        let mut stats = HashMap::new();
+        for chunk in ids.chunks(MAX_BOUND_IDS) {
+            let sql = format!(
+                "SELECT memory_id, retrievals, last_accessed, queries FROM memory_access WHERE memory_id IN ({})",
+                vec!["?"; chunk.len()].join(", ")
+            );
+            let mut query = sqlx::query(&sql);
+            for id in chunk {
+                query = query.bind(id);
+            }
+            for row in query.fetch_all(&self.pool).await? {
+                let record_stats = stats_from_row(&row)?;
+                stats.insert(record_stats.memory_id.clone(), record_stats);
+            }
+        }
+        Ok(stats)
+    }
+
+    /// Forget the statistics of a deleted record
+    pub async fn delete(&self, id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM memory_access WHERE memory_id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(())
+    }
+}
+
+fn stats_from_row(row: &SqliteRow) -> Result<AccessStats> {
+    Ok(AccessStats {
+        memory_id: row.try_get("memory_id")?,
+        retrievals: row.try_get::<i64, _>("retrievals")?.max(0) as u64,
+        last_accessed: row.try_get("last_accessed")?,
+        queries: serde_json::from_str(&row.try_get::<String, _>("queries")?)?,
+    })
+}
+
+/// A record with its access statistics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccessedMemory {
+    pub record: MemoryRecord,
+    pub access: AccessStats,
+}
+
+/// Which records of a layer are used and which are not
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerAccessReport {
+    pub layer: MemoryLayer,
+    /// Records in the layer
+    pub records: usize,
+    /// Records retrieved at least once
+    pub accessed: usize,
+    /// Most retrieved records, most retrieved first
+    pub hottest: Vec<AccessedMemory>,
+    /// Records never retrieved, oldest first
+    pub never_accessed: Vec<MemoryRecord>,
+}
+
+impl MemorySystem {
+    /// Replace the default access ranking configuration
+    pub fn with_access_config(mut self, config: AccessConfig) -> Self {
+        self.access_config = config;
+        self
+    }
+
+    /// Access statistics of the records in `ids`, by ID; records never
+    /// retrieved are absent
+    pub async fn access_stats(&self, ids: &[String]) -> Result<HashMap<String, AccessStats>> {
+        match &self.access_store {
+            Some(store) => store.get(ids).await,
+            None => Ok(ids
+                .iter()
+                .filter_map(|id| self.access.get(id).map(|stats| (id.clone(), stats.clone())))
+                .collect()),
+        }
+    }
+
+    /// The `limit` hottest and never-retrieved records of a layer among those
+    /// passing `include`
+    pub async fn access_report(
+        &self,
+        layer: MemoryLayer,
+        limit: usize,
+        include: impl Fn(&MemoryRecord) -> bool,
+    ) -> Result<LayerAccessReport> {
+        self.flush().await?;
+        let mut records: Vec<MemoryRecord> = self.all_records(layer)?.into_iter().filter(|r| include(r)).collect();
+        let ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+        let mut stats = self.access_stats(&ids).await?;
+
+        let total = records.len();
+        records.sort_by_key(|record| record.timestamp);
+        let (accessed, never_accessed): (Vec<_>, Vec<_>) =
+            records.into_iter().partition(|record| stats.contains_key(&record.id));
+
+        let mut hottest: Vec<AccessedMemory> = accessed
+            .into_iter()
+            .filter_map(|record| {
+                let access = stats.remove(&record.id)?;
+                Some(AccessedMemory { record, access })
+            })
+            .collect();
+        let accessed = hottest.len();
+        hottest.sort_by(|a, b| {
+            b.access
+                .retrievals
+                .cmp(&a.access.retrievals)
+                .then(b.access.last_accessed.cmp(&a.access.last_accessed))
+        });
+        hottest.truncate(limit);
+
+        Ok(LayerAccessReport {
+            layer,
+            records: total,
+            accessed,
+            hottest,
+            never_accessed: never_accessed.into_iter().take(limit).collect(),
+        })
+    }
+
+    /// Number of results to fetch so that the access boost can lift records
+    /// from just below the top `limit`
+    pub(crate) fn access_candidates(&self, limit: usize) -> usize {
+        if self.access_config.boost_weight > 0.0 {
+            limit.max(super::HYBRID_CANDIDATES)
+        } else {
+            limit
+        }
+    }
+
+    /// Multiply each record's score by its access boost, then sort the
+    /// records by score
+    pub(crate) async fn boost_by_access(&self, records: &mut [MemoryRecord]) {
+        if self.access_config.boost_weight > 0.0 && !records.is_empty() {
+            let ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+            match self.access_stats(&ids).await {
+                Ok(stats) => {
+                    for record in records.iter_mut() {
+                        let retrievals = stats.get(&record.id).map_or(0, |stats| stats.retrievals);
+                        record.score = record.score.map(|score| score * self.access_config.boost(retrievals));
+                    }
+                }
+                Err(e) => warn!("Failed to load access statistics, ranking without them: {}", e),
+            }
+        }
+        records.sort_by(|a, b| {
+            b.score
+                .partial_cmp(&a.score)
+                .unwrap_or(std::cmp::Ordering::Equal)
+        });
+    }
+
+    /// Count one retrieval by `query` of every record
+    ///
+    /// Queries without words to rank by only list the records that pass
+    /// their filters, so they are not counted.
+    pub(crate) async fn record_access(&self, records: &[MemoryRecord], query: &str) {
+        let has_text = query.parse::<MemoryQuery>().is_ok_and(|query| !query.text().is_empty());
+        if !has_text || records.is_empty() {
+            return;
+        }
+
+        let now = Utc::now();
+        let max_queries = self.access_config.max_queries;
+        for record in records {
+            self.access
+                .entry(record.id.clone())
+                .or_insert_with(|| AccessStats::new(record.id.as_str(), now))
+                .record(query, now, max_queries);
+        }
+
+        if let Some(store) = &self.access_store {
+            let ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+            if let Err(e) = store.record(&ids, query, now, max_queries).await {
+                warn!("Failed to persist access statistics: {}", e);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::store::test_pool;
+    use crate::memory::{NewMemory, SearchOptions};
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_boost_saturates() {
+        let config = AccessConfig::default();
+        assert_eq!(config.boost(0), 1.0);
+        assert!(config.boost(1) > 1.0);
+        assert!(config.boost(5) > config.boost(1));
+        assert!((config.boost(20) - 1.2).abs() < 1e-6);
+        assert_eq!(config.boost(1000), config.boost(20));
+    }
+
+    #[tokio::test]
+    async fn test_tracks_and_boosts_retrievals() {
+        let dir = TempDir::new().unwrap();
+        let pool = test_pool().await;
+        let memory = MemorySystem::new(dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_database(pool.clone());
+        let recital = memory
+            .store_with(MemoryLayer::Episodic, NewMemory::new("Piano recital on Friday"))
+            .await
+            .unwrap();
+        let lesson = memory
+            .store_with(MemoryLayer::Episodic, NewMemory::new("Piano lesson on Monday"))
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+
+        for query in ["recital", "Friday recital", "recital"] {
+            memory.search(MemoryLayer::Episodic, query, 10).await.unwrap();
+        }
+        // Listing by filters alone is not a retrieval
+        for query in ["", "tag:music", "-lesson"] {
+            memory.search(MemoryLayer::Episodic, query, 10).await.unwrap();
+        }
+        let unrecorded = SearchOptions::new(10).without_access_recording();
+        memory
+            .search_with(MemoryLayer::Episodic, "recital", &unrecorded)
+            .await
+            .unwrap();
+        let stats = memory.access_stats(&[recital.clone(), lesson.clone()]).await.unwrap();
+        assert_eq!(stats[&recital].retrievals, 3);
+        assert_eq!(stats[&recital].queries, vec!["recital", "Friday recital"]);
+        assert!(!stats.contains_key(&lesson));
+
+        // Equal BM25 scores; the often retrieved record ranks first
+        let results = memory
+            .search_with(MemoryLayer::Episodic, "piano", &SearchOptions::new(1))
+            .await
+            .unwrap();
+        assert_eq!(results[0].id, recital);
+
+        // Statistics are persisted, not kept in the index
+        drop(memory);
+        let reopened = MemorySystem::new(dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_database(pool);
+        let report = reopened.access_report(MemoryLayer::Episodic, 10, |_| true).await.unwrap();
+        assert_eq!(report.records, 2);
+        assert_eq!(report.accessed, 1);
+        assert_eq!(report.hottest[0].record.id, recital);
+        assert_eq!(report.hottest[0].access.retrievals, 4);
+        assert_eq!(report.never_accessed[0].id, lesson);
+        assert_eq!(reopened.get(&recital).await.unwrap().unwrap().reinforcements, 0);
+    }
+}
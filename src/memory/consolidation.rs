@@ -0,0 +1,401 @@
+//! Memory Consolidation
+//!
+//! Periodically scans the ShortTerm layer, scores each record by recency,
+//! retrieval frequency and emotional weight, and promotes salient records
+//! into the LongTerm, Episodic or Semantic layers. Whatever is left in
+//! ShortTerm after its retention window is expired.
+
+use std::collections::HashSet;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+use uuid::Uuid;
+
+use crate::config::env_or;
+use super::{MemoryLayer, MemoryRecord, MemorySystem};
+
+/// Words that mark a memory as emotionally charged
+const EMOTIONAL_LEXICON: &[&str] = &[
+    "love", "loved", "proud", "pride", "joy", "happy", "afraid", "fear", "scared",
+    "worried", "worry", "angry", "anger", "sad", "cry", "hurt", "harm", "danger",
+    "safe", "protect", "grateful", "excited", "lonely", "miss", "hate",
+];
+
+/// Consolidation thresholds and weights
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidationConfig {
+    /// How often the background task runs, in seconds
+    pub interval_secs: u64,
+    /// Records younger than this are left alone, in seconds
+    pub min_age_secs: u64,
+    /// Unpromoted records older than this are expired, in seconds
+    pub expire_after_secs: u64,
+    /// Half-life of the recency component, in seconds
+    pub recency_half_life_secs: u64,
+    /// Weight of the recency component (0.0 to 1.0)
+    pub recency_weight: f64,
+    /// Weight of the retrieval component (0.0 to 1.0)
+    pub retrieval_weight: f64,
+    /// Weight of the emotional component (0.0 to 1.0)
+    pub emotion_weight: f64,
+    /// Retrieval count at which the retrieval component saturates
+    pub retrieval_saturation: u64,
+    /// Minimum combined score for a record to be promoted
+    pub promote_threshold: f64,
+    /// Emotional weight at which a record is routed to Episodic
+    pub episodic_emotion_threshold: f64,
+    /// Retrieval count at which a record is routed to Semantic
+    pub semantic_retrieval_threshold: u64,
+}
+
+impl Default for ConsolidationConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 300,          // 5 minutes
+            min_age_secs: 600,           // 10 minutes
+            expire_after_secs: 86400,    // 24 hours
+            recency_half_life_secs: 21600, // 6 hours
+            recency_weight: 0.2,
+            retrieval_weight: 0.4,
+            emotion_weight: 0.4,
+            retrieval_saturation: 5,
+            promote_threshold: 0.35,
+            episodic_emotion_threshold: 0.5,
+            semantic_retrieval_threshold: 3,
+        }
+    }
+}
+
+impl ConsolidationConfig {
+    /// Load consolidation configuration from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            interval_secs: env_or("MEMORY_CONSOLIDATION_INTERVAL_SECS", defaults.interval_secs),
+            min_age_secs: env_or("MEMORY_CONSOLIDATION_MIN_AGE_SECS", defaults.min_age_secs),
+            expire_after_secs: env_or("MEMORY_CONSOLIDATION_EXPIRE_AFTER_SECS", defaults.expire_after_secs),
+            recency_half_life_secs: env_or("MEMORY_CONSOLIDATION_RECENCY_HALF_LIFE_SECS", defaults.recency_half_life_secs),
+            recency_weight: env_or("MEMORY_CONSOLIDATION_RECENCY_WEIGHT", defaults.recency_weight),
+            retrieval_weight: env_or("MEMORY_CONSOLIDATION_RETRIEVAL_WEIGHT", defaults.retrieval_weight),
+            emotion_weight: env_or("MEMORY_CONSOLIDATION_EMOTION_WEIGHT", defaults.emotion_weight),
+            retrieval_saturation: env_or("MEMORY_CONSOLIDATION_RETRIEVAL_SATURATION", defaults.retrieval_saturation),
+            promote_threshold: env_or("MEMORY_CONSOLIDATION_PROMOTE_THRESHOLD", defaults.promote_threshold),
+            episodic_emotion_threshold: env_or("MEMORY_CONSOLIDATION_EPISODIC_EMOTION_THRESHOLD", defaults.episodic_emotion_threshold),
+            semantic_retrieval_threshold: env_or("MEMORY_CONSOLIDATION_SEMANTIC_RETRIEVAL_THRESHOLD", defaults.semantic_retrieval_threshold),
+        }
+    }
+
+    /// Get the run interval as Duration
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+/// Salience score for a single ShortTerm record
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct SalienceScore {
+    /// Recency component (0.0 to 1.0)
+    pub recency: f64,
+    /// Retrieval component (0.0 to 1.0)
+    pub retrieval: f64,
+    /// Emotional component (0.0 to 1.0)
+    pub emotion: f64,
+    /// Weighted combination of the three components
+    pub total: f64,
+}
+
+/// A record that was moved or copied out of ShortTerm
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Promotion {
+    /// Id of the ShortTerm record
+    pub source_id: String,
+    /// Id of the record in the target layer (same as `source_id` for moves)
+    pub target_id: String,
+    /// Layer the record was promoted into
+    pub target: MemoryLayer,
+    /// Score that triggered the promotion
+    pub score: f64,
+}
+
+/// Outcome of a single consolidation run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsolidationReport {
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    /// How long the run took, in milliseconds
+    pub duration_ms: u64,
+    /// Number of ShortTerm records examined
+    pub scanned: usize,
+    /// Records moved or copied into other layers
+    pub promoted: Vec<Promotion>,
+    /// Ids of ShortTerm records that expired without being promoted
+    pub expired: Vec<String>,
+    /// Records left in ShortTerm for a later run
+    pub retained: usize,
+}
+
+/// Runs consolidation passes over a memory system
+pub struct Consolidator {
+    memory: Arc<MemorySystem>,
+    config: ConsolidationConfig,
+    last_report: Arc<RwLock<Option<ConsolidationReport>>>,
+}
+
+impl Consolidator {
+    /// Create a new consolidator
+    pub fn new(memory: Arc<MemorySystem>, config: ConsolidationConfig) -> Self {
+        Self {
+            memory,
+            config,
+            last_report: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Get the report of the most recent run, if any
+    pub async fn last_report(&self) -> Option<ConsolidationReport> {
+        self.last_report.read().await.clone()
+    }
+
+    /// Score a record's salience
+    pub fn score(&self, record: &MemoryRecord, retrievals: u64, now: DateTime<Utc>) -> SalienceScore {
+        let age_secs = (now - record.timestamp).num_seconds().max(0) as f64;
+        let half_life = self.config.recency_half_life_secs.max(1) as f64;
+        let recency = 0.5f64.powf(age_secs / half_life);
+
+        let saturation = self.config.retrieval_saturation.max(1) as f64;
+        let retrieval = (retrievals as f64 / saturation).min(1.0);
+
+        let emotion = emotional_weight(&record.content, &record.emotional_tags);
+
+        let total = recency * self.config.recency_weight
+            + retrieval * self.config.retrieval_weight
+            + emotion * self.config.emotion_weight;
+
+        SalienceScore {
+            recency,
+            retrieval,
+            emotion,
+            total,
+        }
+    }
+
+    /// Decide which layers a salient record belongs in
+    fn targets(&self, score: &SalienceScore, retrievals: u64) -> Vec<MemoryLayer> {
+        let mut targets = Vec::new();
+        if score.emotion >= self.config.episodic_emotion_threshold {
+            targets.push(MemoryLayer::Episodic);
+        }
+        if retrievals >= self.config.semantic_retrieval_threshold {
+            targets.push(MemoryLayer::Semantic);
+        }
+        if targets.is_empty() {
+            targets.push(MemoryLayer::LongTerm);
+        }
+        targets
+    }
+
+    /// Run a single consolidation pass over ShortTerm
+    ///
+    /// The first target layer receives the record under its original id so
+    /// that soul memory links keep resolving; any further targets receive a
+    /// copy under a fresh id. Promoted records are removed from ShortTerm.
+    pub async fn run_once(&self) -> Result<ConsolidationReport> {
+        let started_at = Utc::now();
+        let start = std::time::Instant::now();
+
+        // Make every acknowledged store visible before scanning
+        self.memory.flush().await?;
+        let records = self.memory.all_records(MemoryLayer::ShortTerm)?;
+        let min_age = chrono::Duration::seconds(self.config.min_age_secs as i64);
+        let expire_after = chrono::Duration::seconds(self.config.expire_after_secs as i64);
+
+        let mut promoted = Vec::new();
+        let mut expired = Vec::new();
+        let mut retained = 0;
+        let mut seen = HashSet::new();
+
+        let ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+        let access = self.memory.access_stats(&ids).await?;
+
+        for record in &records {
+            if !seen.insert(record.id.clone()) {
+                continue;
+            }
+
+            let age = started_at - record.timestamp;
+            if age < min_age {
+                retained += 1;
+                continue;
+            }
+
+            let retrievals = access.get(&record.id).map_or(0, |stats| stats.retrievals);
+            let score = self.score(record, retrievals, started_at);
+
+            if score.total >= self.config.promote_threshold {
+                for (i, target) in self.targets(&score, retrievals).into_iter().enumerate() {
+                    let target_id = if i == 0 {
+                        record.id.clone()
+                    } else {
+                        Uuid::new_v4().to_string()
+                    };
+                    self.memory
+                        .write_record(&MemoryRecord {
+                            id: target_id.clone(),
+                            layer: target,
+                            ..record.clone()
+                        })
+                        .await?;
+                    promoted.push(Promotion {
+                        source_id: record.id.clone(),
+                        target_id,
+                        target,
+                        score: score.total,
+                    });
+                }
+                self.memory.remove_record(MemoryLayer::ShortTerm, &record.id).await?;
+            } else if age >= expire_after && !record.pinned {
+                self.memory.forget_record(MemoryLayer::ShortTerm, &record.id).await?;
+                expired.push(record.id.clone());
+            } else {
+                retained += 1;
+            }
+        }
+
+        self.memory.flush().await?;
+
+        let report = ConsolidationReport {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as u64,
+            scanned: records.len(),
+            promoted,
+            expired,
+            retained,
+        };
+
+        counter!("memory.consolidation.runs_total", 1);
+        counter!("memory.consolidation.promoted_total", report.promoted.len() as u64);
+        counter!("memory.consolidation.expired_total", report.expired.len() as u64);
+        gauge!("memory.consolidation.short_term_retained", report.retained as f64);
+
+        info!(
+            "Memory consolidation: scanned {}, promoted {}, expired {}, retained {} in {}ms",
+            report.scanned,
+            report.promoted.len(),
+            report.expired.len(),
+            report.retained,
+            report.duration_ms
+        );
+        for promotion in &report.promoted {
+            info!(
+                "  {} -> {:?} as {} (score {:.2})",
+                promotion.source_id, promotion.target, promotion.target_id, promotion.score
+            );
+        }
+
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Spawn the consolidation loop on the Tokio runtime
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval());
+            // The first tick completes immediately; skip it so startup isn't slowed down
+            ticker.tick().await;
+            info!("Memory consolidation started (every {:?})", self.config.interval());
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    error!("Memory consolidation failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Estimate the emotional weight of a piece of content (0.0 to 1.0)
+///
+/// Each distinct emotional tag counts like a lexicon word in the content.
+pub fn emotional_weight(content: &str, emotional_tags: &[String]) -> f64 {
+    let lower = content.to_lowercase();
+    let words = lower
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| EMOTIONAL_LEXICON.contains(word))
+        .count();
+    let tags = emotional_tags
+        .iter()
+        .map(|tag| tag.trim().to_lowercase())
+        .filter(|tag| !tag.is_empty())
+        .collect::<HashSet<_>>()
+        .len();
+    let hits = words + tags;
+    let exclamations = content.chars().filter(|&c| c == '!').count().min(2);
+
+    ((hits as f64 + exclamations as f64 * 0.5) / 3.0).min(1.0)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::NewMemory;
+    use tempfile::TempDir;
+
+    fn aged(content: &str, hours: i64) -> MemoryRecord {
+        NewMemory::new(content).into_record(
+            MemoryLayer::ShortTerm,
+            Utc::now() - chrono::Duration::hours(hours),
+        )
+    }
+
+    #[test]
+    fn test_emotional_weight() {
+        assert_eq!(emotional_weight("Checked the router logs", &[]), 0.0);
+        assert!(emotional_weight("So proud of Phoenix, I love her!", &[]) > 0.5);
+
+        // Tags weigh in even when the content has no emotional words, and
+        // repeated tags count once
+        let tags = ["joy".to_string(), "Joy".to_string(), "pride".to_string()];
+        let tagged = emotional_weight("Phoenix played at the recital", &tags);
+        assert!((tagged - 2.0 / 3.0).abs() < 1e-9);
+        assert!(emotional_weight("Phoenix played at the recital!", &tags) > tagged);
+    }
+
+    #[tokio::test]
+    async fn test_consolidation_routes_and_expires() {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = Arc::new(MemorySystem::new(temp_dir.path().to_path_buf()).await.unwrap());
+
+        let emotional = aged("I was so proud and happy when Phoenix sang, I love her", 1);
+        let mundane_old = aged("Rotated the backup keys", 48);
+        let fresh = aged("Just now: checked the weather", 0);
+        for record in [&emotional, &mundane_old, &fresh] {
+            memory.write_record(record).await.unwrap();
+        }
+
+        let consolidator = Consolidator::new(memory.clone(), ConsolidationConfig::default());
+        let report = consolidator.run_once().await.unwrap();
+
+        assert_eq!(report.scanned, 3);
+        assert_eq!(report.promoted.len(), 1);
+        assert_eq!(report.promoted[0].source_id, emotional.id);
+        assert_eq!(report.promoted[0].target, MemoryLayer::Episodic);
+        assert_eq!(report.expired, vec![mundane_old.id.clone()]);
+        assert_eq!(report.retained, 1);
+
+        let short_term = memory.all_records(MemoryLayer::ShortTerm).unwrap();
+        assert_eq!(short_term.len(), 1);
+        assert_eq!(short_term[0].id, fresh.id);
+
+        let episodic = memory.all_records(MemoryLayer::Episodic).unwrap();
+        assert_eq!(episodic.len(), 1);
+        assert_eq!(episodic[0].id, emotional.id);
+    }
+}
@@ -0,0 +1,384 @@
+//! Near-duplicate detection on store
+//!
+//! Every record is fingerprinted with a 64-bit SimHash of its words and word
+//! pairs. When a layer has a [`DuplicatePolicy`] other than `Keep`, storing a
+//! record whose fingerprint is within `max_distance` bits of an existing one
+//! in the same namespace either drops the new record or merges it into the
+//! existing one.
+//!
+//! Fingerprints are also indexed as `FINGERPRINT_BANDS` terms, one per 16-bit
+//! band. Two fingerprints within `FINGERPRINT_BANDS - 1` bits of each other
+//! agree on at least one band, so for the usual distances only the records
+//! sharing a band are compared. Staged writes that are not committed yet are
+//! compared in memory.
+
+use std::collections::HashSet;
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::Utc;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use tantivy::collector::DocSetCollector;
+use tantivy::query::{BooleanQuery, Occur, Query, TermQuery};
+use tantivy::schema::IndexRecordOption;
+use tantivy::{TantivyDocument, Term};
+use tracing::debug;
+
+use crate::config::env_or;
+use super::embedding::fnv1a;
+use super::{schema, MemoryLayer, MemoryRecord, MemorySystem};
+
+/// Number of 16-bit bands a fingerprint is indexed under
+pub(crate) const FINGERPRINT_BANDS: u32 = 4;
+
+/// What happens when a stored record nearly duplicates an existing one
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum DuplicatePolicy {
+    /// Store the new record anyway
+    #[default]
+    Keep,
+    /// Drop the new record and return the existing record's ID
+    Skip,
+    /// Fold the new record into the existing one: its timestamp moves to
+    /// now, its reinforcement count goes up and the metadata is combined
+    Merge,
+}
+
+impl FromStr for DuplicatePolicy {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "keep" => Ok(DuplicatePolicy::Keep),
+            "skip" => Ok(DuplicatePolicy::Skip),
+            "merge" => Ok(DuplicatePolicy::Merge),
+            _ => anyhow::bail!("Unknown duplicate policy: {} (expected keep, skip or merge)", s),
+        }
+    }
+}
+
+/// Per-layer duplicate policies
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DedupConfig {
+    pub short_term: DuplicatePolicy,
+    pub long_term: DuplicatePolicy,
+    pub working: DuplicatePolicy,
+    pub episodic: DuplicatePolicy,
+    pub semantic: DuplicatePolicy,
+    /// Largest number of differing fingerprint bits (out of 64) for two
+    /// records to count as duplicates. Up to 3 is looked up by band; larger
+    /// distances compare every record of the namespace.
+    pub max_distance: u32,
+}
+
+impl Default for DedupConfig {
+    fn default() -> Self {
+        Self {
+            short_term: DuplicatePolicy::Merge,
+            long_term: DuplicatePolicy::Keep,
+            working: DuplicatePolicy::Keep,
+            episodic: DuplicatePolicy::Keep,
+            semantic: DuplicatePolicy::Merge,
+            max_distance: 3,
+        }
+    }
+}
+
+impl DedupConfig {
+    /// Load duplicate policies from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            short_term: env_or("MEMORY_DEDUP_SHORT_TERM", defaults.short_term),
+            long_term: env_or("MEMORY_DEDUP_LONG_TERM", defaults.long_term),
+            working: env_or("MEMORY_DEDUP_WORKING", defaults.working),
+            episodic: env_or("MEMORY_DEDUP_EPISODIC", defaults.episodic),
+            semantic: env_or("MEMORY_DEDUP_SEMANTIC", defaults.semantic),
+            max_distance: env_or("MEMORY_DEDUP_MAX_DISTANCE", defaults.max_distance),
+        }
+    }
+
+    /// Duplicate policy for a layer
+    pub fn policy(&self, layer: MemoryLayer) -> DuplicatePolicy {
+        match layer {
+            MemoryLayer::ShortTerm => self.short_term,
+            MemoryLayer::LongTerm => self.long_term,
+            MemoryLayer::Working => self.working,
+            MemoryLayer::Episodic => self.episodic,
+            MemoryLayer::Semantic => self.semantic,
+        }
+    }
+}
+
+/// 64-bit SimHash of the lowercased words and adjacent word pairs of `text`
+///
+/// Texts that share most of their words get fingerprints that differ in few
+/// bits. Text without words fingerprints to 0.
+pub fn simhash(text: &str) -> u64 {
+    let lowercase = text.to_lowercase();
+    let words: Vec<&str> = lowercase
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|w| !w.is_empty())
+        .collect();
+    if words.is_empty() {
+        return 0;
+    }
+
+    let mut weights = [0i64; 64];
+    let mut add = |feature: &[u8]| {
+        let hash = fnv1a(feature);
+        for (bit, weight) in weights.iter_mut().enumerate() {
+            if hash >> bit & 1 == 1 {
+                *weight += 1;
+            } else {
+                *weight -= 1;
+            }
+        }
+    };
+    for word in &words {
+        add(word.as_bytes());
+    }
+    for pair in words.windows(2) {
+        add(format!("{} {}", pair[0], pair[1]).as_bytes());
+    }
+
+    weights
+        .iter()
+        .enumerate()
+        .filter(|(_, weight)| **weight > 0)
+        .fold(0u64, |fingerprint, (bit, _)| fingerprint | 1 << bit)
+}
+
+/// Number of differing bits between two fingerprints
+pub fn hamming_distance(a: u64, b: u64) -> u32 {
+    (a ^ b).count_ones()
+}
+
+/// Terms indexing each band of a fingerprint, e.g. `2:03f1`
+pub(crate) fn band_terms(fingerprint: u64) -> impl Iterator<Item = String> {
+    (0..FINGERPRINT_BANDS).map(move |band| format!("{}:{:04x}", band, fingerprint >> (band * 16) & 0xffff))
+}
+
+impl MemorySystem {
+    /// Apply the layer's duplicate policy to a record about to be stored
+    ///
+    /// Returns the ID of the record it was skipped for or merged into, or
+    /// `None` if it should be stored.
+    pub(crate) async fn deduplicate(&self, record: &MemoryRecord) -> Result<Option<String>> {
+        let Some(config) = &self.dedup else {
+            return Ok(None);
+        };
+        let policy = config.policy(record.layer);
+        if policy == DuplicatePolicy::Keep {
+            return Ok(None);
+        }
+        let Some(mut existing) = self.find_near_duplicate(record, config.max_distance)? else {
+            return Ok(None);
+        };
+
+        match policy {
+            DuplicatePolicy::Keep => return Ok(None),
+            DuplicatePolicy::Skip => {
+                counter!("memory.dedup.skipped_total", 1, "layer" => record.layer.as_str());
+            }
+            DuplicatePolicy::Merge => {
+                merge_into(&mut existing, record);
+                self.replace_record(&existing).await?;
+                self.commit_if_due(existing.layer)?;
+                counter!("memory.dedup.merged_total", 1, "layer" => record.layer.as_str());
+            }
+        }
+        debug!("Near-duplicate of {} in {} ({:?})", existing.id, record.layer.as_str(), policy);
+
+        Ok(Some(existing.id))
+    }
+
+    /// The record in `record.layer` and `record.namespace` with the closest
+    /// fingerprint, if it is within `max_distance` bits
+    ///
+    /// Staged records are compared in memory; committed records that a staged
+    /// write replaced or deleted are skipped.
+    fn find_near_duplicate(&self, record: &MemoryRecord, max_distance: u32) -> Result<Option<MemoryRecord>> {
+        let fingerprint = simhash(&record.content);
+        let layer_index = self.layer(record.layer)?;
+
+        let (mut nearest, staged_ids, searcher) = layer_index.with_staged(|staged, searcher| {
+            let nearest = staged
+                .values()
+                .flatten()
+                .filter(|staged| staged.record.namespace == record.namespace)
+                .map(|staged| (hamming_distance(fingerprint, staged.fingerprint), &staged.record))
+                .filter(|(distance, _)| *distance <= max_distance)
+                .min_by_key(|(distance, _)| *distance)
+                .map(|(distance, existing)| (distance, existing.clone()));
+            let staged_ids: HashSet<String> = staged.keys().cloned().collect();
+            (nearest, staged_ids, searcher)
+        })?;
+
+        let schema = layer_index.index.schema();
+        let namespace = Term::from_field_text(schema.get_field("namespace")?, &record.namespace);
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = vec![(
+            Occur::Must,
+            Box::new(TermQuery::new(namespace, IndexRecordOption::Basic)),
+        )];
+        if max_distance < FINGERPRINT_BANDS {
+            let bands_field = schema.get_field("fingerprint_bands")?;
+            let bands: Vec<(Occur, Box<dyn Query>)> = band_terms(fingerprint)
+                .map(|band| {
+                    let term = Term::from_field_text(bands_field, &band);
+                    (Occur::Should, Box::new(TermQuery::new(term, IndexRecordOption::Basic)) as Box<dyn Query>)
+                })
+                .collect();
+            clauses.push((Occur::Must, Box::new(BooleanQuery::new(bands))));
+        }
+
+        for doc_address in searcher.search(&BooleanQuery::new(clauses), &DocSetCollector)? {
+            let segment_reader = searcher.segment_reader(doc_address.segment_ord);
+            let Some(other) = segment_reader
+                .fast_fields()
+                .u64("fingerprint")
+                .ok()
+                .and_then(|fingerprints| fingerprints.first(doc_address.doc_id))
+            else {
+                continue;
+            };
+            let distance = hamming_distance(fingerprint, other);
+            if distance > max_distance || nearest.as_ref().is_some_and(|(best, _)| *best <= distance) {
+                continue;
+            }
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            let existing = schema::record_from_doc(&schema, &doc, record.layer)?;
+            if !staged_ids.contains(&existing.id) {
+                nearest = Some((distance, existing));
+            }
+        }
+        Ok(nearest.map(|(_, existing)| existing))
+    }
+}
+
+/// Fold a near-duplicate into the existing record, keeping its ID and content
+fn merge_into(existing: &mut MemoryRecord, duplicate: &MemoryRecord) {
+    existing.timestamp = duplicate.timestamp;
+    existing.reinforcements += 1;
+    existing.last_reinforced = Some(Utc::now());
+    existing.importance = existing.importance.max(duplicate.importance);
+    existing.pinned |= duplicate.pinned;
+    if existing.source.is_none() {
+        existing.source = duplicate.source.clone();
+    }
+    for (labels, new_labels) in [
+        (&mut existing.tags, &duplicate.tags),
+        (&mut existing.entities, &duplicate.entities),
+        (&mut existing.emotional_tags, &duplicate.emotional_tags),
+        (&mut existing.derived_from, &duplicate.derived_from),
+    ] {
+        for label in new_labels {
+            if !labels.iter().any(|l| l.eq_ignore_ascii_case(label)) {
+                labels.push(label.clone());
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::NewMemory;
+    use tempfile::TempDir;
+
+    #[test]
+    fn test_simhash_distance() {
+        let a = simhash("User: what time is Phoenix's piano recital on Friday evening?");
+        let b = simhash("User: what time is Phoenix's piano recital on Friday evening");
+        let c = simhash("The router firmware update failed twice overnight");
+
+        assert_eq!(a, b);
+        assert!(hamming_distance(a, c) > 3);
+        assert_eq!(simhash("  ?! "), 0);
+    }
+
+    #[test]
+    fn test_close_fingerprints_share_a_band() {
+        let a = simhash("Phoenix's piano recital is on Friday evening");
+        for flipped in [0u64, 1 << 3, 1 << 3 | 1 << 20 | 1 << 40, 1 << 15 | 1 << 31 | 1 << 47] {
+            let b = a ^ flipped;
+            assert!(band_terms(a).zip(band_terms(b)).any(|(x, y)| x == y));
+        }
+        assert_eq!(band_terms(0x0001_0002_0003_0004).collect::<Vec<_>>(), ["0:0004", "1:0003", "2:0002", "3:0001"]);
+    }
+
+    #[tokio::test]
+    async fn test_staged_and_committed_duplicates() {
+        let dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_dedup(DedupConfig::default());
+        let fact = "Phoenix's piano teacher is Ms. Lee";
+
+        // A committed record is found through its fingerprint bands
+        let committed = memory.store(MemoryLayer::Semantic, fact.to_string()).await.unwrap();
+        memory.flush().await.unwrap();
+        let merged = memory.store(MemoryLayer::Semantic, fact.to_string()).await.unwrap();
+        assert_eq!(merged, committed);
+
+        // A staged deletion hides the committed copy
+        memory.flush().await.unwrap();
+        memory.remove_record(MemoryLayer::Semantic, &committed).await.unwrap();
+        let fresh = memory.store(MemoryLayer::Semantic, fact.to_string()).await.unwrap();
+        assert_ne!(fresh, committed);
+        assert_eq!(memory.store(MemoryLayer::Semantic, fact.to_string()).await.unwrap(), fresh);
+    }
+
+    #[tokio::test]
+    async fn test_duplicate_policies() {
+        let dir = TempDir::new().unwrap();
+        let config = DedupConfig {
+            long_term: DuplicatePolicy::Skip,
+            ..DedupConfig::default()
+        };
+        let memory = MemorySystem::new(dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_dedup(config);
+        let action = "Action: help a neighbour carry groceries | Score: 9.5";
+
+        // Merge: one record, reinforced, with combined tags
+        let first = memory
+            .store_with(MemoryLayer::ShortTerm, NewMemory::new(action).with_tags(["evaluation"]))
+            .await
+            .unwrap();
+        let second = memory
+            .store_with(MemoryLayer::ShortTerm, NewMemory::new(action).with_tags(["api"]))
+            .await
+            .unwrap();
+        assert_eq!(first, second);
+        memory.flush().await.unwrap();
+        let records = memory.recent(MemoryLayer::ShortTerm, 10).await.unwrap();
+        assert_eq!(records.len(), 1);
+        assert_eq!(records[0].reinforcements, 1);
+        assert_eq!(records[0].tags, vec!["evaluation", "api"]);
+
+        // Skip: the stored record is left untouched
+        let first = memory.store(MemoryLayer::LongTerm, action.to_string()).await.unwrap();
+        let second = memory.store(MemoryLayer::LongTerm, action.to_string()).await.unwrap();
+        assert_eq!(first, second);
+        assert_eq!(memory.get(&first).await.unwrap().unwrap().reinforcements, 0);
+
+        // Another owner's copy is never a duplicate
+        let theirs = memory
+            .store_with(MemoryLayer::LongTerm, NewMemory::new(action).with_namespace("phoenix"))
+            .await
+            .unwrap();
+        assert_ne!(theirs, first);
+
+        // Keep: duplicates are stored, different content always is
+        memory.store(MemoryLayer::Episodic, action.to_string()).await.unwrap();
+        memory.store(MemoryLayer::Episodic, action.to_string()).await.unwrap();
+        memory.store(MemoryLayer::ShortTerm, "Router firmware update".to_string()).await.unwrap();
+        memory.flush().await.unwrap();
+        assert_eq!(memory.recent(MemoryLayer::Episodic, 10).await.unwrap().len(), 2);
+        assert_eq!(memory.recent(MemoryLayer::ShortTerm, 10).await.unwrap().len(), 2);
+    }
+}
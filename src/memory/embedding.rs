@@ -0,0 +1,302 @@
+//! Text embeddings for semantic memory retrieval
+//!
+//! An [`Embedder`] turns text into fixed-size vectors that are stored with
+//! each memory record. [`HashingEmbedder`] works offline with no model
+//! download; [`RemoteEmbedder`] calls an OpenAI-compatible `/embeddings`
+//! endpoint.
+
+use std::env;
+use std::sync::Arc;
+
+use anyhow::Result;
+use futures::future::BoxFuture;
+use serde::{Deserialize, Serialize};
+
+use crate::config::env_or;
+
+/// Turns text into embedding vectors
+pub trait Embedder: Send + Sync {
+    /// Length of every vector returned by `embed`
+    fn dimensions(&self) -> usize;
+
+    /// Embed a batch of texts, returning one vector per text in input order
+    fn embed<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>>>;
+}
+
+/// Offline embedder that hashes words and character trigrams into a
+/// fixed number of buckets
+///
+/// It captures shared vocabulary and spelling variants ("recital" /
+/// "recitals"), not meaning; use a [`RemoteEmbedder`] for paraphrases.
+#[derive(Debug, Clone)]
+pub struct HashingEmbedder {
+    dimensions: usize,
+}
+
+impl Default for HashingEmbedder {
+    fn default() -> Self {
+        Self { dimensions: 256 }
+    }
+}
+
+impl HashingEmbedder {
+    pub fn new(dimensions: usize) -> Self {
+        Self {
+            dimensions: dimensions.max(1),
+        }
+    }
+
+    /// Embed a single text synchronously
+    pub fn embed_one(&self, text: &str) -> Vec<f32> {
+        let mut vector = vec![0.0f32; self.dimensions];
+        let lowercase = text.to_lowercase();
+
+        for word in lowercase
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|w| !w.is_empty())
+        {
+            self.add_feature(&mut vector, word.as_bytes(), 1.0);
+
+            let padded: Vec<char> = format!(" {} ", word).chars().collect();
+            for trigram in padded.windows(3) {
+                let trigram: String = trigram.iter().collect();
+                self.add_feature(&mut vector, trigram.as_bytes(), 0.5);
+            }
+        }
+
+        normalize(&mut vector);
+        vector
+    }
+
+    /// Signed feature hashing: the hash picks the bucket and the sign, so
+    /// collisions cancel out on average instead of accumulating
+    fn add_feature(&self, vector: &mut [f32], feature: &[u8], weight: f32) {
+        let hash = fnv1a(feature);
+        let bucket = (hash % self.dimensions as u64) as usize;
+        let sign = if hash >> 63 == 0 { 1.0 } else { -1.0 };
+        vector[bucket] += sign * weight;
+    }
+}
+
+impl Embedder for HashingEmbedder {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>>> {
+        let vectors = texts.iter().map(|text| self.embed_one(text)).collect();
+        Box::pin(async move { Ok(vectors) })
+    }
+}
+
+/// FNV-1a; stable across Rust versions, unlike `DefaultHasher`, so stored
+/// vectors stay comparable with freshly computed ones
+pub(crate) fn fnv1a(bytes: &[u8]) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    for byte in bytes {
+        hash ^= *byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+    hash
+}
+
+/// Embedder backed by an OpenAI-compatible `/embeddings` endpoint
+pub struct RemoteEmbedder {
+    base_url: String,
+    api_key: Option<String>,
+    model: String,
+    dimensions: usize,
+    client: reqwest::Client,
+}
+
+#[derive(Serialize)]
+struct EmbeddingRequest<'a> {
+    model: &'a str,
+    input: &'a [String],
+}
+
+#[derive(Deserialize)]
+struct EmbeddingResponse {
+    data: Vec<EmbeddingData>,
+}
+
+#[derive(Deserialize)]
+struct EmbeddingData {
+    embedding: Vec<f32>,
+    index: usize,
+}
+
+impl RemoteEmbedder {
+    pub fn new(base_url: String, api_key: Option<String>, model: String, dimensions: usize) -> Self {
+        let client = reqwest::Client::builder()
+            .timeout(std::time::Duration::from_secs(30))
+            .build()
+            .expect("Failed to create HTTP client");
+
+        Self {
+            base_url,
+            api_key,
+            model,
+            dimensions,
+            client,
+        }
+    }
+}
+
+impl Embedder for RemoteEmbedder {
+    fn dimensions(&self) -> usize {
+        self.dimensions
+    }
+
+    fn embed<'a>(&'a self, texts: &'a [String]) -> BoxFuture<'a, Result<Vec<Vec<f32>>>> {
+        Box::pin(async move {
+            let url = format!("{}/embeddings", self.base_url.trim_end_matches('/'));
+            let mut request = self.client.post(&url).json(&EmbeddingRequest {
+                model: &self.model,
+                input: texts,
+            });
+            if let Some(api_key) = &self.api_key {
+                request = request.header("Authorization", format!("Bearer {}", api_key));
+            }
+
+            let response = request.send().await?;
+            let status = response.status();
+            if !status.is_success() {
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                anyhow::bail!("Embedding API error: {} - {}", status, error_text);
+            }
+
+            let mut body: EmbeddingResponse = response.json().await?;
+            if body.data.len() != texts.len() {
+                anyhow::bail!(
+                    "Embedding API returned {} vectors for {} inputs",
+                    body.data.len(),
+                    texts.len()
+                );
+            }
+            body.data.sort_by_key(|d| d.index);
+
+            body.data
+                .into_iter()
+                .map(|d| {
+                    if d.embedding.len() != self.dimensions {
+                        anyhow::bail!(
+                            "Embedding API returned {} dimensions, expected {}",
+                            d.embedding.len(),
+                            self.dimensions
+                        );
+                    }
+                    Ok(d.embedding)
+                })
+                .collect()
+        })
+    }
+}
+
+/// Embedding configuration
+#[derive(Debug, Clone)]
+pub struct EmbeddingConfig {
+    /// Base URL of an OpenAI-compatible API; the offline embedder is used when unset
+    pub url: Option<String>,
+    pub api_key: Option<String>,
+    pub model: String,
+    pub dimensions: usize,
+}
+
+impl Default for EmbeddingConfig {
+    fn default() -> Self {
+        Self {
+            url: None,
+            api_key: None,
+            model: "text-embedding-3-small".to_string(),
+            dimensions: 256,
+        }
+    }
+}
+
+impl EmbeddingConfig {
+    /// Load from environment variables, falling back to defaults
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            url: env::var("MEMORY_EMBEDDING_URL").ok().filter(|s| !s.is_empty()),
+            api_key: env::var("MEMORY_EMBEDDING_API_KEY").ok().filter(|s| !s.is_empty()),
+            model: env::var("MEMORY_EMBEDDING_MODEL").unwrap_or(defaults.model),
+            dimensions: env_or("MEMORY_EMBEDDING_DIMENSIONS", defaults.dimensions),
+        }
+    }
+
+    /// Build the configured embedder
+    pub fn build(&self) -> Arc<dyn Embedder> {
+        match &self.url {
+            Some(url) => Arc::new(RemoteEmbedder::new(
+                url.clone(),
+                self.api_key.clone(),
+                self.model.clone(),
+                self.dimensions,
+            )),
+            None => Arc::new(HashingEmbedder::new(self.dimensions)),
+        }
+    }
+}
+
+/// Cosine similarity, or `None` when the vectors can't be compared
+pub fn cosine_similarity(a: &[f32], b: &[f32]) -> Option<f32> {
+    if a.len() != b.len() || a.is_empty() {
+        return None;
+    }
+    let dot: f32 = a.iter().zip(b).map(|(x, y)| x * y).sum();
+    let norm_a = a.iter().map(|x| x * x).sum::<f32>().sqrt();
+    let norm_b = b.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm_a == 0.0 || norm_b == 0.0 {
+        return None;
+    }
+    Some(dot / (norm_a * norm_b))
+}
+
+fn normalize(vector: &mut [f32]) {
+    let norm = vector.iter().map(|x| x * x).sum::<f32>().sqrt();
+    if norm > 0.0 {
+        for x in vector.iter_mut() {
+            *x /= norm;
+        }
+    }
+}
+
+pub(crate) fn vector_to_bytes(vector: &[f32]) -> Vec<u8> {
+    vector.iter().flat_map(|x| x.to_le_bytes()).collect()
+}
+
+pub(crate) fn vector_from_bytes(bytes: &[u8]) -> Vec<f32> {
+    bytes
+        .chunks_exact(4)
+        .map(|chunk| f32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_hashing_embedder_is_deterministic_and_normalized() {
+        let embedder = HashingEmbedder::default();
+        let texts = vec!["Phoenix's piano recital".to_string(), "piano recitals".to_string()];
+        let vectors = embedder.embed(&texts).await.unwrap();
+
+        assert_eq!(vectors[0].len(), 256);
+        assert_eq!(vectors[0], embedder.embed_one("Phoenix's piano recital"));
+        let norm: f32 = vectors[0].iter().map(|x| x * x).sum::<f32>().sqrt();
+        assert!((norm - 1.0).abs() < 1e-5);
+
+        let related = cosine_similarity(&vectors[0], &vectors[1]).unwrap();
+        let unrelated = cosine_similarity(&vectors[0], &embedder.embed_one("router firmware update")).unwrap();
+        assert!(related > unrelated);
+    }
+
+    #[test]
+    fn test_vector_bytes_round_trip() {
+        let vector = vec![0.25, -1.5, 3.0];
+        assert_eq!(vector_from_bytes(&vector_to_bytes(&vector)), vector);
+    }
+}
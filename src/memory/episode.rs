@@ -0,0 +1,406 @@
+//! Episodes: ordered sequences of Episodic records
+//!
+//! An episode groups what happened during a chat session, a day or a
+//! conversation with someone. Every episode belongs to a namespace, and each
+//! namespace has at most one open episode at a time; records appended to it
+//! are stored in the Episodic layer and listed in the episode in the order
+//! they were appended. Episodes are kept as one JSON file each under
+//! `<data_dir>/episodes`.
+
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::RwLock;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use tracing::{info, warn};
+use uuid::Uuid;
+
+use super::summarize::LanguageModel;
+use super::{default_namespace, MemoryLayer, MemoryRecord, MemorySystem, NewMemory, SHARED_NAMESPACE};
+
+/// A sequence of Episodic records with a beginning and an end
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Episode {
+    pub id: String,
+    /// Namespace of the user the episode belongs to
+    ///
+    /// Episodes written before owners existed belong to the shared namespace.
+    #[serde(default = "default_namespace")]
+    pub owner: String,
+    pub title: String,
+    pub started_at: DateTime<Utc>,
+    /// Unset while the episode is open
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub ended_at: Option<DateTime<Utc>>,
+    /// Who took part, e.g. "User" and "Jamey"
+    #[serde(default)]
+    pub participants: Vec<String>,
+    /// IDs of the episode's records, in the order they were appended
+    #[serde(default)]
+    pub records: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub summary: Option<String>,
+    /// Emotions of the records that have any, in order
+    #[serde(default)]
+    pub emotional_arc: Vec<EmotionalBeat>,
+}
+
+impl Episode {
+    pub fn is_open(&self) -> bool {
+        self.ended_at.is_none()
+    }
+
+    /// Whether `owner` may read this episode: its own and shared ones
+    pub fn visible_to(&self, owner: &str) -> bool {
+        self.owner == owner || self.owner == SHARED_NAMESPACE
+    }
+}
+
+/// The emotions of one record in an episode
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct EmotionalBeat {
+    pub record_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub emotions: Vec<String>,
+}
+
+/// Episodes on disk, one JSON file each
+pub struct EpisodeStore {
+    dir: PathBuf,
+    episodes: RwLock<HashMap<String, Episode>>,
+}
+
+impl EpisodeStore {
+    /// Open the store in `dir`, loading every episode in it
+    ///
+    /// Unreadable episode files are skipped with a warning.
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        std::fs::create_dir_all(&dir)?;
+
+        let mut episodes = HashMap::new();
+        for entry in std::fs::read_dir(&dir)? {
+            let path = entry?.path();
+            if path.extension().and_then(|e| e.to_str()) != Some("json") {
+                continue;
+            }
+            match read_episode(&path) {
+                Ok(episode) => {
+                    episodes.insert(episode.id.clone(), episode);
+                }
+                Err(e) => warn!("Skipping unreadable episode {}: {}", path.display(), e),
+            }
+        }
+
+        Ok(Self {
+            dir,
+            episodes: RwLock::new(episodes),
+        })
+    }
+
+    /// Get an episode by ID
+    pub fn get(&self, id: &str) -> Option<Episode> {
+        self.episodes.read().unwrap().get(id).cloned()
+    }
+
+    /// Every episode, newest first
+    pub fn list(&self) -> Vec<Episode> {
+        let mut episodes: Vec<Episode> = self.episodes.read().unwrap().values().cloned().collect();
+        episodes.sort_by_key(|episode| std::cmp::Reverse(episode.started_at));
+        episodes
+    }
+
+    /// The most recently started open episode of `owner`
+    pub fn current(&self, owner: &str) -> Option<Episode> {
+        self.episodes
+            .read()
+            .unwrap()
+            .values()
+            .filter(|episode| episode.is_open() && episode.owner == owner)
+            .max_by_key(|episode| episode.started_at)
+            .cloned()
+    }
+
+    /// Persist an episode, replacing any previous version
+    pub fn insert(&self, episode: Episode) -> Result<()> {
+        let mut episodes = self.episodes.write().unwrap();
+        self.write_episode(&episode)?;
+        episodes.insert(episode.id.clone(), episode);
+        Ok(())
+    }
+
+    /// Change an episode and persist it in one step
+    ///
+    /// Returns `None` if there is no episode with this ID.
+    pub fn update(&self, id: &str, change: impl FnOnce(&mut Episode)) -> Result<Option<Episode>> {
+        let mut episodes = self.episodes.write().unwrap();
+        let Some(episode) = episodes.get_mut(id) else {
+            return Ok(None);
+        };
+        let mut changed = episode.clone();
+        change(&mut changed);
+        self.write_episode(&changed)?;
+        *episode = changed.clone();
+        Ok(Some(changed))
+    }
+
+    fn episode_path(&self, id: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", id))
+    }
+
+    /// Write through a temporary file so a crash never leaves a torn episode
+    fn write_episode(&self, episode: &Episode) -> Result<()> {
+        let path = self.episode_path(&episode.id);
+        let staging = path.with_extension("tmp");
+        std::fs::write(&staging, serde_json::to_vec_pretty(episode)?)?;
+        std::fs::rename(staging, path)?;
+        Ok(())
+    }
+}
+
+fn read_episode(path: &Path) -> Result<Episode> {
+    Ok(serde_json::from_slice(&std::fs::read(path)?)?)
+}
+
+impl MemorySystem {
+    /// Start a new episode and make it `owner`'s current one
+    ///
+    /// An episode of `owner` that is still open is closed first.
+    pub async fn open_episode<S: Into<String>>(
+        &self,
+        owner: &str,
+        title: impl Into<String>,
+        participants: impl IntoIterator<Item = S>,
+    ) -> Result<Episode> {
+        self.close_episode(owner, None).await?;
+
+        let episode = Episode {
+            id: Uuid::new_v4().to_string(),
+            owner: owner.to_string(),
+            title: title.into(),
+            started_at: Utc::now(),
+            ended_at: None,
+            participants: participants.into_iter().map(Into::into).collect(),
+            records: Vec::new(),
+            summary: None,
+            emotional_arc: Vec::new(),
+        };
+        self.episodes.insert(episode.clone())?;
+
+        counter!("memory.episodes.opened_total", 1);
+        info!("Opened episode {} ({})", episode.id, episode.title);
+        Ok(episode)
+    }
+
+    /// The open episode of `owner` that records are appended to
+    pub fn current_episode(&self, owner: &str) -> Option<Episode> {
+        self.episodes.current(owner)
+    }
+
+    /// Store a memory in the Episodic layer as the next record of the
+    /// current episode of the memory's namespace
+    ///
+    /// Returns the record's ID. Fails if that namespace has no open episode.
+    pub async fn append_to_episode(&self, memory: NewMemory) -> Result<String> {
+        let episode = self
+            .current_episode(&memory.namespace)
+            .context("No episode is open")?;
+        let emotions = memory.emotional_tags.clone();
+        let id = self.store_with(MemoryLayer::Episodic, memory).await?;
+
+        self.episodes.update(&episode.id, |episode| {
+            // A near-duplicate merged into an earlier record keeps its place
+            if episode.records.contains(&id) {
+                return;
+            }
+            episode.records.push(id.clone());
+            if !emotions.is_empty() {
+                episode.emotional_arc.push(EmotionalBeat {
+                    record_id: id.clone(),
+                    timestamp: Utc::now(),
+                    emotions,
+                });
+            }
+        })?;
+        Ok(id)
+    }
+
+    /// End the current episode of `owner`, optionally with a summary
+    ///
+    /// Returns the closed episode, or `None` if `owner` had no open episode.
+    pub async fn close_episode(&self, owner: &str, summary: Option<String>) -> Result<Option<Episode>> {
+        let Some(episode) = self.current_episode(owner) else {
+            return Ok(None);
+        };
+
+        let closed = self.episodes.update(&episode.id, |episode| {
+            episode.ended_at = Some(Utc::now());
+            if summary.is_some() {
+                episode.summary = summary;
+            }
+        })?;
+        if let Some(closed) = &closed {
+            counter!("memory.episodes.closed_total", 1);
+            info!("Closed episode {} with {} records", closed.id, closed.records.len());
+        }
+        Ok(closed)
+    }
+
+    /// Get an episode by ID
+    pub fn episode(&self, id: &str) -> Option<Episode> {
+        self.episodes.get(id)
+    }
+
+    /// Every episode `owner` may read, newest first
+    pub fn episodes(&self, owner: &str) -> Vec<Episode> {
+        let mut episodes = self.episodes.list();
+        episodes.retain(|episode| episode.visible_to(owner));
+        episodes
+    }
+
+    /// The records of an episode in the order they were appended
+    ///
+    /// Records deleted since they were appended are left out. Returns `None`
+    /// if there is no episode with this ID.
+    pub async fn replay_episode(&self, id: &str) -> Result<Option<Vec<MemoryRecord>>> {
+        let Some(episode) = self.episode(id) else {
+            return Ok(None);
+        };
+
+        let mut records = Vec::with_capacity(episode.records.len());
+        for record_id in &episode.records {
+            if let Some(record) = self.get(record_id).await? {
+                records.push(record);
+            }
+        }
+        Ok(Some(records))
+    }
+
+    /// Ask a language model to summarize an episode and store the summary
+    ///
+    /// Returns the updated episode, or `None` if there is no episode with
+    /// this ID.
+    pub async fn summarize_episode(&self, id: &str, model: &dyn LanguageModel) -> Result<Option<Episode>> {
+        let Some(records) = self.replay_episode(id).await? else {
+            return Ok(None);
+        };
+        if records.is_empty() {
+            return Ok(self.episode(id));
+        }
+
+        let summary = model.complete(&episode_summary_prompt(&records)).await?;
+        self.episodes.update(id, |episode| {
+            episode.summary = Some(summary.trim().to_string());
+        })
+    }
+}
+
+/// Prompt asking for a short summary of an episode's records
+fn episode_summary_prompt(records: &[MemoryRecord]) -> String {
+    let mut prompt = String::from(
+        "Below is everything recorded during one episode, in order. Summarize what \
+         happened in one or two sentences. Reply with only the summary.\n\n",
+    );
+    for record in records {
+        prompt.push_str(&format!(
+            "{}: {}\n",
+            record.timestamp.format("%Y-%m-%d %H:%M UTC"),
+            record.content
+        ));
+    }
+    prompt
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::future::BoxFuture;
+    use tempfile::TempDir;
+
+    struct FixedSummary;
+
+    impl LanguageModel for FixedSummary {
+        fn complete<'a>(&'a self, _prompt: &'a str) -> BoxFuture<'a, Result<String>> {
+            Box::pin(async { Ok(" Planned Phoenix's recital. \n".to_string()) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_episode_lifecycle() {
+        let dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf()).await.unwrap();
+        assert!(memory.append_to_episode(NewMemory::new("Nobody listening")).await.is_err());
+
+        let episode = memory
+            .open_episode(SHARED_NAMESPACE, "Chat session", ["User", "Jamey"])
+            .await
+            .unwrap();
+        let first = memory
+            .append_to_episode(NewMemory::new("User: when is Phoenix's recital?"))
+            .await
+            .unwrap();
+        let second = memory
+            .append_to_episode(NewMemory::new("Jamey: Friday at six").with_emotional_tags(["joy"]))
+            .await
+            .unwrap();
+
+        let current = memory.current_episode(SHARED_NAMESPACE).unwrap();
+        assert_eq!(current.records, vec![first.clone(), second.clone()]);
+        assert_eq!(current.emotional_arc.len(), 1);
+        assert_eq!(current.emotional_arc[0].record_id, second);
+
+        // Opening another episode closes this one
+        memory.open_episode(SHARED_NAMESPACE, "Second session", ["User"]).await.unwrap();
+        assert!(!memory.episode(&episode.id).unwrap().is_open());
+        memory
+            .close_episode(SHARED_NAMESPACE, Some("Nothing happened".to_string()))
+            .await
+            .unwrap();
+        assert!(memory.current_episode(SHARED_NAMESPACE).is_none());
+
+        let replay = memory.replay_episode(&episode.id).await.unwrap().unwrap();
+        let contents: Vec<&str> = replay.iter().map(|record| record.content.as_str()).collect();
+        assert_eq!(contents, vec!["User: when is Phoenix's recital?", "Jamey: Friday at six"]);
+        assert!(replay.iter().all(|record| record.layer == MemoryLayer::Episodic));
+
+        let summarized = memory.summarize_episode(&episode.id, &FixedSummary).await.unwrap().unwrap();
+        assert_eq!(summarized.summary.as_deref(), Some("Planned Phoenix's recital."));
+
+        // Episodes survive a restart
+        drop(memory);
+        let memory = MemorySystem::new(dir.path().to_path_buf()).await.unwrap();
+        assert_eq!(memory.episodes(SHARED_NAMESPACE).len(), 2);
+        assert_eq!(memory.episode(&episode.id).unwrap().records.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_episodes_are_kept_per_owner() {
+        let dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf()).await.unwrap();
+
+        let alice = memory.open_episode("alice", "Alice's day", ["alice"]).await.unwrap();
+        let bob = memory.open_episode("bob", "Bob's day", ["bob"]).await.unwrap();
+
+        // Opening Bob's episode leaves Alice's open
+        assert_eq!(memory.current_episode("alice").unwrap().id, alice.id);
+        assert_eq!(memory.current_episode("bob").unwrap().id, bob.id);
+        assert!(memory.current_episode(SHARED_NAMESPACE).is_none());
+
+        // Records go to the episode of their own namespace
+        let id = memory
+            .append_to_episode(NewMemory::new("Walked the dog").with_namespace("bob"))
+            .await
+            .unwrap();
+        assert_eq!(memory.episode(&bob.id).unwrap().records, vec![id]);
+        assert!(memory.episode(&alice.id).unwrap().records.is_empty());
+        assert!(memory.append_to_episode(NewMemory::new("Nobody listening")).await.is_err());
+
+        let listed: Vec<String> = memory.episodes("bob").into_iter().map(|episode| episode.id).collect();
+        assert_eq!(listed, vec![bob.id.clone()]);
+
+        memory.close_episode("bob", None).await.unwrap();
+        assert!(memory.episode(&alice.id).unwrap().is_open());
+    }
+}
@@ -0,0 +1,270 @@
+//! JSON Lines export and import of memory layers
+//!
+//! An export starts with a header line followed by one record per line:
+//!
+//! ```text
+//! {"format":"jamey-memory","version":1,"layer":"long_term","exported_at":"...","records":2}
+//! {"id":"...","content":"...","timestamp":"...","layer":"long_term",...,"embedding":[...]}
+//! {"id":"...","content":"...","timestamp":"...","layer":"long_term",...,"embedding":[...]}
+//! ```
+//!
+//! Exports of several layers can be concatenated into one file; each keeps
+//! its own header.
+
+use super::{MemoryLayer, MemoryRecord, MemorySystem};
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::io::{BufRead, Write};
+use std::str::FromStr;
+use uuid::Uuid;
+
+/// Value of the `format` field in export headers
+pub const EXPORT_FORMAT: &str = "jamey-memory";
+
+/// Current export format version; imports reject newer versions
+pub const EXPORT_VERSION: u32 = 1;
+
+/// First line of every export
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ExportHeader {
+    pub format: String,
+    pub version: u32,
+    pub layer: MemoryLayer,
+    pub exported_at: DateTime<Utc>,
+    /// Number of record lines that follow
+    pub records: usize,
+}
+
+/// A record line: every `MemoryRecord` field, including the embedding
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct ExportedRecord {
+    #[serde(flatten)]
+    record: MemoryRecord,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    embedding: Option<Vec<f32>>,
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum ExportLine {
+    Header(ExportHeader),
+    Record(Box<ExportedRecord>),
+}
+
+/// What `import` does with a record whose ID is already stored
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum IdCollision {
+    /// Keep the stored record and drop the imported one
+    #[default]
+    Skip,
+    /// Replace the stored record, wherever it lives, with the imported one
+    Overwrite,
+    /// Store the imported record under a fresh ID
+    Reid,
+}
+
+impl FromStr for IdCollision {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.to_lowercase().as_str() {
+            "skip" => Ok(IdCollision::Skip),
+            "overwrite" => Ok(IdCollision::Overwrite),
+            "reid" | "re-id" => Ok(IdCollision::Reid),
+            _ => anyhow::bail!("Unknown ID collision policy: {} (expected skip, overwrite or reid)", s),
+        }
+    }
+}
+
+/// Outcome of an import
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct ImportReport {
+    /// Records stored under their exported ID
+    pub imported: usize,
+    /// Records dropped because their ID was already stored
+    pub skipped: usize,
+    /// Stored records replaced by imported ones
+    pub overwritten: usize,
+    /// Records stored under a fresh ID because theirs was taken
+    pub reidentified: usize,
+}
+
+impl MemorySystem {
+    /// Write every record of a layer to `writer` as JSON Lines
+    ///
+    /// Returns the number of records written.
+    pub async fn export<W: Write>(&self, layer: MemoryLayer, mut writer: W) -> Result<usize> {
+        self.flush().await?;
+        let records = self.all_records(layer)?;
+
+        let header = ExportHeader {
+            format: EXPORT_FORMAT.to_string(),
+            version: EXPORT_VERSION,
+            layer,
+            exported_at: Utc::now(),
+            records: records.len(),
+        };
+        serde_json::to_writer(&mut writer, &header)?;
+        writer.write_all(b"\n")?;
+
+        for mut record in records {
+            let embedding = record.embedding.take();
+            serde_json::to_writer(&mut writer, &ExportedRecord { record, embedding })?;
+            writer.write_all(b"\n")?;
+        }
+        writer.flush()?;
+
+        Ok(header.records)
+    }
+
+    /// Read records written by `export` and store each in the layer it
+    /// names, keeping IDs, timestamps and metadata
+    ///
+    /// Records exported without an embedding are embedded on the way in.
+    pub async fn import<R: BufRead>(&self, reader: R, on_collision: IdCollision) -> Result<ImportReport> {
+        self.flush().await?;
+        let mut stored: HashMap<String, MemoryLayer> = HashMap::new();
+        for layer in MemoryLayer::ALL {
+            for record in self.all_records(layer)? {
+                stored.insert(record.id, layer);
+            }
+        }
+
+        let mut report = ImportReport::default();
+        let mut seen_header = false;
+        for (number, line) in reader.lines().enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+
+            let parsed: ExportLine = serde_json::from_str(&line)
+                .with_context(|| format!("Invalid memory export on line {}", number + 1))?;
+            let mut record = match parsed {
+                ExportLine::Header(header) => {
+                    if header.format != EXPORT_FORMAT {
+                        anyhow::bail!("Not a memory export: format is {:?}", header.format);
+                    }
+                    if header.version > EXPORT_VERSION {
+                        anyhow::bail!(
+                            "Memory export version {} is newer than the supported version {}",
+                            header.version,
+                            EXPORT_VERSION
+                        );
+                    }
+                    seen_header = true;
+                    continue;
+                }
+                ExportLine::Record(exported) => {
+                    if !seen_header {
+                        anyhow::bail!("Memory export is missing its header line");
+                    }
+                    let ExportedRecord { mut record, embedding } = *exported;
+                    record.embedding = embedding;
+                    record.score = None;
+                    record
+                }
+            };
+
+            if let Some(&existing_layer) = stored.get(&record.id) {
+                match on_collision {
+                    IdCollision::Skip => {
+                        report.skipped += 1;
+                        continue;
+                    }
+                    IdCollision::Overwrite => {
+                        if existing_layer != record.layer {
+                            self.remove_record(existing_layer, &record.id).await?;
+                        }
+                        report.overwritten += 1;
+                    }
+                    IdCollision::Reid => {
+                        record.id = Uuid::new_v4().to_string();
+                        report.reidentified += 1;
+                    }
+                }
+            } else {
+                report.imported += 1;
+            }
+
+            if record.embedding.is_none() {
+                self.embed_records(std::slice::from_mut(&mut record)).await;
+            }
+            // Replacing is a plain add when the ID isn't in the layer yet
+            self.replace_record(&record).await?;
+            stored.insert(record.id.clone(), record.layer);
+        }
+
+        self.flush().await?;
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::NewMemory;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_export_import_round_trip() {
+        let source_dir = TempDir::new().unwrap();
+        let source = MemorySystem::new(source_dir.path().to_path_buf()).await.unwrap();
+        let id = source
+            .store_with(
+                MemoryLayer::Episodic,
+                NewMemory::new("Phoenix's first recital")
+                    .with_tags(["music"])
+                    .with_entities(["Phoenix"])
+                    .with_importance(0.9)
+                    .pinned(true),
+            )
+            .await
+            .unwrap();
+
+        let mut export = Vec::new();
+        assert_eq!(source.export(MemoryLayer::Episodic, &mut export).await.unwrap(), 1);
+        let original = source.get(&id).await.unwrap().unwrap();
+
+        let target_dir = TempDir::new().unwrap();
+        let target = MemorySystem::new(target_dir.path().to_path_buf()).await.unwrap();
+        let report = target.import(export.as_slice(), IdCollision::Skip).await.unwrap();
+        assert_eq!(report.imported, 1);
+
+        let imported = target.get(&id).await.unwrap().unwrap();
+        assert_eq!(imported.layer, MemoryLayer::Episodic);
+        assert_eq!(imported.timestamp, original.timestamp);
+        assert_eq!(imported.tags, original.tags);
+        assert_eq!(imported.entities, original.entities);
+        assert_eq!(imported.importance, original.importance);
+        assert!(imported.pinned);
+        assert_eq!(imported.embedding, original.embedding);
+
+        // Importing again collides with every record
+        let report = target.import(export.as_slice(), IdCollision::Skip).await.unwrap();
+        assert_eq!((report.imported, report.skipped), (0, 1));
+        let report = target.import(export.as_slice(), IdCollision::Reid).await.unwrap();
+        assert_eq!(report.reidentified, 1);
+        assert_eq!(target.recent(MemoryLayer::Episodic, 10).await.unwrap().len(), 2);
+
+        target.update(&id, "Edited locally".to_string()).await.unwrap();
+        let report = target.import(export.as_slice(), IdCollision::Overwrite).await.unwrap();
+        assert_eq!(report.overwritten, 1);
+        assert_eq!(target.get(&id).await.unwrap().unwrap().content, "Phoenix's first recital");
+        assert_eq!(target.recent(MemoryLayer::Episodic, 10).await.unwrap().len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_import_rejects_newer_versions() {
+        let dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf()).await.unwrap();
+        let export = format!(
+            "{{\"format\":\"{}\",\"version\":{},\"layer\":\"long_term\",\"exported_at\":\"2024-11-16T00:00:00Z\",\"records\":0}}\n",
+            EXPORT_FORMAT,
+            EXPORT_VERSION + 1
+        );
+        assert!(memory.import(export.as_bytes(), IdCollision::Skip).await.is_err());
+    }
+}
@@ -0,0 +1,710 @@
+//! Knowledge graph of semantic facts
+//!
+//! Facts are subject-predicate-object triples in the `knowledge_triples`
+//! table. Objects either name an entity, which can be traversed to, or hold a
+//! literal value. Names compare case-insensitively and predicates are
+//! normalized to lowercase snake case, so "Lives in" and "lives_in" are the
+//! same relation.
+//!
+//! Asserting a triple reports the stored triples it contradicts: the same
+//! fact negated (`likes` and `not_likes`), or another object for a predicate
+//! that allows only one (`lives_in`). The new triple is stored either way.
+//!
+//! Subjects are linked to `SoulEntity` records whose name matches.
+//!
+//! Triples belong to a namespace like memory records do: their owner's user
+//! ID, or `SHARED_NAMESPACE` for facts everyone can see. Contradictions are
+//! only looked for among the triples the asserting owner can see.
+
+use std::collections::HashSet;
+use std::env;
+use std::str::FromStr;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use sqlx::sqlite::SqliteRow;
+use sqlx::{Row, SqlitePool};
+use tracing::warn;
+use uuid::Uuid;
+
+use super::SHARED_NAMESPACE;
+
+/// Deepest neighborhood traversal
+pub const MAX_HOPS: usize = 3;
+
+/// Most triples a neighborhood traversal returns
+pub const MAX_NEIGHBORS: usize = 500;
+
+/// Prefix that negates a predicate
+const NEGATION_PREFIX: &str = "not_";
+
+const SELECT_TRIPLES: &str = r#"
+    SELECT t.id, t.namespace, t.subject, t.predicate, t.object, t.object_kind, t.confidence,
+           t.source_memory_id, t.created_at, t.updated_at,
+           (SELECT s.entity_name FROM soul_entities s
+            WHERE s.entity_name = t.subject COLLATE NOCASE LIMIT 1) AS soul_entity
+    FROM knowledge_triples t
+"#;
+
+/// Knowledge graph configuration
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct KnowledgeConfig {
+    /// Predicates a subject can only have one object for
+    pub functional_predicates: Vec<String>,
+}
+
+impl Default for KnowledgeConfig {
+    fn default() -> Self {
+        Self {
+            functional_predicates: [
+                "age", "birthday", "born_in", "born_on", "lives_in", "located_in", "married_to",
+                "works_at",
+            ]
+            .into_iter()
+            .map(String::from)
+            .collect(),
+        }
+    }
+}
+
+impl KnowledgeConfig {
+    /// Load knowledge graph configuration from environment variables
+    ///
+    /// `KNOWLEDGE_FUNCTIONAL_PREDICATES` is a comma-separated list.
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            functional_predicates: env::var("KNOWLEDGE_FUNCTIONAL_PREDICATES")
+                .ok()
+                .map(|v| {
+                    v.split(',')
+                        .map(normalize_predicate)
+                        .filter(|p| !p.is_empty())
+                        .collect()
+                })
+                .unwrap_or(defaults.functional_predicates),
+        }
+    }
+
+    fn is_functional(&self, predicate: &str) -> bool {
+        self.functional_predicates.iter().any(|p| p == predicate)
+    }
+}
+
+/// Object of a triple
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TripleObject {
+    /// Another node of the graph
+    Entity(String),
+    /// A plain value, e.g. a date or a number
+    Literal(String),
+}
+
+impl TripleObject {
+    pub fn value(&self) -> &str {
+        match self {
+            TripleObject::Entity(value) | TripleObject::Literal(value) => value,
+        }
+    }
+
+    fn kind(&self) -> &'static str {
+        match self {
+            TripleObject::Entity(_) => "entity",
+            TripleObject::Literal(_) => "literal",
+        }
+    }
+}
+
+/// A stored fact
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Triple {
+    pub id: String,
+    /// Owner of the triple, or `SHARED_NAMESPACE`
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    pub subject: String,
+    pub predicate: String,
+    pub object: TripleObject,
+    /// From 0.0 to 1.0
+    pub confidence: f32,
+    /// Memory record the fact was learned from
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub source_memory_id: Option<String>,
+    /// Name of the soul entity the subject refers to
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub soul_entity: Option<String>,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+}
+
+fn default_namespace() -> String {
+    SHARED_NAMESPACE.to_string()
+}
+
+impl Triple {
+    /// Whether `owner` can see the triple: it is theirs or shared
+    pub fn visible_to(&self, owner: &str) -> bool {
+        self.namespace == owner || self.namespace == SHARED_NAMESPACE
+    }
+}
+
+/// A fact to be asserted
+#[derive(Debug, Clone)]
+pub struct NewTriple {
+    /// Defaults to `SHARED_NAMESPACE`
+    pub namespace: String,
+    pub subject: String,
+    pub predicate: String,
+    pub object: TripleObject,
+    pub confidence: f32,
+    pub source_memory_id: Option<String>,
+}
+
+impl NewTriple {
+    pub fn new(subject: impl Into<String>, predicate: impl Into<String>, object: TripleObject) -> Self {
+        Self {
+            namespace: default_namespace(),
+            subject: subject.into(),
+            predicate: predicate.into(),
+            object,
+            confidence: 1.0,
+            source_memory_id: None,
+        }
+    }
+
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    pub fn with_confidence(mut self, confidence: f32) -> Self {
+        self.confidence = confidence.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_source(mut self, memory_id: impl Into<String>) -> Self {
+        self.source_memory_id = Some(memory_id.into());
+        self
+    }
+}
+
+/// Outcome of asserting a triple
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Assertion {
+    pub triple: Triple,
+    /// Stored triples the new one conflicts with
+    pub contradictions: Vec<Triple>,
+}
+
+/// A triple found by neighborhood traversal
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Neighbor {
+    /// Number of hops from the starting entity, from 1
+    pub hop: usize,
+    #[serde(flatten)]
+    pub triple: Triple,
+}
+
+/// A pattern of terms, each either fixed or a wildcard
+///
+/// Parsed from three whitespace-separated terms, where `?` (or any term
+/// starting with `?`) matches anything and terms with spaces are quoted:
+/// `Phoenix.Marie ? ?`, `? lives_in "New York"`.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TriplePattern {
+    pub subject: Option<String>,
+    pub predicate: Option<String>,
+    pub object: Option<String>,
+    /// Triples must be in one of these namespaces; all when empty
+    pub namespaces: Vec<String>,
+}
+
+impl TriplePattern {
+    /// Only match the triples `owner` can see: theirs and the shared ones
+    pub fn visible_to(self, owner: impl Into<String>) -> Self {
+        self.with_namespace(owner).with_namespace(SHARED_NAMESPACE)
+    }
+
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespaces.push(namespace.into());
+        self
+    }
+}
+
+impl FromStr for TriplePattern {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut terms = Vec::new();
+        let mut chars = s.trim().chars().peekable();
+        while let Some(&c) = chars.peek() {
+            if c.is_whitespace() {
+                chars.next();
+            } else if c == '"' {
+                chars.next();
+                let term: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                terms.push(term);
+            } else {
+                let mut term = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() {
+                        break;
+                    }
+                    term.push(c);
+                    chars.next();
+                }
+                terms.push(term);
+            }
+        }
+
+        let [subject, predicate, object]: [String; 3] = terms
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("Triple pattern needs three terms: {}", s))?;
+        let fixed = |term: String| (!term.starts_with('?')).then_some(term);
+        Ok(Self {
+            subject: fixed(subject),
+            predicate: fixed(predicate).map(|p| normalize_predicate(&p)),
+            object: fixed(object),
+            namespaces: Vec::new(),
+        })
+    }
+}
+
+/// Lowercase a predicate and join its words with underscores
+pub fn normalize_predicate(predicate: &str) -> String {
+    predicate
+        .split(|c: char| c.is_whitespace() || c == '_' || c == '-')
+        .filter(|word| !word.is_empty())
+        .map(str::to_lowercase)
+        .collect::<Vec<_>>()
+        .join("_")
+}
+
+/// The predicate with its negation added or removed
+fn negated(predicate: &str) -> String {
+    match predicate.strip_prefix(NEGATION_PREFIX) {
+        Some(base) => base.to_string(),
+        None => format!("{}{}", NEGATION_PREFIX, predicate),
+    }
+}
+
+/// Triples in SQLite
+#[derive(Clone)]
+pub struct KnowledgeGraph {
+    pool: SqlitePool,
+    config: KnowledgeConfig,
+}
+
+impl KnowledgeGraph {
+    pub fn new(pool: SqlitePool, config: KnowledgeConfig) -> Self {
+        Self { pool, config }
+    }
+
+    /// Store a triple, or raise the confidence of the same stored triple,
+    /// and report the stored triples it contradicts
+    pub async fn assert(&self, triple: NewTriple) -> Result<Assertion> {
+        let subject = triple.subject.trim();
+        let predicate = normalize_predicate(&triple.predicate);
+        let object = triple.object.value().trim();
+        if subject.is_empty() || predicate.is_empty() || object.is_empty() {
+            anyhow::bail!("Triples need a subject, a predicate and an object");
+        }
+
+        let namespace = triple.namespace.trim();
+        if namespace.is_empty() {
+            anyhow::bail!("Triples need a namespace");
+        }
+
+        let contradictions = self.contradictions(namespace, subject, &predicate, object).await?;
+
+        let now = Utc::now();
+        let id: String = sqlx::query_scalar(
+            r#"
+            INSERT INTO knowledge_triples (
+                id, namespace, subject, predicate, object, object_kind, confidence,
+                source_memory_id, created_at, updated_at
+            )
+            VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)
+            ON CONFLICT(namespace, subject, predicate, object) DO UPDATE SET
+                object_kind = excluded.object_kind,
+                confidence = MAX(confidence, excluded.confidence),
+                source_memory_id = COALESCE(source_memory_id, excluded.source_memory_id),
+                updated_at = excluded.updated_at
+            RETURNING id
+            "#,
+        )
+        .bind(Uuid::new_v4().to_string())
+        .bind(namespace)
+        .bind(subject)
+        .bind(&predicate)
+        .bind(object)
+        .bind(triple.object.kind())
+        .bind(triple.confidence.clamp(0.0, 1.0) as f64)
+        .bind(&triple.source_memory_id)
+        .bind(now)
+        .bind(now)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let stored = self
+            .get(&id)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("Triple {} vanished after it was stored", id))?;
+
+        counter!("knowledge.triples_asserted_total", 1);
+        if !contradictions.is_empty() {
+            counter!("knowledge.contradictions_total", contradictions.len() as u64);
+            warn!(
+                "{} {} {} contradicts {} stored triples",
+                stored.subject,
+                stored.predicate,
+                stored.object.value(),
+                contradictions.len()
+            );
+        }
+
+        Ok(Assertion {
+            triple: stored,
+            contradictions,
+        })
+    }
+
+    /// Stored triples visible to `namespace` that conflict with
+    /// `subject predicate object`
+    async fn contradictions(
+        &self,
+        namespace: &str,
+        subject: &str,
+        predicate: &str,
+        object: &str,
+    ) -> Result<Vec<Triple>> {
+        let mut contradictions: Vec<Triple> = self
+            .query(
+                &TriplePattern {
+                    subject: Some(subject.to_string()),
+                    predicate: Some(negated(predicate)),
+                    object: Some(object.to_string()),
+                    namespaces: Vec::new(),
+                }
+                .visible_to(namespace),
+                usize::MAX,
+            )
+            .await?;
+
+        if self.config.is_functional(predicate) {
+            let others = self
+                .query(
+                    &TriplePattern {
+                        subject: Some(subject.to_string()),
+                        predicate: Some(predicate.to_string()),
+                        object: None,
+                        namespaces: Vec::new(),
+                    }
+                    .visible_to(namespace),
+                    usize::MAX,
+                )
+                .await?;
+            contradictions.extend(
+                others
+                    .into_iter()
+                    .filter(|triple| !triple.object.value().eq_ignore_ascii_case(object)),
+            );
+        }
+        Ok(contradictions)
+    }
+
+    /// Get a triple by ID
+    pub async fn get(&self, id: &str) -> Result<Option<Triple>> {
+        let row = sqlx::query(&format!("{} WHERE t.id = ?", SELECT_TRIPLES))
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+        row.as_ref().map(triple_from_row).transpose()
+    }
+
+    /// Delete a triple
+    ///
+    /// Returns `false` if there was no triple with this ID.
+    pub async fn retract(&self, id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM knowledge_triples WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Triples matching a pattern, most confident first
+    pub async fn query(&self, pattern: &TriplePattern, limit: usize) -> Result<Vec<Triple>> {
+        let mut clauses = Vec::new();
+        let mut binds = Vec::new();
+        for (column, term) in [
+            ("t.subject", &pattern.subject),
+            ("t.predicate", &pattern.predicate),
+            ("t.object", &pattern.object),
+        ] {
+            if let Some(term) = term {
+                clauses.push(format!("{} = ?", column));
+                binds.push(term.trim().to_string());
+            }
+        }
+
+        if !pattern.namespaces.is_empty() {
+            clauses.push(namespace_clause(pattern.namespaces.len()));
+            binds.extend(pattern.namespaces.iter().cloned());
+        }
+
+        let mut sql = SELECT_TRIPLES.to_string();
+        if !clauses.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&clauses.join(" AND "));
+        }
+        sql.push_str(" ORDER BY t.confidence DESC, t.updated_at DESC LIMIT ?");
+
+        let mut query = sqlx::query(&sql);
+        for bind in binds {
+            query = query.bind(bind);
+        }
+        let rows = query
+            .bind(i64::try_from(limit).unwrap_or(i64::MAX))
+            .fetch_all(&self.pool)
+            .await?;
+        rows.iter().map(triple_from_row).collect()
+    }
+
+    /// Triples within `hops` of an entity, nearest first
+    ///
+    /// A hop follows a triple from its subject to its object or back, but
+    /// never continues from a literal. `hops` is clamped to 1..=3 and at most
+    /// `MAX_NEIGHBORS` triples are returned. Only triples in `namespaces` are
+    /// followed, or all of them when it is empty.
+    pub async fn neighborhood(&self, entity: &str, hops: usize, namespaces: &[String]) -> Result<Vec<Neighbor>> {
+        let hops = hops.clamp(1, MAX_HOPS);
+        let mut visited: HashSet<String> = HashSet::from([entity.trim().to_lowercase()]);
+        let mut seen_triples = HashSet::new();
+        let mut frontier = vec![entity.trim().to_string()];
+        let mut neighbors = Vec::new();
+
+        for hop in 1..=hops {
+            let mut next = Vec::new();
+            for node in &frontier {
+                let mut sql = format!(
+                    "{} WHERE (t.subject = ? OR (t.object = ? AND t.object_kind = 'entity'))",
+                    SELECT_TRIPLES
+                );
+                if !namespaces.is_empty() {
+                    sql.push_str(" AND ");
+                    sql.push_str(&namespace_clause(namespaces.len()));
+                }
+                sql.push_str(" ORDER BY t.confidence DESC");
+
+                let mut query = sqlx::query(&sql).bind(node).bind(node);
+                for namespace in namespaces {
+                    query = query.bind(namespace);
+                }
+                let rows = query.fetch_all(&self.pool).await?;
+
+                for row in &rows {
+                    let triple = triple_from_row(row)?;
+                    if !seen_triples.insert(triple.id.clone()) {
+                        continue;
+                    }
+                    let other = if triple.subject.eq_ignore_ascii_case(node) {
+                        match &triple.object {
+                            TripleObject::Entity(object) => Some(object.clone()),
+                            TripleObject::Literal(_) => None,
+                        }
+                    } else {
+                        Some(triple.subject.clone())
+                    };
+                    if let Some(other) = other {
+                        if visited.insert(other.to_lowercase()) {
+                            next.push(other);
+                        }
+                    }
+
+                    neighbors.push(Neighbor { hop, triple });
+                    if neighbors.len() >= MAX_NEIGHBORS {
+                        return Ok(neighbors);
+                    }
+                }
+            }
+            if next.is_empty() {
+                break;
+            }
+            frontier = next;
+        }
+        Ok(neighbors)
+    }
+}
+
+/// `t.namespace IN (?, ...)` with `count` placeholders
+fn namespace_clause(count: usize) -> String {
+    format!("t.namespace IN ({})", vec!["?"; count].join(", "))
+}
+
+fn triple_from_row(row: &SqliteRow) -> Result<Triple> {
+    let object: String = row.try_get("object")?;
+    let kind: String = row.try_get("object_kind")?;
+    Ok(Triple {
+        id: row.try_get("id")?,
+        namespace: row.try_get("namespace")?,
+        subject: row.try_get("subject")?,
+        predicate: row.try_get("predicate")?,
+        object: match kind.as_str() {
+            "literal" => TripleObject::Literal(object),
+            _ => TripleObject::Entity(object),
+        },
+        confidence: row.try_get::<f64, _>("confidence")? as f32,
+        source_memory_id: row.try_get("source_memory_id")?,
+        soul_entity: row.try_get("soul_entity")?,
+        created_at: row.try_get("created_at")?,
+        updated_at: row.try_get("updated_at")?,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::store::test_pool;
+
+    async fn test_graph() -> KnowledgeGraph {
+        KnowledgeGraph::new(test_pool().await, KnowledgeConfig::default())
+    }
+
+    fn entity(name: &str) -> TripleObject {
+        TripleObject::Entity(name.to_string())
+    }
+
+    #[test]
+    fn test_parse_pattern() {
+        let pattern: TriplePattern = "Phoenix.Marie ? ?".parse().unwrap();
+        assert_eq!(pattern.subject.as_deref(), Some("Phoenix.Marie"));
+        assert_eq!((pattern.predicate, pattern.object), (None, None));
+
+        let pattern: TriplePattern = "?who \"Lives in\" \"New York\"".parse().unwrap();
+        assert_eq!(pattern.subject, None);
+        assert_eq!(pattern.predicate.as_deref(), Some("lives_in"));
+        assert_eq!(pattern.object.as_deref(), Some("New York"));
+
+        assert!("Phoenix plays".parse::<TriplePattern>().is_err());
+    }
+
+    #[tokio::test]
+    async fn test_assert_query_and_contradictions() {
+        let graph = test_graph().await;
+        sqlx::query("INSERT INTO soul_entities (entity_name, last_interaction) VALUES ('Phoenix.Marie', '2024-11-19')")
+            .execute(&graph.pool)
+            .await
+            .unwrap();
+
+        let first = graph
+            .assert(NewTriple::new("Phoenix.Marie", "Lives in", entity("Austin")).with_confidence(0.6))
+            .await
+            .unwrap();
+        assert!(first.contradictions.is_empty());
+        assert_eq!(first.triple.predicate, "lives_in");
+        assert_eq!(first.triple.soul_entity.as_deref(), Some("Phoenix.Marie"));
+
+        // The same fact, differently cased, raises the confidence
+        let again = graph
+            .assert(NewTriple::new("phoenix.marie", "lives_in", entity("austin")).with_source("memory-1"))
+            .await
+            .unwrap();
+        assert_eq!(again.triple.id, first.triple.id);
+        assert_eq!(again.triple.confidence, 1.0);
+        assert_eq!(again.triple.source_memory_id.as_deref(), Some("memory-1"));
+
+        let moved = graph
+            .assert(NewTriple::new("Phoenix.Marie", "lives_in", entity("Denver")))
+            .await
+            .unwrap();
+        assert_eq!(moved.contradictions.len(), 1);
+        assert_eq!(moved.contradictions[0].id, first.triple.id);
+
+        graph
+            .assert(NewTriple::new("Phoenix.Marie", "plays", entity("Piano")))
+            .await
+            .unwrap();
+        let negated = graph
+            .assert(NewTriple::new("Phoenix.Marie", "not plays", entity("piano")))
+            .await
+            .unwrap();
+        assert_eq!(negated.contradictions.len(), 1);
+
+        let about: TriplePattern = "Phoenix.Marie ? ?".parse().unwrap();
+        assert_eq!(graph.query(&about, 10).await.unwrap().len(), 4);
+        let plays: TriplePattern = "? plays ?".parse().unwrap();
+        assert_eq!(graph.query(&plays, 10).await.unwrap().len(), 1);
+
+        assert!(graph.retract(&negated.triple.id).await.unwrap());
+        assert!(!graph.retract(&negated.triple.id).await.unwrap());
+    }
+
+    #[tokio::test]
+    async fn test_neighborhood_traversal() {
+        let graph = test_graph().await;
+        for (subject, predicate, object) in [
+            ("Phoenix.Marie", "studies_with", entity("Ms. Lee")),
+            ("Ms. Lee", "teaches_at", entity("Riverside Academy")),
+            ("Riverside Academy", "located_in", entity("Austin")),
+            ("Phoenix.Marie", "born_on", TripleObject::Literal("2015-04-02".to_string())),
+        ] {
+            graph.assert(NewTriple::new(subject, predicate, object)).await.unwrap();
+        }
+
+        let hops = |neighbors: &[Neighbor]| neighbors.iter().map(|n| n.hop).collect::<Vec<_>>();
+        let one = graph.neighborhood("phoenix.marie", 1, &[]).await.unwrap();
+        assert_eq!(hops(&one), vec![1, 1]);
+        let three = graph.neighborhood("Phoenix.Marie", 3, &[]).await.unwrap();
+        assert_eq!(hops(&three), vec![1, 1, 2, 3]);
+        assert_eq!(three[3].triple.object, entity("Austin"));
+
+        // Traversal also follows triples backwards from their object
+        let from_school = graph.neighborhood("Riverside Academy", 2, &[]).await.unwrap();
+        assert_eq!(from_school.len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_namespaces() {
+        let graph = test_graph().await;
+        let shared = graph
+            .assert(NewTriple::new("Phoenix.Marie", "lives_in", entity("Austin")))
+            .await
+            .unwrap();
+        assert_eq!(shared.triple.namespace, SHARED_NAMESPACE);
+
+        // Another owner's facts are neither contradicted nor seen
+        let alice = graph
+            .assert(NewTriple::new("Phoenix.Marie", "lives_in", entity("Denver")).with_namespace("alice"))
+            .await
+            .unwrap();
+        assert_eq!(alice.contradictions.len(), 1);
+        let bob = graph
+            .assert(NewTriple::new("Phoenix.Marie", "lives_in", entity("Boston")).with_namespace("bob"))
+            .await
+            .unwrap();
+        assert_eq!(bob.contradictions.len(), 1);
+        assert_eq!(bob.contradictions[0].id, shared.triple.id);
+
+        // The same fact is held separately per namespace
+        let again = graph
+            .assert(NewTriple::new("Phoenix.Marie", "lives_in", entity("Austin")).with_namespace("bob"))
+            .await
+            .unwrap();
+        assert_ne!(again.triple.id, shared.triple.id);
+        assert!(!again.triple.visible_to("alice"));
+        assert!(shared.triple.visible_to("alice"));
+
+        let about: TriplePattern = "Phoenix.Marie ? ?".parse().unwrap();
+        assert_eq!(graph.query(&about.clone().visible_to("alice"), 10).await.unwrap().len(), 2);
+        assert_eq!(graph.query(&about, 10).await.unwrap().len(), 4);
+
+        let visible = vec!["bob".to_string(), SHARED_NAMESPACE.to_string()];
+        let neighbors = graph.neighborhood("Phoenix.Marie", 1, &visible).await.unwrap();
+        assert_eq!(neighbors.len(), 3);
+        assert!(neighbors.iter().all(|n| n.triple.namespace != "alice"));
+    }
+}
@@ -0,0 +1,108 @@
+//! Highlighted snippets of search results
+//!
+//! A search asked for highlights (`SearchOptions::with_highlights`) attaches
+//! to each result the part of its content that best matches the query, with
+//! the matching words marked. Results matching none of the query's words,
+//! as semantic matches may, get the beginning of their content instead.
+
+use serde::{Deserialize, Serialize};
+use tantivy::SnippetGenerator;
+
+/// Longest snippet, in characters
+pub const SNIPPET_CHARS: usize = 150;
+
+/// Part of a record's content and the words in it that matched the query
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MemorySnippet {
+    pub fragment: String,
+    /// Byte ranges of `fragment` that matched, in order
+    pub highlighted: Vec<(usize, usize)>,
+}
+
+impl MemorySnippet {
+    /// The best matching fragment of `content`
+    pub(crate) fn generate(generator: &SnippetGenerator, content: &str) -> Self {
+        let snippet = generator.snippet(content);
+        if snippet.fragment().is_empty() {
+            let end = content
+                .char_indices()
+                .nth(SNIPPET_CHARS)
+                .map_or(content.len(), |(i, _)| i);
+            return Self {
+                fragment: content[..end].to_string(),
+                highlighted: Vec::new(),
+            };
+        }
+        Self {
+            fragment: snippet.fragment().to_string(),
+            highlighted: snippet
+                .highlighted()
+                .iter()
+                .map(|range| (range.start, range.end))
+                .collect(),
+        }
+    }
+
+    /// The fragment with every match wrapped in `open` and `close`, e.g.
+    /// `<b>` and `</b>`
+    pub fn marked(&self, open: &str, close: &str) -> String {
+        let mut marked = String::with_capacity(self.fragment.len());
+        let mut from = 0;
+        for &(start, end) in &self.highlighted {
+            marked.push_str(&self.fragment[from..start]);
+            marked.push_str(open);
+            marked.push_str(&self.fragment[start..end]);
+            marked.push_str(close);
+            from = end;
+        }
+        marked.push_str(&self.fragment[from..]);
+        marked
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::{MemoryLayer, MemorySystem, NewMemory, SearchOptions};
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_highlights_and_explains_results() {
+        let dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf()).await.unwrap();
+        let long_text = format!(
+            "{} Phoenix played the piano at her recital. {}",
+            "The school year started quietly. ".repeat(10),
+            "Afterwards everyone went out for ice cream. ".repeat(10)
+        );
+        memory
+            .store_with(MemoryLayer::Episodic, NewMemory::new(long_text.clone()).with_tags(["music"]))
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+
+        let plain = memory.search(MemoryLayer::Episodic, "recital", 1).await.unwrap();
+        assert!(plain[0].snippet.is_none() && plain[0].explanation.is_none());
+
+        let options = SearchOptions::new(1).with_highlights().with_explanations();
+        let results = memory
+            .search_with(MemoryLayer::Episodic, "piano recital tag:music", &options)
+            .await
+            .unwrap();
+        let snippet = results[0].snippet.as_ref().unwrap();
+        assert!(snippet.fragment.len() < long_text.len());
+        assert_eq!(snippet.highlighted.len(), 2);
+        assert!(snippet
+            .marked("[", "]")
+            .contains("Phoenix played the [piano] at her [recital]"));
+        let explanation = results[0].explanation.as_ref().unwrap();
+        assert!(explanation["value"].as_f64().unwrap() > 0.0);
+
+        // Filter-only queries match no words; the snippet is the beginning
+        let results = memory.search_with(MemoryLayer::Episodic, "tag:music", &options).await.unwrap();
+        let snippet = results[0].snippet.as_ref().unwrap();
+        assert!(long_text.starts_with(&snippet.fragment));
+        assert_eq!(snippet.fragment.chars().count(), SNIPPET_CHARS);
+        assert!(snippet.highlighted.is_empty());
+    }
+}
@@ -4,18 +4,25 @@
 //! brain theory. This allows for distributed storage and associative recall,
 //! where memories are stored across the system in a wave interference pattern,
 //! making the system more resilient and enabling content-addressable memory.
+//!
+//! A [`TraceStore`] attached to the memory system keeps a trace of every
+//! record on disk and finds records with similar traces.
 
-use std::sync::{Arc, RwLock};
-use std::collections::VecDeque;
+use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::path::{Path, PathBuf};
 use anyhow::Result;
-use ndarray::{Array2, arr2};
+use ndarray::Array2;
 use serde::{Serialize, Deserialize};
 use uuid::Uuid;
 use metrics::{counter, gauge, histogram};
 use rayon::prelude::*;
+use rustfft::{num_complex::Complex, Fft, FftPlanner};
 use thiserror::Error;
-use lz4_flex::{compress_prepend_size, decompress_size_prepended};
 use chrono::{DateTime, Utc};
+use tracing::warn;
+
+use super::{MemoryLayer, MemoryRecord, MemorySystem};
 
 #[derive(Debug, Error)]
 pub enum MemoryError {
@@ -115,7 +122,7 @@ pub struct CompressedStore<T> where T: Serialize + for<'de> Deserialize<'de> {
 }
 
 /// A compressed block of data with metadata
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Serialize)]
 struct CompressedBlock<T> where T: Serialize + for<'de> Deserialize<'de> {
     /// Compressed data bytes
     data: Vec<u8>,
@@ -143,7 +150,8 @@ impl<T> CompressedStore<T> where T: Serialize + for<'de> Deserialize<'de> {
         let serialized = serde_json::to_vec(data)
             .map_err(|e| MemoryError::CompressionFailed(e.to_string()))?;
         
-        let compressed = compress_prepend_size(&serialized);
+        let compressed = lz4::block::compress(&serialized, None, true)
+            .map_err(|e| MemoryError::CompressionFailed(e.to_string()))?;
         let block = CompressedBlock {
             data: compressed,
             original_size: serialized.len(),
@@ -173,8 +181,18 @@ impl<T> CompressedStore<T> where T: Serialize + for<'de> Deserialize<'de> {
         let block = self.blocks.get(block_index)
             .ok_or_else(|| MemoryError::InvalidConfig("Block index out of bounds".into()))?;
 
+        Self::decompress(&block.data)
+    }
+
+    /// Compressed bytes of a block, for persisting it elsewhere
+    pub fn block_data(&self, block_index: usize) -> Option<&[u8]> {
+        self.blocks.get(block_index).map(|block| block.data.as_slice())
+    }
+
+    /// Decompress the bytes of a block, as returned by `block_data`
+    pub fn decompress(data: &[u8]) -> Result<Vec<T>, MemoryError> {
         // Decompress data
-        let decompressed = decompress_size_prepended(&block.data)
+        let decompressed = lz4::block::decompress(data, None)
             .map_err(|e| MemoryError::CompressionFailed(e.to_string()))?;
 
         // Deserialize
@@ -286,13 +304,22 @@ impl ConsciousnessMetricsStore {
 }
 
 
+/// Transform size used by [`TraceStore`]; longer content spans several rows
+pub const TRACE_TRANSFORM_SIZE: usize = 256;
+
+/// Seed of the reference wave. Traces only decode with the reference they
+/// were encoded with, so this must never change.
+const REFERENCE_SEED: u64 = 0x4a61_6d65_7933;
+
 /// Represents a holographic memory trace
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct HolographicTrace {
     /// Unique identifier
     pub id: Uuid,
-    /// Interference pattern matrix
+    /// Interference pattern matrix, one row per `transform_size` bytes of content
     pub pattern: Array2<f64>,
+    /// Length of the original content in bytes
+    pub length: usize,
     /// Original content hash
     pub content_hash: String,
     /// Creation timestamp
@@ -304,22 +331,43 @@ pub struct HolographicTrace {
 }
 
 /// Holographic memory encoder/decoder
+///
+/// Content is stored as the circular convolution of its bytes with a
+/// reference wave, computed with an FFT. Every frequency of the reference has
+/// magnitude 1, so correlating a trace with the same reference recovers the
+/// content exactly.
 pub struct HolographicMemory {
     /// Fourier transform size
     transform_size: usize,
-    /// Phase encoding resolution
-    phase_resolution: usize,
-    /// Minimum pattern strength
-    min_strength: f64,
+    /// Spectrum of the reference wave
+    reference: Vec<Complex<f64>>,
+    forward: Arc<dyn Fft<f64>>,
+    inverse: Arc<dyn Fft<f64>>,
+}
+
+impl Default for HolographicMemory {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
 impl HolographicMemory {
     /// Create new holographic memory system
     pub fn new() -> Self {
+        Self::with_transform_size(1024)
+    }
+
+    /// Create a holographic memory system encoding `transform_size` bytes
+    /// per pattern row
+    pub fn with_transform_size(transform_size: usize) -> Self {
+        let transform_size = transform_size.max(2);
+        let mut planner = FftPlanner::new();
+
         Self {
-            transform_size: 1024,
-            phase_resolution: 256,
-            min_strength: 0.1,
+            transform_size,
+            reference: reference_spectrum(transform_size, REFERENCE_SEED),
+            forward: planner.plan_fft_forward(transform_size),
+            inverse: planner.plan_fft_inverse(transform_size),
         }
     }
 
@@ -330,18 +378,32 @@ impl HolographicMemory {
         emotional_tags: Vec<String>,
         context_associations: Vec<String>,
     ) -> Result<HolographicTrace> {
-        // Convert content to frequency domain
-        let frequencies = self.content_to_frequencies(content);
-        
-        // Generate reference wave
-        let reference = self.generate_reference_wave();
-        
-        // Create interference pattern
-        let pattern = self.create_interference_pattern(&frequencies, &reference);
+        let bytes = content.as_bytes();
+        let rows = bytes.len().div_ceil(self.transform_size).max(1);
+        let mut pattern = Array2::zeros((rows, self.transform_size));
+
+        // Each row is independent, so rows are convolved in parallel
+        pattern
+            .axis_iter_mut(ndarray::Axis(0))
+            .into_par_iter()
+            .zip(bytes.par_chunks(self.transform_size))
+            .for_each(|(mut row, chunk)| {
+                let mut buffer: Vec<Complex<f64>> = chunk
+                    .iter()
+                    .map(|&byte| Complex::new(byte_to_amplitude(byte), 0.0))
+                    .collect();
+                buffer.resize(self.transform_size, Complex::new(0.0, 0.0));
+
+                self.convolve(&mut buffer, false);
+                for (value, sample) in row.iter_mut().zip(&buffer) {
+                    *value = sample.re;
+                }
+            });
 
         let trace = HolographicTrace {
             id: Uuid::new_v4(),
             pattern,
+            length: bytes.len(),
             content_hash: self.hash_content(content),
             timestamp: chrono::Utc::now(),
             emotional_tags,
@@ -356,15 +418,31 @@ impl HolographicMemory {
     }
 
     /// Decode content from holographic trace
+    ///
+    /// Fails if the trace was encoded with another transform size or the
+    /// decoded content doesn't match the trace's hash.
     pub fn decode(&self, trace: &HolographicTrace) -> Result<String> {
-        // Generate reference wave
-        let reference = self.generate_reference_wave();
-        
-        // Extract frequencies from interference pattern
-        let frequencies = self.extract_frequencies(&trace.pattern, &reference);
-        
-        // Convert frequencies back to content
-        let content = self.frequencies_to_content(&frequencies)?;
+        if trace.pattern.ncols() != self.transform_size {
+            anyhow::bail!(
+                "Trace was encoded with transform size {}, not {}",
+                trace.pattern.ncols(),
+                self.transform_size
+            );
+        }
+
+        let mut bytes = Vec::with_capacity(trace.pattern.len());
+        for row in trace.pattern.axis_iter(ndarray::Axis(0)) {
+            let mut buffer: Vec<Complex<f64>> = row.iter().map(|&value| Complex::new(value, 0.0)).collect();
+            self.convolve(&mut buffer, true);
+            bytes.extend(buffer.iter().map(|sample| amplitude_to_byte(sample.re)));
+        }
+        bytes.truncate(trace.length);
+
+        let content = String::from_utf8(bytes)
+            .map_err(|e| anyhow::anyhow!("Failed to decode content: {}", e))?;
+        if self.hash_content(&content) != trace.content_hash {
+            anyhow::bail!("Decoded content doesn't match trace {}", trace.id);
+        }
 
         // Update metrics
         counter!("memory.holographic.decodings_total", 1);
@@ -372,149 +450,480 @@ impl HolographicMemory {
         Ok(content)
     }
 
-    /// Convert content to frequency domain
-    fn content_to_frequencies(&self, content: &str) -> Array2<f64> {
-        // Convert content to numerical representation
-        let mut numerical: Vec<f64> = content.bytes()
-            .map(|b| b as f64 / 255.0)
+    /// Circularly convolve `buffer` with the reference wave, or correlate
+    /// it with the reference when `inverse` is set
+    fn convolve(&self, buffer: &mut [Complex<f64>], inverse: bool) {
+        self.forward.process(buffer);
+        for (sample, reference) in buffer.iter_mut().zip(&self.reference) {
+            *sample *= if inverse { reference.conj() } else { *reference };
+        }
+        self.inverse.process(buffer);
+
+        // rustfft doesn't normalize the round trip
+        let scale = 1.0 / self.transform_size as f64;
+        for sample in buffer.iter_mut() {
+            *sample *= scale;
+        }
+    }
+
+    /// Calculate hash of content for verification
+    fn hash_content(&self, content: &str) -> String {
+        use sha2::{Sha256, Digest};
+        let mut hasher = Sha256::new();
+        hasher.update(content.as_bytes());
+        format!("{:x}", hasher.finalize())
+    }
+
+    /// Calculate strength of interference pattern: its root mean square,
+    /// which is at most 0.5
+    fn calculate_pattern_strength(&self, trace: &HolographicTrace) -> f64 {
+        if trace.pattern.is_empty() {
+            return 0.0;
+        }
+        (trace.pattern.iter().map(|v| v * v).sum::<f64>() / trace.pattern.len() as f64).sqrt()
+    }
+
+    /// Cosine similarity of two traces' interference patterns, from -1.0 to 1.0
+    ///
+    /// The encoding preserves angles, so traces made with the same reference
+    /// compare like their contents byte by byte: near-identical content
+    /// scores close to 1.0. Use memory search to compare meaning.
+    pub fn similarity(&self, a: &HolographicTrace, b: &HolographicTrace) -> f64 {
+        let dot: f64 = a.pattern.iter().zip(b.pattern.iter()).map(|(x, y)| x * y).sum();
+        let norm_a = a.pattern.iter().map(|x| x * x).sum::<f64>().sqrt();
+        let norm_b = b.pattern.iter().map(|y| y * y).sum::<f64>().sqrt();
+        if norm_a == 0.0 || norm_b == 0.0 {
+            0.0
+        } else {
+            dot / (norm_a * norm_b)
+        }
+    }
+
+    /// Find traces among `candidates` whose similarity to `trace` is at
+    /// least `threshold`, most similar first
+    ///
+    /// `trace` itself is skipped if it is among the candidates.
+    pub fn find_similar<'a>(
+        &self,
+        trace: &HolographicTrace,
+        candidates: impl IntoParallelIterator<Item = &'a HolographicTrace>,
+        threshold: f64,
+    ) -> Vec<(Uuid, f64)> {
+        let mut similar: Vec<(Uuid, f64)> = candidates
+            .into_par_iter()
+            .filter(|candidate| candidate.id != trace.id)
+            .map(|candidate| (candidate.id, self.similarity(trace, candidate)))
+            .filter(|(_, similarity)| *similarity >= threshold)
             .collect();
+        similar.sort_by(|a, b| b.1.total_cmp(&a.1));
+        similar
+    }
+}
+
+/// Content bytes are centered on zero so unrelated content isn't similar
+/// just because every byte is positive
+fn byte_to_amplitude(byte: u8) -> f64 {
+    byte as f64 / 255.0 - 0.5
+}
 
-        // Pad to transform size
-        numerical.resize(self.transform_size, 0.0);
+fn amplitude_to_byte(amplitude: f64) -> u8 {
+    ((amplitude + 0.5) * 255.0).round().clamp(0.0, 255.0) as u8
+}
+
+/// Spectrum of a real reference wave with pseudo-random phases and unit
+/// magnitude at every frequency
+fn reference_spectrum(size: usize, seed: u64) -> Vec<Complex<f64>> {
+    let mut state = seed;
+    let mut next_phase = || {
+        // splitmix64
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^= z >> 31;
+        (z >> 11) as f64 / (1u64 << 53) as f64 * 2.0 * std::f64::consts::PI
+    };
+
+    // Conjugate-symmetric so the wave and every trace are real
+    let mut spectrum = vec![Complex::new(1.0, 0.0); size];
+    for k in 1..size.div_ceil(2) {
+        let phase = Complex::from_polar(1.0, next_phase());
+        spectrum[k] = phase;
+        spectrum[size - k] = phase.conj();
+    }
+    if size.is_multiple_of(2) {
+        spectrum[size / 2] = Complex::new(if next_phase() < std::f64::consts::PI { 1.0 } else { -1.0 }, 0.0);
+    }
+    spectrum
+}
+
+/// A trace on disk, with the record it encodes
+#[derive(Debug, Clone)]
+struct StoredTrace {
+    memory_id: String,
+    trace: HolographicTrace,
+}
+
+/// Magic bytes at the start of every trace file, naming the format version
+const TRACE_MAGIC: &[u8; 4] = b"HTR1";
+
+/// Holographic traces of memory records, one LZ4-compressed file per trace
+///
+/// Traces live under `<dir>/<layer>/` and are read on demand, so opening the
+/// store is cheap however many traces it holds. File I/O and encoding run on
+/// Tokio's blocking pool.
+///
+/// Attach to a memory system with [`MemorySystem::with_holographic`] to
+/// encode every record it writes.
+pub struct TraceStore {
+    dir: PathBuf,
+    encoder: Arc<HolographicMemory>,
+}
 
-        // Apply FFT
-        let mut frequencies = Array2::zeros((self.transform_size, 2));
-        for i in 0..self.transform_size {
-            let phase = 2.0 * std::f64::consts::PI * i as f64 / self.transform_size as f64;
-            frequencies[[i, 0]] = numerical[i] * phase.cos(); // Real
-            frequencies[[i, 1]] = numerical[i] * phase.sin(); // Imaginary
+impl TraceStore {
+    /// Open the store in `dir`, creating it if needed
+    pub fn open(dir: PathBuf) -> Result<Self> {
+        for layer in MemoryLayer::ALL {
+            std::fs::create_dir_all(dir.join(layer.as_str()))?;
         }
+        Ok(Self {
+            dir,
+            encoder: Arc::new(HolographicMemory::with_transform_size(TRACE_TRANSFORM_SIZE)),
+        })
+    }
 
-        frequencies
+    /// Encode a record and persist its trace, replacing any previous trace
+    /// of the same record in its layer
+    pub async fn encode(&self, record: &MemoryRecord) -> Result<()> {
+        let encoder = self.encoder.clone();
+        let path = self.trace_path(record.layer, &record.id);
+        let record = record.clone();
+        blocking(move || {
+            let mut trace = encoder.encode(&record.content, record.emotional_tags, record.entities)?;
+            trace.timestamp = record.timestamp;
+            write_trace(
+                &path,
+                &StoredTrace {
+                    memory_id: record.id,
+                    trace,
+                },
+            )
+        })
+        .await?;
+        counter!("memory.holographic.traces_written_total", 1);
+        Ok(())
     }
 
-    /// Generate reference wave for encoding/decoding
-    fn generate_reference_wave(&self) -> Array2<f64> {
-        let mut reference = Array2::zeros((self.transform_size, 2));
-        
-        for i in 0..self.transform_size {
-            let phase = 2.0 * std::f64::consts::PI * i as f64 / self.phase_resolution as f64;
-            reference[[i, 0]] = phase.cos();
-            reference[[i, 1]] = phase.sin();
+    /// Delete the trace of a record if it is stored in `layer`
+    ///
+    /// Returns `false` if there was no such trace.
+    pub async fn remove(&self, layer: MemoryLayer, memory_id: &str) -> Result<bool> {
+        let path = self.trace_path(layer, memory_id);
+        let removed = blocking(move || match std::fs::remove_file(&path) {
+            Ok(()) => Ok(true),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(false),
+            Err(e) => Err(e.into()),
+        })
+        .await?;
+        if removed {
+            counter!("memory.holographic.traces_removed_total", 1);
         }
+        Ok(removed)
+    }
 
-        reference
+    /// Trace of a record
+    pub async fn get(&self, memory_id: &str) -> Result<Option<HolographicTrace>> {
+        let paths = self.candidate_paths(memory_id);
+        let stored = blocking(move || find_trace(&paths)).await?;
+        Ok(stored.map(|stored| stored.trace))
     }
 
-    /// Create interference pattern between content and reference
-    fn create_interference_pattern(
-        &self,
-        frequencies: &Array2<f64>,
-        reference: &Array2<f64>,
-    ) -> Array2<f64> {
-        let mut pattern = Array2::zeros((self.transform_size, self.transform_size));
+    /// Decode a record's content from its trace
+    pub async fn recall(&self, memory_id: &str) -> Result<Option<String>> {
+        match self.get(memory_id).await? {
+            Some(trace) => Ok(Some(self.encoder.decode(&trace)?)),
+            None => Ok(None),
+        }
+    }
 
-        // Parallel computation of interference pattern
-        pattern.axis_iter_mut(ndarray::Axis(0))
-            .into_par_iter()
-            .enumerate()
-            .for_each(|(i, mut row)| {
-                for j in 0..self.transform_size {
-                    // Complex multiplication
-                    let real = frequencies[[i, 0]] * reference[[j, 0]] -
-                             frequencies[[i, 1]] * reference[[j, 1]];
-                    let imag = frequencies[[i, 0]] * reference[[j, 1]] +
-                             frequencies[[i, 1]] * reference[[j, 0]];
-                    
-                    // Store magnitude
-                    row[j] = (real * real + imag * imag).sqrt();
+    /// Records whose traces are at least `threshold` similar to the trace of
+    /// `memory_id`, most similar first, excluding the record itself
+    ///
+    /// Every stored trace is read to compare it.
+    pub async fn find_similar(&self, memory_id: &str, threshold: f64) -> Result<Vec<(String, f64)>> {
+        let encoder = self.encoder.clone();
+        let paths = self.candidate_paths(memory_id);
+        let dirs: Vec<PathBuf> = MemoryLayer::ALL.iter().map(|layer| self.dir.join(layer.as_str())).collect();
+        blocking(move || {
+            let Some(target) = find_trace(&paths)? else {
+                return Ok(Vec::new());
+            };
+
+            let mut traces = Vec::new();
+            for dir in &dirs {
+                for entry in std::fs::read_dir(dir)? {
+                    let path = entry?.path();
+                    if path.extension().and_then(|e| e.to_str()) != Some("trace") {
+                        continue;
+                    }
+                    match read_trace(&path) {
+                        Ok(stored) if stored.memory_id != target.memory_id => traces.push(stored),
+                        Ok(_) => {}
+                        Err(e) => warn!("Skipping unreadable holographic trace {}: {}", path.display(), e),
+                    }
                 }
-            });
+            }
 
-        pattern
+            let memory_ids: HashMap<Uuid, &str> = traces
+                .iter()
+                .map(|stored| (stored.trace.id, stored.memory_id.as_str()))
+                .collect();
+            let candidates: Vec<&HolographicTrace> = traces.iter().map(|stored| &stored.trace).collect();
+            Ok(encoder
+                .find_similar(&target.trace, candidates, threshold)
+                .into_iter()
+                .filter_map(|(id, similarity)| Some((memory_ids.get(&id)?.to_string(), similarity)))
+                .collect())
+        })
+        .await
     }
 
-    /// Extract frequencies from interference pattern
-    fn extract_frequencies(
-        &self,
-        pattern: &Array2<f64>,
-        reference: &Array2<f64>,
-    ) -> Array2<f64> {
-        let mut frequencies = Array2::zeros((self.transform_size, 2));
+    /// Whether a record has a trace
+    pub async fn contains(&self, memory_id: &str) -> Result<bool> {
+        let paths = self.candidate_paths(memory_id);
+        blocking(move || Ok(paths.iter().any(|path| path.exists()))).await
+    }
 
-        // Parallel frequency extraction
-        frequencies.axis_iter_mut(ndarray::Axis(0))
-            .into_par_iter()
-            .enumerate()
-            .for_each(|(i, mut row)| {
-                let mut real_sum = 0.0;
-                let mut imag_sum = 0.0;
-
-                for j in 0..self.transform_size {
-                    real_sum += pattern[[i, j]] * reference[[j, 0]];
-                    imag_sum += pattern[[i, j]] * reference[[j, 1]];
-                }
+    /// File holding the trace of a record in a layer
+    ///
+    /// Files are named after a hash of the record ID, so any ID makes a
+    /// valid file name.
+    fn trace_path(&self, layer: MemoryLayer, memory_id: &str) -> PathBuf {
+        use sha2::{Digest, Sha256};
+        let digest = Sha256::digest(memory_id.as_bytes());
+        let name: String = digest[..16].iter().map(|byte| format!("{:02x}", byte)).collect();
+        self.dir.join(layer.as_str()).join(format!("{}.trace", name))
+    }
 
-                row[0] = real_sum / self.transform_size as f64;
-                row[1] = imag_sum / self.transform_size as f64;
-            });
+    /// Where the trace of a record may be, one path per layer
+    fn candidate_paths(&self, memory_id: &str) -> Vec<PathBuf> {
+        MemoryLayer::ALL
+            .iter()
+            .map(|layer| self.trace_path(*layer, memory_id))
+            .collect()
+    }
+}
+
+/// Run blocking file I/O or encoding on Tokio's blocking pool
+async fn blocking<T: Send + 'static>(f: impl FnOnce() -> Result<T> + Send + 'static) -> Result<T> {
+    tokio::task::spawn_blocking(f).await?
+}
+
+/// The first of `paths` that holds a trace
+fn find_trace(paths: &[PathBuf]) -> Result<Option<StoredTrace>> {
+    for path in paths {
+        match read_trace(path) {
+            Ok(stored) => return Ok(Some(stored)),
+            Err(e) if is_not_found(&e) => continue,
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(None)
+}
+
+fn is_not_found(e: &anyhow::Error) -> bool {
+    e.downcast_ref::<std::io::Error>()
+        .is_some_and(|e| e.kind() == std::io::ErrorKind::NotFound)
+}
+
+/// Write through a uniquely named temporary file so a crash or a concurrent
+/// write never leaves a torn trace
+fn write_trace(path: &Path, stored: &StoredTrace) -> Result<()> {
+    let compressed = lz4::block::compress(&trace_to_bytes(stored), None, true)
+        .map_err(|e| MemoryError::CompressionFailed(e.to_string()))?;
+    let mut file = Vec::with_capacity(TRACE_MAGIC.len() + compressed.len());
+    file.extend_from_slice(TRACE_MAGIC);
+    file.extend_from_slice(&compressed);
+
+    let staging = path.with_extension(format!("{}.tmp", Uuid::new_v4()));
+    std::fs::write(&staging, file)?;
+    std::fs::rename(staging, path)?;
+    Ok(())
+}
+
+fn read_trace(path: &Path) -> Result<StoredTrace> {
+    let file = std::fs::read(path)?;
+    let compressed = file
+        .strip_prefix(TRACE_MAGIC)
+        .ok_or_else(|| anyhow::anyhow!("Not a holographic trace file"))?;
+    let bytes = lz4::block::decompress(compressed, None)
+        .map_err(|e| MemoryError::CompressionFailed(e.to_string()))?;
+    trace_from_bytes(&bytes)
+}
+
+/// Serialize a trace: little-endian integers, length-prefixed strings, and
+/// the pattern as its shape followed by its values in row-major order
+fn trace_to_bytes(stored: &StoredTrace) -> Vec<u8> {
+    fn put_str(bytes: &mut Vec<u8>, s: &str) {
+        bytes.extend_from_slice(&(s.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(s.as_bytes());
+    }
+    fn put_strs(bytes: &mut Vec<u8>, strs: &[String]) {
+        bytes.extend_from_slice(&(strs.len() as u32).to_le_bytes());
+        for s in strs {
+            put_str(bytes, s);
+        }
+    }
+
+    let trace = &stored.trace;
+    let mut bytes = Vec::with_capacity(128 + trace.pattern.len() * 8);
+    put_str(&mut bytes, &stored.memory_id);
+    bytes.extend_from_slice(trace.id.as_bytes());
+    bytes.extend_from_slice(&(trace.length as u64).to_le_bytes());
+    put_str(&mut bytes, &trace.content_hash);
+    bytes.extend_from_slice(&trace.timestamp.timestamp_micros().to_le_bytes());
+    put_strs(&mut bytes, &trace.emotional_tags);
+    put_strs(&mut bytes, &trace.context_associations);
+    bytes.extend_from_slice(&(trace.pattern.nrows() as u32).to_le_bytes());
+    bytes.extend_from_slice(&(trace.pattern.ncols() as u32).to_le_bytes());
+    for value in trace.pattern.iter() {
+        bytes.extend_from_slice(&value.to_le_bytes());
+    }
+    bytes
+}
+
+fn trace_from_bytes(mut bytes: &[u8]) -> Result<StoredTrace> {
+    fn take<const N: usize>(bytes: &mut &[u8]) -> Result<[u8; N]> {
+        let (head, rest) = bytes
+            .split_first_chunk::<N>()
+            .ok_or_else(|| anyhow::anyhow!("Truncated holographic trace"))?;
+        *bytes = rest;
+        Ok(*head)
+    }
+    fn take_u32(bytes: &mut &[u8]) -> Result<usize> {
+        Ok(u32::from_le_bytes(take(bytes)?) as usize)
+    }
+    fn take_str(bytes: &mut &[u8]) -> Result<String> {
+        let len = take_u32(bytes)?;
+        if bytes.len() < len {
+            anyhow::bail!("Truncated holographic trace");
+        }
+        let (s, rest) = bytes.split_at(len);
+        *bytes = rest;
+        Ok(String::from_utf8(s.to_vec())?)
+    }
+    fn take_strs(bytes: &mut &[u8]) -> Result<Vec<String>> {
+        (0..take_u32(bytes)?).map(|_| take_str(bytes)).collect()
+    }
 
-        frequencies
+    let memory_id = take_str(&mut bytes)?;
+    let id = Uuid::from_bytes(take(&mut bytes)?);
+    let length = u64::from_le_bytes(take(&mut bytes)?) as usize;
+    let content_hash = take_str(&mut bytes)?;
+    let timestamp = DateTime::from_timestamp_micros(i64::from_le_bytes(take(&mut bytes)?))
+        .ok_or_else(|| anyhow::anyhow!("Holographic trace timestamp out of range"))?;
+    let emotional_tags = take_strs(&mut bytes)?;
+    let context_associations = take_strs(&mut bytes)?;
+    let rows = take_u32(&mut bytes)?;
+    let cols = take_u32(&mut bytes)?;
+    if bytes.len() != rows * cols * 8 {
+        anyhow::bail!("Holographic trace pattern doesn't match its {}x{} shape", rows, cols);
     }
+    let values = bytes
+        .chunks_exact(8)
+        .map(|chunk| f64::from_le_bytes(chunk.try_into().expect("chunks are 8 bytes")))
+        .collect();
+
+    Ok(StoredTrace {
+        memory_id,
+        trace: HolographicTrace {
+            id,
+            pattern: Array2::from_shape_vec((rows, cols), values)?,
+            length,
+            content_hash,
+            timestamp,
+            emotional_tags,
+            context_associations,
+        },
+    })
+}
 
-    /// Convert frequencies back to content
-    fn frequencies_to_content(&self, frequencies: &Array2<f64>) -> Result<String> {
-        let mut content = Vec::with_capacity(self.transform_size);
+impl MemorySystem {
+    /// Encode every record written from now on into a holographic trace,
+    /// stored under `<data_dir>/holographic`
+    ///
+    /// Records written before are encoded by `trace_missing`.
+    pub fn with_holographic(mut self) -> Result<Self> {
+        self.traces = Some(TraceStore::open(self.data_dir.join("holographic"))?);
+        Ok(self)
+    }
 
-        for i in 0..self.transform_size {
-            let magnitude = (frequencies[[i, 0]].powi(2) + frequencies[[i, 1]].powi(2)).sqrt();
-            if magnitude > self.min_strength {
-                let byte = (magnitude * 255.0).round() as u8;
-                content.push(byte);
+    /// Encode committed records that have no trace yet
+    ///
+    /// Returns how many records were encoded.
+    pub async fn trace_missing(&self) -> Result<usize> {
+        let traces = self.trace_store()?;
+        self.flush().await?;
+
+        let mut encoded = 0;
+        for layer in MemoryLayer::ALL {
+            for record in self.all_records(layer)? {
+                if !traces.contains(&record.id).await? {
+                    traces.encode(&record).await?;
+                    encoded += 1;
+                }
             }
         }
+        Ok(encoded)
+    }
 
-        String::from_utf8(content)
-            .map_err(|e| anyhow::anyhow!("Failed to decode content: {}", e))
+    /// Holographic trace of a record
+    pub async fn holographic_trace(&self, id: &str) -> Result<Option<HolographicTrace>> {
+        self.trace_store()?.get(id).await
     }
 
-    /// Calculate hash of content for verification
-    fn hash_content(&self, content: &str) -> String {
-        use sha2::{Sha256, Digest};
-        let mut hasher = Sha256::new();
-        hasher.update(content.as_bytes());
-        format!("{:x}", hasher.finalize())
+    /// Decode a record's content from its holographic trace
+    pub async fn recall_trace(&self, id: &str) -> Result<Option<String>> {
+        self.trace_store()?.recall(id).await
     }
 
-    /// Calculate strength of interference pattern
-    fn calculate_pattern_strength(&self, trace: &HolographicTrace) -> f64 {
-        trace.pattern.iter().sum::<f64>() / (self.transform_size * self.transform_size) as f64
+    /// Records whose holographic traces are at least `threshold` similar
+    /// to the trace of `id`, most similar first
+    pub async fn similar_traces(&self, id: &str, threshold: f64) -> Result<Vec<(String, f64)>> {
+        self.trace_store()?.find_similar(id, threshold).await
     }
 
-    /// Find similar traces using pattern matching
-    pub fn find_similar(&self, trace: &HolographicTrace, threshold: f64) -> Vec<f64> {
-        trace.pattern.axis_iter(ndarray::Axis(0))
-            .into_par_iter()
-            .map(|row| {
-                let similarity = row.iter()
-                    .zip(trace.pattern.iter())
-                    .map(|(a, b)| (a - b).powi(2))
-                    .sum::<f64>()
-                    .sqrt();
-                
-                if similarity < threshold {
-                    similarity
-                } else {
-                    f64::INFINITY
+    /// Keep traces in step with a write; traces are derived data, so
+    /// failures are logged rather than failing the write
+    pub(crate) async fn trace_records(&self, records: &[MemoryRecord]) {
+        if let Some(traces) = &self.traces {
+            for record in records {
+                if let Err(e) = traces.encode(record).await {
+                    warn!("Failed to encode holographic trace of {}: {}", record.id, e);
                 }
-            })
-            .collect()
+            }
+        }
+    }
+
+    pub(crate) async fn untrace_record(&self, layer: MemoryLayer, id: &str) {
+        if let Some(traces) = &self.traces {
+            if let Err(e) = traces.remove(layer, id).await {
+                warn!("Failed to remove holographic trace of {}: {}", id, e);
+            }
+        }
+    }
+
+    fn trace_store(&self) -> Result<&TraceStore> {
+        self.traces
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Memory system has no holographic trace store"))
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::memory::WorkingMemoryConfig;
     use crate::soul::Emotion;
 
     #[test]
@@ -529,7 +938,15 @@ mod tests {
         ).unwrap();
         
         let decoded = memory.decode(&trace).unwrap();
-        assert!(decoded.contains(content));
+        assert_eq!(decoded, content);
+
+        // Content longer than the transform spans several rows
+        let memory = HolographicMemory::with_transform_size(16);
+        let content = "Phoenix's recital is on Friday at 7pm — don't be late! 🎹";
+        let trace = memory.encode(content, vec![], vec![]).unwrap();
+        assert_eq!(trace.pattern.nrows(), content.len().div_ceil(16));
+        assert_eq!(memory.decode(&trace).unwrap(), content);
+        assert_eq!(memory.decode(&memory.encode("", vec![], vec![]).unwrap()).unwrap(), "");
     }
 
     #[test]
@@ -560,9 +977,99 @@ mod tests {
             vec![],
             vec![],
         ).unwrap();
+
+        let trace3 = memory.encode(
+            "router firmware update failed twice",
+            vec![],
+            vec![],
+        ).unwrap();
         
-        let similarities = memory.find_similar(&trace1, 0.5);
-        assert!(!similarities.is_empty());
+        let similar = memory.find_similar(&trace1, [&trace1, &trace2, &trace3], 0.5);
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].0, trace2.id);
+        assert!(similar[0].1 > memory.similarity(&trace1, &trace3));
+    }
+
+    #[test]
+    fn test_trace_file_round_trip() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let path = dir.path().join("recital.trace");
+        let encoder = HolographicMemory::with_transform_size(16);
+        let stored = StoredTrace {
+            memory_id: "recital".to_string(),
+            trace: encoder
+                .encode("Phoenix's recital is on Friday", vec!["joy".to_string()], vec!["Phoenix".to_string()])
+                .unwrap(),
+        };
+
+        write_trace(&path, &stored).unwrap();
+        let read = read_trace(&path).unwrap();
+        assert_eq!(read.memory_id, "recital");
+        assert_eq!(read.trace.id, stored.trace.id);
+        assert_eq!(read.trace.pattern, stored.trace.pattern);
+        assert_eq!(read.trace.emotional_tags, vec!["joy"]);
+        assert_eq!(read.trace.context_associations, vec!["Phoenix"]);
+        assert_eq!(read.trace.timestamp.timestamp_micros(), stored.trace.timestamp.timestamp_micros());
+        assert_eq!(encoder.decode(&read.trace).unwrap(), "Phoenix's recital is on Friday");
+
+        // Truncated files are rejected instead of misread
+        let bytes = trace_to_bytes(&stored);
+        assert!(trace_from_bytes(&bytes[..bytes.len() - 1]).is_err());
+        std::fs::write(&path, b"{\"memory_id\":\"recital\"}").unwrap();
+        assert!(read_trace(&path).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_trace_store_follows_memory_writes() {
+        let dir = tempfile::TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_holographic()
+            .unwrap();
+
+        let first = memory.store(MemoryLayer::Episodic, "Phoenix's recital is on Friday".to_string()).await.unwrap();
+        let second = memory.store(MemoryLayer::Episodic, "Phoenix's recital is on Sunday".to_string()).await.unwrap();
+        memory.store(MemoryLayer::LongTerm, "Router firmware updated".to_string()).await.unwrap();
+
+        assert_eq!(
+            memory.recall_trace(&first).await.unwrap().as_deref(),
+            Some("Phoenix's recital is on Friday")
+        );
+        let similar = memory.similar_traces(&first, 0.8).await.unwrap();
+        assert_eq!(similar.len(), 1);
+        assert_eq!(similar[0].0, second);
+
+        // Traces survive a restart and are removed with their records
+        drop(memory);
+        let memory = MemorySystem::new(dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_holographic()
+            .unwrap();
+        assert_eq!(memory.trace_missing().await.unwrap(), 0);
+        assert!(memory.holographic_trace(&second).await.unwrap().is_some());
+        memory.delete(&second).await.unwrap();
+        assert!(memory.holographic_trace(&second).await.unwrap().is_none());
+        assert!(memory.similar_traces(&first, 0.8).await.unwrap().is_empty());
+
+        // A moved record leaves no trace behind in its old layer
+        memory.move_layer(&first, MemoryLayer::LongTerm).await.unwrap();
+        assert!(!memory.traces.as_ref().unwrap().trace_path(MemoryLayer::Episodic, &first).exists());
+        memory.delete(&first).await.unwrap();
+        assert!(memory.holographic_trace(&first).await.unwrap().is_none());
+
+        // Neither does a record spilled out of working memory
+        let memory = memory.with_working_memory(WorkingMemoryConfig {
+            capacity: 1,
+            ..WorkingMemoryConfig::default()
+        });
+        let spilled = memory.store(MemoryLayer::Working, "Call the plumber".to_string()).await.unwrap();
+        memory.store(MemoryLayer::Working, "Buy milk".to_string()).await.unwrap();
+        assert_eq!(memory.get(&spilled).await.unwrap().unwrap().layer, MemoryLayer::ShortTerm);
+        assert!(!memory.traces.as_ref().unwrap().trace_path(MemoryLayer::Working, &spilled).exists());
+        memory.delete(&spilled).await.unwrap();
+        assert!(memory.holographic_trace(&spilled).await.unwrap().is_none());
     }
 
     #[test]
@@ -616,6 +1123,7 @@ mod tests {
             emotional_state: Emotion::default(),
             attention_focus: "first".to_string(),
             metacognition_level: 0.0,
+            timestamp: Utc::now(),
         };
         let m2 = crate::consciousness::ConsciousnessMetrics {
             phi_value: 2.0,
@@ -623,6 +1131,7 @@ mod tests {
             emotional_state: Emotion::default(),
             attention_focus: "second".to_string(),
             metacognition_level: 0.0,
+            timestamp: Utc::now(),
         };
         let m3 = crate::consciousness::ConsciousnessMetrics {
             phi_value: 3.0,
@@ -630,6 +1139,7 @@ mod tests {
             emotional_state: Emotion::default(),
             attention_focus: "third".to_string(),
             metacognition_level: 0.0,
+            timestamp: Utc::now(),
         };
 
         store.record(m1).unwrap();
@@ -653,6 +1163,7 @@ mod tests {
             emotional_state: Emotion::default(),
             attention_focus: "zero-capacity".to_string(),
             metacognition_level: 0.0,
+            timestamp: Utc::now(),
         };
 
         store.record(m).unwrap();
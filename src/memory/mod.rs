@@ -1,12 +1,64 @@
 use chrono::{DateTime, Utc};
+use dashmap::DashMap;
+use rayon::prelude::*;
 use serde::{Deserialize, Serialize};
-use std::path::PathBuf;
-use tantivy::collector::TopDocs;
-use tantivy::query::QueryParser;
+use std::collections::{HashMap, HashSet};
+use metrics::counter;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::sync::{Arc, Mutex, RwLock};
+use std::time::{Duration, Instant};
+use tantivy::collector::{DocSetCollector, TopDocs};
+use std::ops::Bound;
+use tantivy::query::{
+    AllQuery, BooleanQuery, ConstScoreQuery, Occur, Query, RangeQuery, TermQuery,
+};
 use tantivy::schema::*;
-use tantivy::{Index, IndexWriter, TantivyDocument};
+use tantivy::{
+    DocAddress, DocId, Index, IndexReader, IndexWriter, Order, ReloadPolicy, Score, Searcher,
+    SegmentReader, SnippetGenerator, TantivyDocument,
+};
+use tokio::task::JoinHandle;
+use sqlx::SqlitePool;
+use tracing::{error, info, warn};
 use uuid::Uuid;
 
+use crate::db::{DatabaseOperations, DbMetrics};
+use crate::soul::SoulStorage;
+
+pub mod access;
+pub mod consolidation;
+pub mod dedup;
+pub mod embedding;
+pub mod episode;
+pub mod export;
+pub mod graph;
+pub mod highlight;
+pub mod holographic;
+pub mod query;
+pub mod retention;
+mod schema;
+pub mod store;
+pub mod summarize;
+pub mod working;
+
+pub use access::{AccessConfig, AccessStats, AccessStore, AccessedMemory, LayerAccessReport};
+pub use consolidation::{ConsolidationConfig, ConsolidationReport, Consolidator};
+pub use dedup::{DedupConfig, DuplicatePolicy};
+pub use embedding::{Embedder, EmbeddingConfig, HashingEmbedder, RemoteEmbedder};
+pub use episode::{EmotionalBeat, Episode, EpisodeStore};
+pub use export::{ExportHeader, IdCollision, ImportReport};
+pub use graph::{
+    Assertion, KnowledgeConfig, KnowledgeGraph, Neighbor, NewTriple, Triple, TripleObject, TriplePattern,
+};
+pub use highlight::MemorySnippet;
+pub use holographic::{HolographicMemory, HolographicTrace, TraceStore};
+pub use query::{MemoryQuery, QueryError, TextTerm};
+pub use retention::{RetentionConfig, RetentionPolicy, RetentionReport, RetentionSweeper};
+pub use store::{ConsistencyReport, LayerConsistency};
+pub use summarize::{LanguageModel, SummarizedFact, Summarizer, SummarizerConfig, SummaryReport};
+pub use working::{WorkingMemory, WorkingMemoryConfig, WorkingSlot};
+
 /// Represents a memory record
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MemoryRecord {
@@ -14,19 +66,470 @@ pub struct MemoryRecord {
     pub content: String,
     pub timestamp: DateTime<Utc>,
     pub layer: MemoryLayer,
+    /// Owner of the memory, or `SHARED_NAMESPACE` for system memories that
+    /// everyone can see
+    #[serde(default = "default_namespace")]
+    pub namespace: String,
+    /// Free-form labels, matched exactly (case-insensitive) by filters
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Names of the people, places or things the memory is about
+    #[serde(default)]
+    pub entities: Vec<String>,
+    /// Where the memory came from, e.g. "cli", "api" or "mqtt"
+    #[serde(default)]
+    pub source: Option<String>,
+    /// How much the memory matters, from 0.0 to 1.0
+    #[serde(default = "default_importance")]
+    pub importance: f32,
+    /// Emotions associated with the memory
+    #[serde(default)]
+    pub emotional_tags: Vec<String>,
+    /// Pinned records are never expired or forgotten
+    #[serde(default)]
+    pub pinned: bool,
+    /// Near-duplicates merged into this record
+    #[serde(default)]
+    pub reinforcements: u64,
+    /// Last time a near-duplicate was merged into this record
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub last_reinforced: Option<DateTime<Utc>>,
+    /// IDs of the memories this one was derived from, e.g. the episodic
+    /// records a semantic fact was summarized from
+    #[serde(default)]
+    pub derived_from: Vec<String>,
+    /// Embedding of `content`, kept in the index but not serialized
+    #[serde(skip)]
+    pub embedding: Option<Vec<f32>>,
+    /// Relevance score when the record comes from a search
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub score: Option<f32>,
+    /// Best matching part of `content` when the search asked for highlights
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub snippet: Option<MemorySnippet>,
+    /// Tantivy's explanation of the keyword score when the search asked for
+    /// explanations
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub explanation: Option<serde_json::Value>,
+}
+
+/// Importance given to memories stored without one
+pub const DEFAULT_IMPORTANCE: f32 = 0.5;
+
+fn default_importance() -> f32 {
+    DEFAULT_IMPORTANCE
+}
+
+/// Namespace of system memories, visible to every user
+///
+/// Memories stored without an owner, including everything stored before
+/// namespaces existed, belong to it.
+pub const SHARED_NAMESPACE: &str = "shared";
+
+fn default_namespace() -> String {
+    SHARED_NAMESPACE.to_string()
+}
+
+impl MemoryRecord {
+    /// Whether `owner` may see the record: it is theirs or shared
+    pub fn visible_to(&self, owner: &str) -> bool {
+        self.namespace == owner || self.namespace == SHARED_NAMESPACE
+    }
+}
+
+/// A memory to be stored, with optional metadata
+#[derive(Debug, Clone)]
+pub struct NewMemory {
+    pub content: String,
+    pub namespace: String,
+    pub tags: Vec<String>,
+    pub entities: Vec<String>,
+    pub source: Option<String>,
+    pub importance: f32,
+    pub emotional_tags: Vec<String>,
+    pub pinned: bool,
+    pub derived_from: Vec<String>,
+}
+
+impl NewMemory {
+    pub fn new(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            namespace: default_namespace(),
+            tags: Vec::new(),
+            entities: Vec::new(),
+            source: None,
+            importance: DEFAULT_IMPORTANCE,
+            emotional_tags: Vec::new(),
+            pinned: false,
+            derived_from: Vec::new(),
+        }
+    }
+
+    /// Store the memory in an owner's namespace instead of the shared one
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespace = namespace.into();
+        self
+    }
+
+    pub fn with_tags<S: Into<String>>(mut self, tags: impl IntoIterator<Item = S>) -> Self {
+        self.tags.extend(tags.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_entities<S: Into<String>>(mut self, entities: impl IntoIterator<Item = S>) -> Self {
+        self.entities.extend(entities.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    /// Set the importance, clamped to 0.0..=1.0
+    pub fn with_importance(mut self, importance: f32) -> Self {
+        self.importance = importance.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn with_emotional_tags<S: Into<String>>(mut self, emotions: impl IntoIterator<Item = S>) -> Self {
+        self.emotional_tags.extend(emotions.into_iter().map(Into::into));
+        self
+    }
+
+    /// Exempt the memory from expiry and forgetting
+    pub fn pinned(mut self, pinned: bool) -> Self {
+        self.pinned = pinned;
+        self
+    }
+
+    /// Link the memory to the records it was derived from
+    pub fn with_derived_from<S: Into<String>>(mut self, ids: impl IntoIterator<Item = S>) -> Self {
+        self.derived_from.extend(ids.into_iter().map(Into::into));
+        self
+    }
+
+    pub(crate) fn into_record(self, layer: MemoryLayer, timestamp: DateTime<Utc>) -> MemoryRecord {
+        MemoryRecord {
+            id: Uuid::new_v4().to_string(),
+            content: self.content,
+            timestamp,
+            layer,
+            namespace: self.namespace,
+            tags: self.tags,
+            entities: self.entities,
+            source: self.source,
+            importance: self.importance,
+            emotional_tags: self.emotional_tags,
+            pinned: self.pinned,
+            reinforcements: 0,
+            last_reinforced: None,
+            derived_from: self.derived_from,
+            embedding: None,
+            score: None,
+            snippet: None,
+            explanation: None,
+        }
+    }
+}
+
+impl From<String> for NewMemory {
+    fn from(content: String) -> Self {
+        Self::new(content)
+    }
+}
+
+impl From<&str> for NewMemory {
+    fn from(content: &str) -> Self {
+        Self::new(content)
+    }
+}
+
+/// Metadata filters for a search; every condition must hold
+///
+/// Label values (tags, entities, source, emotional tags) match whole values,
+/// ignoring case.
+#[derive(Debug, Clone, Default)]
+pub struct MemoryFilter {
+    /// Records must belong to one of these namespaces; any namespace when
+    /// empty
+    pub namespaces: Vec<String>,
+    /// Records must carry all of these tags
+    pub tags: Vec<String>,
+    /// Records must mention all of these entities
+    pub entities: Vec<String>,
+    pub source: Option<String>,
+    /// Records must carry all of these emotional tags
+    pub emotional_tags: Vec<String>,
+    pub min_importance: Option<f32>,
+    /// Only records stored at or after this time
+    pub since: Option<DateTime<Utc>>,
+    /// Only records stored at or before this time
+    pub until: Option<DateTime<Utc>>,
+}
+
+impl MemoryFilter {
+    /// Only the records `owner` can see: theirs and the shared ones
+    pub fn visible_to(owner: impl Into<String>) -> Self {
+        Self::default()
+            .with_namespace(owner)
+            .with_namespace(SHARED_NAMESPACE)
+    }
+
+    pub fn with_namespace(mut self, namespace: impl Into<String>) -> Self {
+        self.namespaces.push(namespace.into());
+        self
+    }
+
+    pub fn with_tag(mut self, tag: impl Into<String>) -> Self {
+        self.tags.push(tag.into());
+        self
+    }
+
+    pub fn with_entity(mut self, entity: impl Into<String>) -> Self {
+        self.entities.push(entity.into());
+        self
+    }
+
+    pub fn with_source(mut self, source: impl Into<String>) -> Self {
+        self.source = Some(source.into());
+        self
+    }
+
+    pub fn with_emotional_tag(mut self, emotion: impl Into<String>) -> Self {
+        self.emotional_tags.push(emotion.into());
+        self
+    }
+
+    pub fn with_min_importance(mut self, importance: f32) -> Self {
+        self.min_importance = Some(importance);
+        self
+    }
+
+    pub fn with_since(mut self, since: DateTime<Utc>) -> Self {
+        self.since = Some(since);
+        self
+    }
+
+    pub fn with_until(mut self, until: DateTime<Utc>) -> Self {
+        self.until = Some(until);
+        self
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.namespaces.is_empty()
+            && self.tags.is_empty()
+            && self.entities.is_empty()
+            && self.source.is_none()
+            && self.emotional_tags.is_empty()
+            && self.min_importance.is_none()
+            && self.since.is_none()
+            && self.until.is_none()
+    }
+
+    /// Non-scoring query clauses for each condition
+    fn clauses(&self, schema: &Schema) -> anyhow::Result<Vec<(Occur, Box<dyn Query>)>> {
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+
+        if !self.namespaces.is_empty() {
+            let field = schema.get_field("namespace")?;
+            let any_namespace: Vec<(Occur, Box<dyn Query>)> = self
+                .namespaces
+                .iter()
+                .map(|namespace| {
+                    let term = Term::from_field_text(field, namespace);
+                    let query: Box<dyn Query> = Box::new(TermQuery::new(term, IndexRecordOption::Basic));
+                    (Occur::Should, query)
+                })
+                .collect();
+            let query = BooleanQuery::new(any_namespace);
+            clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(Box::new(query), 0.0))));
+        }
+
+        let labels = [
+            ("tags", self.tags.iter().collect::<Vec<_>>()),
+            ("entities", self.entities.iter().collect()),
+            ("source", self.source.iter().collect()),
+            ("emotional_tags", self.emotional_tags.iter().collect()),
+        ];
+        for (field_name, values) in labels {
+            let field = schema.get_field(field_name)?;
+            for value in values {
+                let term = Term::from_field_text(field, &value.to_lowercase());
+                let query = TermQuery::new(term, IndexRecordOption::Basic);
+                clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(Box::new(query), 0.0))));
+            }
+        }
+
+        if let Some(min) = self.min_importance {
+            let query = RangeQuery::new_f64_bounds(
+                "importance".to_string(),
+                Bound::Included(min as f64),
+                Bound::Unbounded,
+            );
+            clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(Box::new(query), 0.0))));
+        }
+
+        if self.since.is_some() || self.until.is_some() {
+            let bound = |time: Option<DateTime<Utc>>| match time {
+                Some(time) => Bound::Included(tantivy::DateTime::from_timestamp_micros(time.timestamp_micros())),
+                None => Bound::Unbounded,
+            };
+            let query = RangeQuery::new_date_bounds(
+                "timestamp".to_string(),
+                bound(self.since),
+                bound(self.until),
+            );
+            clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(Box::new(query), 0.0))));
+        }
+
+        Ok(clauses)
+    }
+}
+
+/// Exponential recency boost applied on top of BM25
+///
+/// A record's score is multiplied by `1 - weight + weight * 0.5^(age / half_life)`,
+/// so with `weight = 1.0` a record one half-life old scores half as much as
+/// an identical fresh one, and with `weight = 0.0` age is ignored.
+#[derive(Debug, Clone, Copy)]
+pub struct RecencyDecay {
+    /// Age at which the decaying part of the score halves
+    pub half_life: Duration,
+    /// Share of the score subject to decay, from 0.0 to 1.0
+    pub weight: f32,
+}
+
+impl RecencyDecay {
+    pub fn new(half_life: Duration, weight: f32) -> Self {
+        Self {
+            half_life,
+            weight: weight.clamp(0.0, 1.0),
+        }
+    }
+
+    /// Score multiplier for a record of the given age
+    pub fn factor(&self, age: Duration) -> f32 {
+        let half_lives = age.as_secs_f64() / self.half_life.as_secs_f64().max(f64::EPSILON);
+        let decayed = 0.5f64.powf(half_lives) as f32;
+        1.0 - self.weight + self.weight * decayed
+    }
+}
+
+/// How a query is matched against records
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum SearchMode {
+    /// BM25 keyword search
+    #[default]
+    Keyword,
+    /// BM25 and embedding cosine similarity, fused by reciprocal rank
+    Hybrid,
+}
+
+/// Options for searching a single layer
+#[derive(Debug, Clone)]
+pub struct SearchOptions {
+    /// Maximum number of results
+    pub limit: usize,
+    pub filter: MemoryFilter,
+    /// Boost recent records (BM25 only if absent)
+    pub recency: Option<RecencyDecay>,
+    pub mode: SearchMode,
+    /// Attach a highlighted snippet to each result
+    pub highlight: bool,
+    /// Attach the explanation of its keyword score to each result
+    pub explain: bool,
+    /// Count the results as retrievals in their access statistics
+    ///
+    /// Callers that fetch more results than they return turn this off and
+    /// count the returned ones with `record_access` themselves.
+    pub record_access: bool,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            limit: 10,
+            filter: MemoryFilter::default(),
+            recency: None,
+            mode: SearchMode::default(),
+            highlight: false,
+            explain: false,
+            record_access: true,
+        }
+    }
+}
+
+impl SearchOptions {
+    /// Create options returning at most `limit` results
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    pub fn with_filter(mut self, filter: MemoryFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_recency(mut self, recency: RecencyDecay) -> Self {
+        self.recency = Some(recency);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_highlights(mut self) -> Self {
+        self.highlight = true;
+        self
+    }
+
+    pub fn with_explanations(mut self) -> Self {
+        self.explain = true;
+        self
+    }
+
+    pub fn without_access_recording(mut self) -> Self {
+        self.record_access = false;
+        self
+    }
 }
 
 /// The five memory layers
+///
+/// Serialized by their snake_case names, e.g. `long_term`. The PascalCase
+/// names that exports and stored JSON used before are still accepted.
 #[derive(Debug, Clone, Copy, Serialize, Deserialize, PartialEq, Eq, Hash)]
+#[serde(rename_all = "snake_case")]
 pub enum MemoryLayer {
+    #[serde(alias = "ShortTerm")]
     ShortTerm,
+    #[serde(alias = "LongTerm")]
     LongTerm,
+    #[serde(alias = "Working")]
     Working,
+    #[serde(alias = "Episodic")]
     Episodic,
+    #[serde(alias = "Semantic")]
     Semantic,
 }
 
 impl MemoryLayer {
+    /// All layers, in index creation order
+    pub const ALL: [MemoryLayer; 5] = [
+        MemoryLayer::ShortTerm,
+        MemoryLayer::LongTerm,
+        MemoryLayer::Working,
+        MemoryLayer::Episodic,
+        MemoryLayer::Semantic,
+    ];
+
     pub fn as_str(&self) -> &'static str {
         match self {
             MemoryLayer::ShortTerm => "short_term",
@@ -38,92 +541,591 @@ impl MemoryLayer {
     }
 }
 
+impl FromStr for MemoryLayer {
+    type Err = anyhow::Error;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        MemoryLayer::ALL
+            .into_iter()
+            .find(|layer| layer.as_str() == s)
+            .ok_or_else(|| anyhow::anyhow!("Unknown memory layer: {}", s))
+    }
+}
+
+/// Options for searching across several layers at once
+#[derive(Debug, Clone)]
+pub struct SearchAllOptions {
+    /// Maximum number of merged results
+    pub limit: usize,
+    /// Layers to search (all layers by default)
+    pub layers: Vec<MemoryLayer>,
+    /// Multiplier applied to each layer's normalized scores (1.0 if absent)
+    pub layer_weights: HashMap<MemoryLayer, f32>,
+    pub filter: MemoryFilter,
+    /// Boost recent records within each layer before normalization
+    pub recency: Option<RecencyDecay>,
+    pub mode: SearchMode,
+    /// Attach a highlighted snippet to each result
+    pub highlight: bool,
+    /// Attach the explanation of its keyword score to each result
+    pub explain: bool,
+    /// Count the results as retrievals in their access statistics
+    ///
+    /// Callers that fetch more results than they return turn this off and
+    /// count the returned ones with `record_access` themselves.
+    pub record_access: bool,
+}
+
+impl Default for SearchAllOptions {
+    fn default() -> Self {
+        Self {
+            limit: 10,
+            layers: MemoryLayer::ALL.to_vec(),
+            layer_weights: HashMap::new(),
+            filter: MemoryFilter::default(),
+            recency: None,
+            mode: SearchMode::default(),
+            highlight: false,
+            explain: false,
+            record_access: true,
+        }
+    }
+}
+
+impl SearchAllOptions {
+    /// Create options returning at most `limit` results
+    pub fn new(limit: usize) -> Self {
+        Self {
+            limit,
+            ..Default::default()
+        }
+    }
+
+    /// Restrict the search to the given layers
+    pub fn with_layers(mut self, layers: Vec<MemoryLayer>) -> Self {
+        self.layers = layers;
+        self
+    }
+
+    /// Set the score weight for a layer
+    pub fn with_weight(mut self, layer: MemoryLayer, weight: f32) -> Self {
+        self.layer_weights.insert(layer, weight);
+        self
+    }
+
+    pub fn with_filter(mut self, filter: MemoryFilter) -> Self {
+        self.filter = filter;
+        self
+    }
+
+    pub fn with_recency(mut self, recency: RecencyDecay) -> Self {
+        self.recency = Some(recency);
+        self
+    }
+
+    pub fn with_mode(mut self, mode: SearchMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn with_highlights(mut self) -> Self {
+        self.highlight = true;
+        self
+    }
+
+    pub fn with_explanations(mut self) -> Self {
+        self.explain = true;
+        self
+    }
+
+    pub fn without_access_recording(mut self) -> Self {
+        self.record_access = false;
+        self
+    }
+
+    /// Weight applied to a layer's scores
+    pub fn weight(&self, layer: MemoryLayer) -> f32 {
+        self.layer_weights.get(&layer).copied().unwrap_or(1.0)
+    }
+}
+
+/// Memory budget handed to each layer's long-lived index writer
+const WRITER_MEMORY_BUDGET: usize = 50_000_000;
+
+/// Candidates taken from each ranking before hybrid fusion
+const HYBRID_CANDIDATES: usize = 50;
+
+/// Reciprocal-rank fusion constant (the value used in the original RRF paper)
+const RRF_K: f32 = 60.0;
+
+/// When staged index writes are committed
+///
+/// Writes are staged on a per-layer writer and become searchable once the
+/// layer is committed: as soon as `max_pending` writes are waiting, when the
+/// oldest staged write is older than `max_delay`, or on an explicit
+/// [`MemorySystem::flush`].
+#[derive(Debug, Clone)]
+pub struct CommitPolicy {
+    /// Commit a layer once this many writes are staged
+    pub max_pending: usize,
+    /// Commit a layer once its oldest staged write is this old
+    pub max_delay: Duration,
+}
+
+impl Default for CommitPolicy {
+    fn default() -> Self {
+        Self {
+            max_pending: 256,
+            max_delay: Duration::from_secs(1),
+        }
+    }
+}
+
+impl CommitPolicy {
+    /// Commit after every write
+    pub fn immediate() -> Self {
+        Self {
+            max_pending: 1,
+            max_delay: Duration::ZERO,
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct PendingWrites {
+    count: usize,
+    since: Option<Instant>,
+    /// Records staged since the last commit by id, `None` for a staged
+    /// deletion, so lookups can see writes the reader doesn't serve yet
+    records: HashMap<String, Option<StagedRecord>>,
+}
+
+/// A record staged on a layer's writer, with its content fingerprint
+#[derive(Debug, Clone)]
+struct StagedRecord {
+    record: MemoryRecord,
+    fingerprint: u64,
+}
+
+/// A change to one record staged on a layer's writer
+enum Staged<'a> {
+    Write(&'a MemoryRecord),
+    Delete(&'a str),
+}
+
+/// A layer's index together with its long-lived writer and reader
+struct LayerIndex {
+    index: Index,
+    reader: IndexReader,
+    /// Opened on first write, so read-only users never take the writer lock
+    writer: RwLock<Option<IndexWriter>>,
+    pending: Mutex<PendingWrites>,
+}
+
+impl LayerIndex {
+    fn new(index: Index) -> anyhow::Result<Self> {
+        let reader = index
+            .reader_builder()
+            .reload_policy(ReloadPolicy::OnCommitWithDelay)
+            .try_into()?;
+
+        Ok(Self {
+            index,
+            reader,
+            writer: RwLock::new(None),
+            pending: Mutex::new(PendingWrites::default()),
+        })
+    }
+
+    /// Stage `writes` operations on the layer's writer without committing
+    ///
+    /// `changes` lists the records the operations write or delete, which are
+    /// tracked until the next commit.
+    fn stage<T>(
+        &self,
+        writes: usize,
+        changes: &[Staged],
+        op: impl FnOnce(&IndexWriter) -> anyhow::Result<T>,
+    ) -> anyhow::Result<T> {
+        {
+            let guard = self.writer.read().map_err(|_| lock_poisoned())?;
+            if let Some(writer) = guard.as_ref() {
+                let result = op(writer)?;
+                self.record_pending(writes, changes)?;
+                return Ok(result);
+            }
+        }
+
+        let mut guard = self.writer.write().map_err(|_| lock_poisoned())?;
+        if guard.is_none() {
+            *guard = Some(self.index.writer(WRITER_MEMORY_BUDGET)?);
+        }
+        let writer = guard.as_ref().expect("writer was just opened");
+        let result = op(writer)?;
+        self.record_pending(writes, changes)?;
+        Ok(result)
+    }
+
+    fn record_pending(&self, writes: usize, changes: &[Staged]) -> anyhow::Result<()> {
+        let mut pending = self.pending.lock().map_err(|_| lock_poisoned())?;
+        pending.count += writes;
+        pending.since.get_or_insert_with(Instant::now);
+        for change in changes {
+            match change {
+                Staged::Write(record) => {
+                    let staged = StagedRecord {
+                        record: (*record).clone(),
+                        fingerprint: dedup::simhash(&record.content),
+                    };
+                    pending.records.insert(record.id.clone(), Some(staged));
+                }
+                Staged::Delete(id) => {
+                    pending.records.insert(id.to_string(), None);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Run `f` on the staged records and a searcher over the committed ones
+    ///
+    /// Both are taken under the pending lock, which `commit` holds until the
+    /// reader has reloaded, so no write is missing from both.
+    fn with_staged<T>(
+        &self,
+        f: impl FnOnce(&HashMap<String, Option<StagedRecord>>, Searcher) -> T,
+    ) -> anyhow::Result<T> {
+        let pending = self.pending.lock().map_err(|_| lock_poisoned())?;
+        Ok(f(&pending.records, self.reader.searcher()))
+    }
+
+    fn is_due(&self, policy: &CommitPolicy) -> anyhow::Result<bool> {
+        let pending = self.pending.lock().map_err(|_| lock_poisoned())?;
+        Ok(match pending.since {
+            Some(since) => pending.count >= policy.max_pending || since.elapsed() >= policy.max_delay,
+            None => false,
+        })
+    }
+
+    /// Commit staged writes and reload the reader so they are searchable
+    ///
+    /// Returns `false` if nothing was staged.
+    fn commit(&self) -> anyhow::Result<bool> {
+        let mut guard = self.writer.write().map_err(|_| lock_poisoned())?;
+        let Some(writer) = guard.as_mut() else {
+            return Ok(false);
+        };
+        let mut pending = self.pending.lock().map_err(|_| lock_poisoned())?;
+        if pending.since.is_none() {
+            return Ok(false);
+        }
+
+        writer.commit()?;
+        drop(guard);
+        // Staged records stay visible through `with_staged` until the reader
+        // serves them
+        self.reader.reload()?;
+        let committed = pending.count;
+        *pending = PendingWrites::default();
+        drop(pending);
+
+        counter!("memory.index.commits_total", 1);
+        counter!("memory.index.committed_writes_total", committed as u64);
+        Ok(true)
+    }
+}
+
+fn lock_poisoned() -> anyhow::Error {
+    anyhow::anyhow!("memory index lock poisoned")
+}
+
 /// Manages the 5-layer memory system with Tantivy indexing
+///
+/// Each layer owns one long-lived `IndexWriter` and `IndexReader`. Stores are
+/// staged and committed in batches according to the [`CommitPolicy`], so a
+/// freshly stored record becomes searchable after the next commit. Lookups
+/// by id (`get`) also see staged changes, and `update`, `delete` and
+/// `move_layer` commit their own changes immediately.
+///
+/// With a database attached (`with_database`), every write is first stored
+/// in the `memory_records` table, which the indices can be rebuilt from.
 pub struct MemorySystem {
-    indices: std::collections::HashMap<MemoryLayer, Index>,
-    #[allow(dead_code)]
-    data_dir: PathBuf, // Kept for potential future use (backup paths, etc.)
+    layers: HashMap<MemoryLayer, LayerIndex>,
+    commit_policy: CommitPolicy,
+    embedder: Arc<dyn Embedder>,
+    /// Source of truth for records when set; the indices are derived from it
+    store: Option<DatabaseOperations>,
+    /// Near-duplicate handling for `store`; off when unset
+    dedup: Option<DedupConfig>,
+    /// Holographic traces of every written record when set
+    traces: Option<TraceStore>,
+    /// Slots holding the records of the Working layer
+    working: WorkingMemory,
+    /// Episodes grouping Episodic records
+    episodes: EpisodeStore,
+    /// Access statistics of each record returned by `search` since startup
+    access: DashMap<String, AccessStats>,
+    /// Persisted access statistics when set
+    access_store: Option<AccessStore>,
+    /// Links from soul entities to records, unlinked when a record is
+    /// forgotten; set with the database
+    souls: Option<SoulStorage>,
+    access_config: AccessConfig,
+    data_dir: PathBuf,
 }
 
 impl MemorySystem {
     /// Create a new memory system with indices for all layers
     pub async fn new(data_dir: PathBuf) -> anyhow::Result<Self> {
+        Self::with_commit_policy(data_dir, CommitPolicy::default()).await
+    }
+
+    /// Create a new memory system that batches commits according to `commit_policy`
+    pub async fn with_commit_policy(
+        data_dir: PathBuf,
+        commit_policy: CommitPolicy,
+    ) -> anyhow::Result<Self> {
         // Ensure data directory exists
         tokio::fs::create_dir_all(&data_dir).await?;
 
-        let mut indices = std::collections::HashMap::new();
+        let mut layers = HashMap::new();
 
         // Create index for each memory layer
-        for layer in [
-            MemoryLayer::ShortTerm,
-            MemoryLayer::LongTerm,
-            MemoryLayer::Working,
-            MemoryLayer::Episodic,
-            MemoryLayer::Semantic,
-        ] {
+        for layer in MemoryLayer::ALL {
             let layer_dir = data_dir.join(layer.as_str());
             tokio::fs::create_dir_all(&layer_dir).await?;
 
-            let mut schema_builder = Schema::builder();
-            schema_builder.add_text_field("id", STRING | STORED);
-            schema_builder.add_text_field("content", TEXT | STORED);
-            schema_builder.add_date_field("timestamp", INDEXED | STORED);
-            let schema = schema_builder.build();
-
-            // Try to open existing index, or create new one if it doesn't exist
-            let index = match Index::open_in_dir(&layer_dir) {
-                Ok(idx) => {
-                    // Index exists, verify schema matches
-                    idx
-                }
-                Err(_) => {
-                    // Index doesn't exist, create it
-                    Index::create_in_dir(&layer_dir, schema)?
-                }
-            };
-            indices.insert(layer, index);
+            let index = schema::open_index(&layer_dir, layer)?;
+            layers.insert(layer, LayerIndex::new(index)?);
         }
 
-        Ok(Self { indices, data_dir })
+        Ok(Self {
+            layers,
+            commit_policy,
+            embedder: Arc::new(HashingEmbedder::default()),
+            store: None,
+            dedup: None,
+            traces: None,
+            working: WorkingMemory::new(WorkingMemoryConfig::default()),
+            episodes: EpisodeStore::open(data_dir.join("episodes"))?,
+            access: DashMap::new(),
+            access_store: None,
+            souls: None,
+            access_config: AccessConfig::default(),
+            data_dir,
+        })
+    }
+
+    /// Replace the default offline embedder
+    ///
+    /// Records embedded by a different embedder are re-embedded by
+    /// `embed_missing` when the vector sizes differ.
+    pub fn with_embedder(mut self, embedder: Arc<dyn Embedder>) -> Self {
+        self.embedder = embedder;
+        self
+    }
+
+    /// Write every record through to the `memory_records` table of `pool`,
+    /// and access statistics to its `memory_access` table
+    ///
+    /// Soul memory links in the same database are removed along with the
+    /// records they point to.
+    pub fn with_database(mut self, pool: SqlitePool) -> Self {
+        self.store = Some(DatabaseOperations::new(pool.clone(), DbMetrics::new()));
+        self.access_store = Some(AccessStore::new(pool.clone()));
+        self.souls = Some(SoulStorage::new(pool));
+        self
+    }
+
+    /// Check stored records for near-duplicates in their layer and apply the
+    /// layer's [`DuplicatePolicy`]
+    ///
+    /// Staged writes that are not committed yet are checked too.
+    /// `store_batch` never deduplicates.
+    pub fn with_dedup(mut self, config: DedupConfig) -> Self {
+        self.dedup = Some(config);
+        self
+    }
+
+    /// Move aside layer indices that can no longer be opened, so that `new`
+    /// creates empty ones for `rebuild_index` to refill from the database
+    ///
+    /// Each unreadable index is renamed to `<layer>.corrupt`. Returns the
+    /// affected layers.
+    pub fn quarantine_unreadable_indices(data_dir: &Path) -> anyhow::Result<Vec<MemoryLayer>> {
+        let mut quarantined = Vec::new();
+        for layer in MemoryLayer::ALL {
+            let layer_dir = data_dir.join(layer.as_str());
+            if !layer_dir.exists() {
+                continue;
+            }
+
+            let readable = schema::open_index(&layer_dir, layer).and_then(|index| Ok(index.reader()?));
+            if let Err(e) = readable {
+                let corrupt_dir = layer_dir.with_extension("corrupt");
+                warn!(
+                    "{} index is unreadable ({}); moving it to {}",
+                    layer.as_str(),
+                    e,
+                    corrupt_dir.display()
+                );
+                if corrupt_dir.exists() {
+                    std::fs::remove_dir_all(&corrupt_dir)?;
+                }
+                std::fs::rename(&layer_dir, &corrupt_dir)?;
+                quarantined.push(layer);
+            }
+        }
+        Ok(quarantined)
     }
 
     /// Store a memory record in the specified layer
     pub async fn store(&self, layer: MemoryLayer, content: String) -> anyhow::Result<String> {
-        let id = Uuid::new_v4().to_string();
-        let timestamp = Utc::now();
+        self.store_with(layer, NewMemory::new(content)).await
+    }
 
-        let record = MemoryRecord {
-            id: id.clone(),
-            content: content.clone(),
-            timestamp,
-            layer,
-        };
+    /// Store a memory together with its metadata
+    ///
+    /// Returns the new record's ID, or the ID of the existing record it was
+    /// skipped for or merged into as a near-duplicate.
+    pub async fn store_with(&self, layer: MemoryLayer, memory: NewMemory) -> anyhow::Result<String> {
+        let record = memory.into_record(layer, Utc::now());
+        if let Some(existing_id) = self.deduplicate(&record).await? {
+            return Ok(existing_id);
+        }
 
-        // Get the index for this layer
-        let index = self.indices.get(&layer).ok_or_else(|| {
-            anyhow::anyhow!("Index not found for layer: {:?}", layer)
+        let mut records = [record];
+        self.embed_records(&mut records).await;
+        let [record] = records;
+        self.write_record(&record).await?;
+        if layer == MemoryLayer::Working {
+            self.hold_working(std::slice::from_ref(&record), |record| record.importance as f64)
+                .await?;
+        }
+
+        Ok(record.id)
+    }
+
+    /// Store many records in one layer with a single staging pass
+    ///
+    /// Returns the new ids in input order.
+    pub async fn store_batch<M: Into<NewMemory>>(
+        &self,
+        layer: MemoryLayer,
+        memories: Vec<M>,
+    ) -> anyhow::Result<Vec<String>> {
+        let timestamp = Utc::now();
+        let mut records: Vec<MemoryRecord> = memories
+            .into_iter()
+            .map(|memory| memory.into().into_record(layer, timestamp))
+            .collect();
+        self.embed_records(&mut records).await;
+        if let Some(store) = &self.store {
+            store.upsert_memory_records(&records).await?;
+        }
+        self.trace_records(&records).await;
+
+        let layer_index = self.layer(layer)?;
+        let schema = layer_index.index.schema();
+        let changes: Vec<Staged> = records.iter().map(Staged::Write).collect();
+        layer_index.stage(records.len(), &changes, |writer| {
+            for record in &records {
+                writer.add_document(schema::doc_from_record(&schema, record)?)?;
+            }
+            Ok(())
         })?;
+        self.commit_if_due(layer)?;
+        if layer == MemoryLayer::Working {
+            self.hold_working(&records, |record| record.importance as f64).await?;
+        }
 
-        // Get schema
-        let schema = index.schema();
-        let id_field = schema.get_field("id")?;
-        let content_field = schema.get_field("content")?;
-        let timestamp_field = schema.get_field("timestamp")?;
+        Ok(records.into_iter().map(|record| record.id).collect())
+    }
 
-        // Create document
-        let mut doc = TantivyDocument::default();
-        doc.add_text(id_field, &record.id);
-        doc.add_text(content_field, &record.content);
-        // Convert chrono DateTime to Tantivy DateTime (Unix timestamp in seconds)
-        let tantivy_timestamp = tantivy::DateTime::from_timestamp_secs(timestamp.timestamp());
-        doc.add_date(timestamp_field, tantivy_timestamp);
+    /// Embed records that have no vector, or one from a different embedder
+    ///
+    /// Returns how many records were updated.
+    pub async fn embed_missing(&self) -> anyhow::Result<usize> {
+        self.commit_all()?;
+        let dimensions = self.embedder.dimensions();
+        let mut updated = 0;
 
-        // Write to index
-        let mut index_writer: IndexWriter = index.writer(50_000_000)?;
-        index_writer.add_document(doc)?;
-        index_writer.commit()?;
+        for layer in MemoryLayer::ALL {
+            let mut stale: Vec<MemoryRecord> = self
+                .all_records(layer)?
+                .into_iter()
+                .filter(|r| r.embedding.as_ref().map(|v| v.len()) != Some(dimensions))
+                .collect();
 
-        Ok(id)
+            for chunk in stale.chunks_mut(64) {
+                self.embed_records(chunk).await;
+                for record in chunk.iter().filter(|r| r.embedding.is_some()) {
+                    self.replace_record(record).await?;
+                    updated += 1;
+                }
+            }
+            self.layer(layer)?.commit()?;
+        }
+
+        Ok(updated)
+    }
+
+    /// Attach embeddings to records; on failure the records are left without
+    /// vectors so the write still succeeds and `embed_missing` can retry later
+    async fn embed_records(&self, records: &mut [MemoryRecord]) {
+        let texts: Vec<String> = records.iter().map(|r| r.content.clone()).collect();
+        match self.embedder.embed(&texts).await {
+            Ok(vectors) => {
+                for (record, vector) in records.iter_mut().zip(vectors) {
+                    record.embedding = Some(vector);
+                }
+            }
+            Err(e) => warn!("Failed to embed {} memories: {}", records.len(), e),
+        }
+    }
+
+    /// Embed the query for hybrid search, falling back to keyword search
+    /// if the embedder fails
+    async fn query_vector(&self, query: &str, mode: SearchMode) -> Option<Vec<f32>> {
+        if mode != SearchMode::Hybrid || query.trim().is_empty() {
+            return None;
+        }
+        match self.embedder.embed(&[query.to_string()]).await {
+            Ok(mut vectors) => vectors.pop(),
+            Err(e) => {
+                warn!("Failed to embed query, using keyword search only: {}", e);
+                None
+            }
+        }
+    }
+
+    /// Commit all staged writes in every layer
+    pub async fn flush(&self) -> anyhow::Result<()> {
+        self.commit_all()
+    }
+
+    /// Spawn a task that commits layers whose oldest staged write has
+    /// exceeded `CommitPolicy::max_delay`
+    ///
+    /// The task stops once the memory system is dropped.
+    pub fn spawn_commit_task(self: &Arc<Self>) -> JoinHandle<()> {
+        let memory = Arc::downgrade(self);
+        let period = self.commit_policy.max_delay.max(Duration::from_millis(100));
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                let Some(memory) = memory.upgrade() else {
+                    break;
+                };
+                for layer in MemoryLayer::ALL {
+                    if let Err(e) = memory.commit_if_due(layer) {
+                        error!("Failed to commit {} index: {}", layer.as_str(), e);
+                    }
+                }
+            }
+        })
     }
 
     /// Search memories in a specific layer
@@ -133,58 +1135,1106 @@ impl MemorySystem {
         query: &str,
         limit: usize,
     ) -> anyhow::Result<Vec<MemoryRecord>> {
-        let index = self.indices.get(&layer).ok_or_else(|| {
-            anyhow::anyhow!("Index not found for layer: {:?}", layer)
-        })?;
+        self.search_with(layer, query, &SearchOptions::new(limit)).await
+    }
 
-        let schema = index.schema();
-        let content_field = schema.get_field("content")?;
-        let id_field = schema.get_field("id")?;
-        let timestamp_field = schema.get_field("timestamp")?;
+    /// Search a layer with metadata filters
+    ///
+    /// The query uses the syntax described in [`query`]; one that doesn't
+    /// parse fails with a [`QueryError`]. An empty query matches every record
+    /// that passes the filter.
+    pub async fn search_with(
+        &self,
+        layer: MemoryLayer,
+        query: &str,
+        opts: &SearchOptions,
+    ) -> anyhow::Result<Vec<MemoryRecord>> {
+        let parsed: MemoryQuery = query.parse()?;
+        if !parsed.allows(layer) {
+            return Ok(Vec::new());
+        }
+        let query_vector = self.query_vector(&parsed.text(), opts.mode).await;
+        let candidates = SearchOptions {
+            limit: self.access_candidates(opts.limit),
+            ..opts.clone()
+        };
+        let mut results = self.search_layer(layer, &parsed, &candidates, query_vector.as_deref())?;
+        self.boost_by_access(&mut results).await;
+        results.truncate(opts.limit);
+        if opts.record_access {
+            self.record_access(&results, query).await;
+        }
+        Ok(results)
+    }
 
-        let reader = index.reader()?;
-        let searcher = reader.searcher();
+    /// Search every requested layer in parallel and merge the results
+    ///
+    /// BM25 scores from different indices are not comparable, so each layer's
+    /// scores are first divided by that layer's best score, then multiplied by
+    /// the layer weight, and finally by the access boost of the record. The
+    /// merged list is sorted by the resulting score.
+    pub async fn search_all(
+        &self,
+        query: &str,
+        opts: &SearchAllOptions,
+    ) -> anyhow::Result<Vec<MemoryRecord>> {
+        let layer_opts = SearchOptions {
+            limit: self.access_candidates(opts.limit),
+            filter: opts.filter.clone(),
+            recency: opts.recency,
+            mode: opts.mode,
+            highlight: opts.highlight,
+            explain: opts.explain,
+            record_access: false,
+        };
+        let parsed: MemoryQuery = query.parse()?;
+        let query_vector = self.query_vector(&parsed.text(), opts.mode).await;
+        let per_layer: Vec<Vec<MemoryRecord>> = opts
+            .layers
+            .par_iter()
+            .filter(|layer| parsed.allows(**layer))
+            .map(|layer| self.search_layer(*layer, &parsed, &layer_opts, query_vector.as_deref()))
+            .collect::<anyhow::Result<_>>()?;
 
-        let query_parser = QueryParser::for_index(index, vec![content_field]);
-        let query = query_parser.parse_query(query)?;
+        let mut merged = Vec::new();
+        for mut results in per_layer {
+            let max_score = results
+                .iter()
+                .filter_map(|r| r.score)
+                .fold(0.0f32, f32::max);
+            for record in &mut results {
+                let normalized = if max_score > 0.0 {
+                    record.score.unwrap_or(0.0) / max_score
+                } else {
+                    0.0
+                };
+                record.score = Some(normalized * opts.weight(record.layer));
+            }
+            merged.extend(results);
+        }
 
-        let top_docs = searcher.search(&query, &TopDocs::with_limit(limit))?;
+        self.boost_by_access(&mut merged).await;
+        merged.truncate(opts.limit);
+
+        if opts.record_access {
+            self.record_access(&merged, query).await;
+        }
+        Ok(merged)
+    }
+
+    fn search_layer(
+        &self,
+        layer: MemoryLayer,
+        query: &MemoryQuery,
+        opts: &SearchOptions,
+        query_vector: Option<&[f32]>,
+    ) -> anyhow::Result<Vec<MemoryRecord>> {
+        let layer_index = self.layer(layer)?;
+        let schema = layer_index.index.schema();
+        let searcher = layer_index.reader.searcher();
+
+        let keyword_query = Self::build_query(&layer_index.index, query, &opts.filter)?;
+        let top_docs = match query_vector {
+            Some(vector) => {
+                let candidates = opts.limit.max(HYBRID_CANDIDATES);
+                let keyword = Self::top_docs(&searcher, &*keyword_query, candidates, opts.recency, None)?;
+                // The semantic ranking considers every record that passes the filter
+                let filter_query = Self::build_query(&layer_index.index, &query.without_text(), &opts.filter)?;
+                let semantic: Vec<_> = Self::top_docs(&searcher, &*filter_query, candidates, opts.recency, Some(vector))?
+                    .into_iter()
+                    .filter(|(score, _)| score.is_finite())
+                    .collect();
+                reciprocal_rank_fusion(&[keyword, semantic], opts.limit)
+            }
+            None => Self::top_docs(&searcher, &*keyword_query, opts.limit, opts.recency, None)?,
+        };
+
+        let snippets = if opts.highlight {
+            let mut generator = SnippetGenerator::create(&searcher, &*keyword_query, schema.get_field("content")?)?;
+            generator.set_max_num_chars(highlight::SNIPPET_CHARS);
+            Some(generator)
+        } else {
+            None
+        };
 
         let mut results = Vec::new();
-        for (_score, doc_address) in top_docs {
+        for (score, doc_address) in top_docs {
             let retrieved_doc: TantivyDocument = searcher.doc(doc_address)?;
-            
-            let id = retrieved_doc
-                .get_first(id_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            
-            let content = retrieved_doc
-                .get_first(content_field)
-                .and_then(|v| v.as_str())
-                .unwrap_or("")
-                .to_string();
-            
-            let timestamp = retrieved_doc
-                .get_first(timestamp_field)
-                .and_then(|v| v.as_datetime())
-                .map(|dt| {
-                    // Convert Tantivy DateTime (Unix timestamp in seconds) to chrono DateTime
-                    DateTime::from_timestamp(dt.into_timestamp_secs(), 0)
-                        .unwrap_or_else(Utc::now)
-                })
-                .unwrap_or_else(Utc::now);
-
-            results.push(MemoryRecord {
-                id,
-                content,
-                timestamp,
-                layer,
-            });
+            let mut record = schema::record_from_doc(&schema, &retrieved_doc, layer)?;
+            record.score = Some(score);
+            if let Some(generator) = &snippets {
+                record.snippet = Some(MemorySnippet::generate(generator, &record.content));
+            }
+            if opts.explain {
+                // Semantic matches need not match the keyword query
+                record.explanation = Some(match keyword_query.explain(&searcher, doc_address) {
+                    Ok(explanation) => serde_json::to_value(explanation)?,
+                    Err(_) => serde_json::json!({
+                        "value": 0.0,
+                        "description": "Not matched by the keyword query; ranked by semantic similarity",
+                    }),
+                });
+            }
+            results.push(record);
         }
 
         Ok(results)
     }
+
+    /// Compile the query (an empty query matches everything) and add the
+    /// filter clauses
+    fn build_query(index: &Index, query: &MemoryQuery, filter: &MemoryFilter) -> anyhow::Result<Box<dyn Query>> {
+        let mut clauses = query.clauses(index)?;
+        clauses.extend(filter.clauses(&index.schema())?);
+        if clauses.len() == 1 {
+            return Ok(clauses.remove(0).1);
+        }
+        Ok(Box::new(BooleanQuery::new(clauses)))
+    }
+
+    /// Collect the best matches of `query`
+    ///
+    /// With a query vector, documents are ranked by cosine similarity to it
+    /// instead of BM25; documents without a comparable embedding score
+    /// negative infinity. A recency decay multiplies either score.
+    fn top_docs(
+        searcher: &Searcher,
+        query: &dyn Query,
+        limit: usize,
+        recency: Option<RecencyDecay>,
+        query_vector: Option<&[f32]>,
+    ) -> anyhow::Result<Vec<(Score, DocAddress)>> {
+        if recency.is_none() && query_vector.is_none() {
+            return Ok(searcher.search(query, &TopDocs::with_limit(limit))?);
+        }
+
+        let now = Utc::now().timestamp_micros();
+        let query_vector: Option<Arc<Vec<f32>>> = query_vector.map(|v| Arc::new(v.to_vec()));
+        let collector = TopDocs::with_limit(limit).tweak_score(move |segment_reader: &SegmentReader| {
+            let timestamps = segment_reader.fast_fields().date("timestamp").ok();
+            let embeddings = segment_reader.fast_fields().bytes("embedding").ok().flatten();
+            let query_vector = query_vector.clone();
+            let mut buffer = Vec::new();
+
+            move |doc: DocId, score: Score| {
+                let score = match &query_vector {
+                    Some(query_vector) => embeddings
+                        .as_ref()
+                        .and_then(|column| {
+                            let ord = column.term_ords(doc).next()?;
+                            buffer.clear();
+                            column.ord_to_bytes(ord, &mut buffer).ok()?;
+                            embedding::cosine_similarity(query_vector, &embedding::vector_from_bytes(&buffer))
+                        })
+                        .unwrap_or(f32::NEG_INFINITY),
+                    None => score,
+                };
+                match recency {
+                    Some(decay) => {
+                        let age_micros = timestamps
+                            .as_ref()
+                            .and_then(|column| column.first(doc))
+                            .map(|timestamp| (now - timestamp.into_timestamp_micros()).max(0))
+                            .unwrap_or(0);
+                        score * decay.factor(Duration::from_micros(age_micros as u64))
+                    }
+                    None => score,
+                }
+            }
+        });
+
+        Ok(searcher.search(query, &collector)?)
+    }
+
+    /// The `limit` most recently stored records in a layer, newest first
+    pub async fn recent(&self, layer: MemoryLayer, limit: usize) -> anyhow::Result<Vec<MemoryRecord>> {
+        self.recent_with(layer, limit, &MemoryFilter::default()).await
+    }
+
+    /// The `limit` most recently stored records in a layer that pass the
+    /// filter, newest first
+    pub async fn recent_with(
+        &self,
+        layer: MemoryLayer,
+        limit: usize,
+        filter: &MemoryFilter,
+    ) -> anyhow::Result<Vec<MemoryRecord>> {
+        let layer_index = self.layer(layer)?;
+        let schema = layer_index.index.schema();
+        let searcher = layer_index.reader.searcher();
+
+        let query = Self::build_query(&layer_index.index, &MemoryQuery::default(), filter)?;
+        let collector = TopDocs::with_limit(limit)
+            .order_by_fast_field::<tantivy::DateTime>("timestamp", Order::Desc);
+        let top_docs = searcher.search(&*query, &collector)?;
+
+        let mut records = Vec::with_capacity(top_docs.len());
+        for (_timestamp, doc_address) in top_docs {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            records.push(schema::record_from_doc(&schema, &doc, layer)?);
+        }
+
+        Ok(records)
+    }
+
+    /// Fetch a record by id from whichever layer holds it
+    ///
+    /// Staged writes and deletions are taken into account without committing
+    /// them, so every stored id resolves.
+    pub async fn get(&self, id: &str) -> anyhow::Result<Option<MemoryRecord>> {
+        for layer in MemoryLayer::ALL {
+            if let Some(record) = self.find_in_layer(layer, id)? {
+                return Ok(Some(record));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Replace the content of a record, keeping its id, layer and timestamp
+    ///
+    /// Returns `None` if no record with this id exists.
+    pub async fn update(&self, id: &str, content: String) -> anyhow::Result<Option<MemoryRecord>> {
+        let Some(mut record) = self.get(id).await? else {
+            return Ok(None);
+        };
+
+        record.content = content;
+        record.embedding = None;
+        self.embed_records(std::slice::from_mut(&mut record)).await;
+        self.replace_record(&record).await?;
+        self.layer(record.layer)?.commit()?;
+
+        Ok(Some(record))
+    }
+
+    /// Delete a record from whichever layer holds it, together with its
+    /// access statistics and soul memory links
+    ///
+    /// Returns `false` if no record with this id exists.
+    pub async fn delete(&self, id: &str) -> anyhow::Result<bool> {
+        let Some(record) = self.get(id).await? else {
+            return Ok(false);
+        };
+
+        self.forget_record(record.layer, id).await?;
+        self.layer(record.layer)?.commit()?;
+        Ok(true)
+    }
+
+    /// Move a record into another layer, keeping its id and timestamp
+    ///
+    /// The record is committed to the target layer before it is deleted from
+    /// the source layer, so it is never absent from both. Soul memory links
+    /// and access statistics follow it. Returns `None` if no record with this
+    /// id exists.
+    pub async fn move_layer(&self, id: &str, to: MemoryLayer) -> anyhow::Result<Option<MemoryRecord>> {
+        let Some(mut record) = self.get(id).await? else {
+            return Ok(None);
+        };
+        if record.layer == to {
+            return Ok(Some(record));
+        }
+
+        let from = record.layer;
+        record.layer = to;
+        self.write_record(&record).await?;
+        self.layer(to)?.commit()?;
+        self.remove_record(from, id).await?;
+        self.layer(from)?.commit()?;
+        if to == MemoryLayer::Working {
+            self.hold_working(std::slice::from_ref(&record), |record| record.importance as f64)
+                .await?;
+        }
+
+        Ok(Some(record))
+    }
+
+    /// Pin or unpin a record; pinned records are never expired or forgotten
+    ///
+    /// Returns `None` if no record with this id exists.
+    pub async fn set_pinned(&self, id: &str, pinned: bool) -> anyhow::Result<Option<MemoryRecord>> {
+        let Some(mut record) = self.get(id).await? else {
+            return Ok(None);
+        };
+
+        record.pinned = pinned;
+        self.replace_record(&record).await?;
+        self.layer(record.layer)?.commit()?;
+
+        Ok(Some(record))
+    }
+
+    /// Number of times a record has been returned by `search` since startup
+    pub fn retrieval_count(&self, id: &str) -> u64 {
+        self.access.get(id).map(|stats| stats.retrievals).unwrap_or(0)
+    }
+
+    /// Compare every layer index against the database and report records
+    /// that are missing from either side or differ between them
+    pub async fn check_consistency(&self) -> anyhow::Result<ConsistencyReport> {
+        let store = self.record_store()?;
+        self.commit_all()?;
+
+        let mut layers = Vec::with_capacity(MemoryLayer::ALL.len());
+        for layer in MemoryLayer::ALL {
+            let database = store.memory_records_in_layer(layer).await?;
+            let indexed = self.all_records(layer)?;
+            layers.push(store::compare_layer(layer, database, indexed));
+        }
+
+        let report = ConsistencyReport {
+            checked_at: Utc::now(),
+            layers,
+        };
+        if !report.is_consistent() {
+            warn!("Memory indices are out of sync with the database: {:?}", report);
+        }
+        Ok(report)
+    }
+
+    /// Regenerate the index of a layer from the database
+    ///
+    /// Every indexed document is replaced by the rows stored for the layer.
+    /// Returns the number of records indexed.
+    pub async fn rebuild_index(&self, layer: MemoryLayer) -> anyhow::Result<usize> {
+        let records = self.record_store()?.memory_records_in_layer(layer).await?;
+
+        let layer_index = self.layer(layer)?;
+        let schema = layer_index.index.schema();
+        let changes: Vec<Staged> = records.iter().map(Staged::Write).collect();
+        layer_index.stage(records.len() + 1, &changes, |writer| {
+            writer.delete_all_documents()?;
+            for record in &records {
+                writer.add_document(schema::doc_from_record(&schema, record)?)?;
+            }
+            Ok(())
+        })?;
+        layer_index.commit()?;
+
+        counter!("memory.index.rebuilds_total", 1, "layer" => layer.as_str());
+        info!("Rebuilt {} index with {} records", layer.as_str(), records.len());
+        Ok(records.len())
+    }
+
+    /// Copy indexed records that have no database row into the database
+    ///
+    /// Adopts indices written before a database was attached. Returns the
+    /// number of records copied.
+    pub async fn backfill_database(&self) -> anyhow::Result<usize> {
+        let store = self.record_store()?;
+        self.commit_all()?;
+
+        let mut copied = 0;
+        for layer in MemoryLayer::ALL {
+            let stored: HashSet<String> = store
+                .memory_records_in_layer(layer)
+                .await?
+                .into_iter()
+                .map(|record| record.id)
+                .collect();
+            let missing: Vec<MemoryRecord> = self
+                .all_records(layer)?
+                .into_iter()
+                .filter(|record| !stored.contains(&record.id))
+                .collect();
+
+            store.upsert_memory_records(&missing).await?;
+            copied += missing.len();
+        }
+        Ok(copied)
+    }
+
+    fn record_store(&self) -> anyhow::Result<&DatabaseOperations> {
+        self.store
+            .as_ref()
+            .ok_or_else(|| anyhow::anyhow!("Memory system has no database attached"))
+    }
+
+    /// Load every committed record in a layer
+    pub(crate) fn all_records(&self, layer: MemoryLayer) -> anyhow::Result<Vec<MemoryRecord>> {
+        let layer_index = self.layer(layer)?;
+        let schema = layer_index.index.schema();
+        let searcher = layer_index.reader.searcher();
+
+        let doc_addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+        let mut records = Vec::with_capacity(doc_addresses.len());
+        for doc_address in doc_addresses {
+            let doc: TantivyDocument = searcher.doc(doc_address)?;
+            records.push(schema::record_from_doc(&schema, &doc, layer)?);
+        }
+
+        Ok(records)
+    }
+
+    /// Stage a fully formed record in the index of `record.layer`,
+    /// keeping its id and timestamp
+    pub(crate) async fn write_record(&self, record: &MemoryRecord) -> anyhow::Result<()> {
+        if let Some(store) = &self.store {
+            store.upsert_memory_records(std::slice::from_ref(record)).await?;
+        }
+        self.trace_records(std::slice::from_ref(record)).await;
+        let layer_index = self.layer(record.layer)?;
+        let doc = schema::doc_from_record(&layer_index.index.schema(), record)?;
+
+        layer_index.stage(1, &[Staged::Write(record)], |writer| {
+            writer.add_document(doc)?;
+            Ok(())
+        })?;
+        self.commit_if_due(record.layer)
+    }
+
+    /// Stage the deletion of a record that no longer exists anywhere: remove
+    /// it from `layer`, drop its access statistics and unlink it from soul
+    /// entities
+    ///
+    /// Records that live on in another layer are taken out of the old one
+    /// with `remove_record` instead.
+    pub(crate) async fn forget_record(&self, layer: MemoryLayer, id: &str) -> anyhow::Result<()> {
+        self.remove_record(layer, id).await?;
+        self.access.remove(id);
+        if let Some(store) = &self.access_store {
+            store.delete(id).await?;
+        }
+        if let (Some(souls), Ok(memory_id)) = (&self.souls, Uuid::parse_str(id)) {
+            souls.unlink_memory(memory_id).await?;
+        }
+        Ok(())
+    }
+
+    /// Stage the deletion of a record from a single layer by id
+    pub(crate) async fn remove_record(&self, layer: MemoryLayer, id: &str) -> anyhow::Result<()> {
+        if let Some(store) = &self.store {
+            store.delete_memory_record(layer, id).await?;
+        }
+        self.untrace_record(layer, id).await;
+        if layer == MemoryLayer::Working {
+            self.working.remove(id);
+        }
+        self.delete_term(layer, id)?;
+        Ok(())
+    }
+
+    /// Overwrite a record in place: the old document and its replacement are
+    /// staged together, so readers never observe the record missing
+    pub(crate) async fn replace_record(&self, record: &MemoryRecord) -> anyhow::Result<()> {
+        if let Some(store) = &self.store {
+            store.upsert_memory_records(std::slice::from_ref(record)).await?;
+        }
+        self.trace_records(std::slice::from_ref(record)).await;
+        self.working.update_record(record);
+        let layer_index = self.layer(record.layer)?;
+        let schema = layer_index.index.schema();
+        let id_field = schema.get_field("id")?;
+        let doc = schema::doc_from_record(&schema, record)?;
+
+        layer_index.stage(2, &[Staged::Write(record)], |writer| {
+            writer.delete_term(Term::from_field_text(id_field, &record.id));
+            writer.add_document(doc)?;
+            Ok(())
+        })
+    }
+
+    fn delete_term(&self, layer: MemoryLayer, id: &str) -> anyhow::Result<()> {
+        let layer_index = self.layer(layer)?;
+        let id_field = layer_index.index.schema().get_field("id")?;
+
+        layer_index.stage(1, &[Staged::Delete(id)], |writer| {
+            writer.delete_term(Term::from_field_text(id_field, id));
+            Ok(())
+        })?;
+        self.commit_if_due(layer)
+    }
+
+    fn commit_if_due(&self, layer: MemoryLayer) -> anyhow::Result<()> {
+        let layer_index = self.layer(layer)?;
+        if layer_index.is_due(&self.commit_policy)? {
+            layer_index.commit()?;
+        }
+        Ok(())
+    }
+
+    fn commit_all(&self) -> anyhow::Result<()> {
+        for layer_index in self.layers.values() {
+            layer_index.commit()?;
+        }
+        Ok(())
+    }
+
+    /// Look a record up in one layer, staged changes first
+    fn find_in_layer(&self, layer: MemoryLayer, id: &str) -> anyhow::Result<Option<MemoryRecord>> {
+        let layer_index = self.layer(layer)?;
+        let schema = layer_index.index.schema();
+        let id_field = schema.get_field("id")?;
+
+        layer_index.with_staged(|staged, searcher| {
+            if let Some(change) = staged.get(id) {
+                return Ok(change.as_ref().map(|staged| staged.record.clone()));
+            }
+
+            let query = TermQuery::new(Term::from_field_text(id_field, id), IndexRecordOption::Basic);
+            let top_docs = searcher.search(&query, &TopDocs::with_limit(1))?;
+
+            match top_docs.first() {
+                Some((_score, doc_address)) => {
+                    let doc: TantivyDocument = searcher.doc(*doc_address)?;
+                    Ok(Some(schema::record_from_doc(&schema, &doc, layer)?))
+                }
+                None => Ok(None),
+            }
+        })?
+    }
+
+    fn layer(&self, layer: MemoryLayer) -> anyhow::Result<&LayerIndex> {
+        self.layers.get(&layer).ok_or_else(|| {
+            anyhow::anyhow!("Index not found for layer: {:?}", layer)
+        })
+    }
+}
+
+/// Fuse several rankings with reciprocal-rank fusion: each document scores
+/// the sum of `1 / (RRF_K + rank)` over the rankings it appears in
+fn reciprocal_rank_fusion(rankings: &[Vec<(Score, DocAddress)>], limit: usize) -> Vec<(Score, DocAddress)> {
+    let mut fused: HashMap<DocAddress, Score> = HashMap::new();
+    for ranking in rankings {
+        for (rank, (_score, doc_address)) in ranking.iter().enumerate() {
+            *fused.entry(*doc_address).or_insert(0.0) += 1.0 / (RRF_K + rank as f32 + 1.0);
+        }
+    }
+
+    let mut fused: Vec<(Score, DocAddress)> = fused
+        .into_iter()
+        .map(|(doc_address, score)| (score, doc_address))
+        .collect();
+    fused.sort_by(|a, b| {
+        b.0.partial_cmp(&a.0)
+            .unwrap_or(std::cmp::Ordering::Equal)
+            .then(a.1.cmp(&b.1))
+    });
+    fused.truncate(limit);
+    fused
 }
 
+impl Drop for MemorySystem {
+    fn drop(&mut self) {
+        if let Err(e) = self.commit_all() {
+            error!("Failed to commit staged memory writes on shutdown: {}", e);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    async fn test_memory() -> (MemorySystem, TempDir) {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(temp_dir.path().to_path_buf()).await.unwrap();
+        (memory, temp_dir)
+    }
+
+    #[tokio::test]
+    async fn test_get_update_delete() {
+        let (memory, _dir) = test_memory().await;
+        let id = memory
+            .store(MemoryLayer::ShortTerm, "Phoenix has a recital on Friday".to_string())
+            .await
+            .unwrap();
+
+        let record = memory.get(&id).await.unwrap().expect("record should exist");
+        assert_eq!(record.layer, MemoryLayer::ShortTerm);
+        assert_eq!(record.content, "Phoenix has a recital on Friday");
+
+        let updated = memory
+            .update(&id, "Phoenix has a recital on Saturday".to_string())
+            .await
+            .unwrap()
+            .expect("record should exist");
+        assert_eq!(updated.id, id);
+        assert_eq!(updated.timestamp, record.timestamp);
+
+        let fetched = memory.get(&id).await.unwrap().unwrap();
+        assert_eq!(fetched.content, "Phoenix has a recital on Saturday");
+        assert_eq!(memory.all_records(MemoryLayer::ShortTerm).unwrap().len(), 1);
+
+        assert!(memory.delete(&id).await.unwrap());
+        assert!(memory.get(&id).await.unwrap().is_none());
+        assert!(!memory.delete(&id).await.unwrap());
+        assert!(memory.update(&id, "gone".to_string()).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_move_layer_keeps_id() {
+        let (memory, _dir) = test_memory().await;
+        let id = memory
+            .store(MemoryLayer::ShortTerm, "Phoenix prefers the blue bike".to_string())
+            .await
+            .unwrap();
+
+        let moved = memory
+            .move_layer(&id, MemoryLayer::Semantic)
+            .await
+            .unwrap()
+            .expect("record should exist");
+        assert_eq!(moved.id, id);
+        assert_eq!(moved.layer, MemoryLayer::Semantic);
+
+        assert!(memory.all_records(MemoryLayer::ShortTerm).unwrap().is_empty());
+        let fetched = memory.get(&id).await.unwrap().unwrap();
+        assert_eq!(fetched.layer, MemoryLayer::Semantic);
+        assert!(memory.move_layer("missing", MemoryLayer::LongTerm).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn test_search_all_merges_and_weights_layers() {
+        let (memory, _dir) = test_memory().await;
+        memory
+            .store(MemoryLayer::ShortTerm, "Phoenix asked about the recital".to_string())
+            .await
+            .unwrap();
+        memory
+            .store(MemoryLayer::Semantic, "Phoenix plays the violin".to_string())
+            .await
+            .unwrap();
+        memory
+            .store(MemoryLayer::Episodic, "Rained all afternoon".to_string())
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+
+        let opts = SearchAllOptions::new(10).with_weight(MemoryLayer::ShortTerm, 0.5);
+        let results = memory.search_all("phoenix", &opts).await.unwrap();
+
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].layer, MemoryLayer::Semantic);
+        assert_eq!(results[0].score, Some(1.0));
+        assert_eq!(results[1].layer, MemoryLayer::ShortTerm);
+        assert_eq!(results[1].score, Some(0.5));
+
+        let semantic_only = SearchAllOptions::new(10).with_layers(vec![MemoryLayer::Semantic]);
+        let results = memory.search_all("phoenix", &semantic_only).await.unwrap();
+        assert_eq!(results.len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_batched_commits() {
+        let temp_dir = TempDir::new().unwrap();
+        let policy = CommitPolicy {
+            max_pending: 3,
+            max_delay: Duration::from_secs(3600),
+        };
+        let memory = MemorySystem::with_commit_policy(temp_dir.path().to_path_buf(), policy)
+            .await
+            .unwrap();
+
+        let id = memory
+            .store(MemoryLayer::LongTerm, "Phoenix likes chess".to_string())
+            .await
+            .unwrap();
+        assert!(memory.search(MemoryLayer::LongTerm, "chess", 10).await.unwrap().is_empty());
+
+        // Lookups by id see the staged write without committing it
+        assert_eq!(memory.get(&id).await.unwrap().unwrap().content, "Phoenix likes chess");
+        assert!(memory.search(MemoryLayer::LongTerm, "chess", 10).await.unwrap().is_empty());
+
+        memory.flush().await.unwrap();
+        assert_eq!(memory.search(MemoryLayer::LongTerm, "chess", 10).await.unwrap().len(), 1);
+
+        // Reaching max_pending commits without an explicit flush
+        let ids = memory
+            .store_batch(
+                MemoryLayer::LongTerm,
+                vec![
+                    "chess club on Monday".to_string(),
+                    "chess club on Wednesday".to_string(),
+                    "chess club on Friday".to_string(),
+                ],
+            )
+            .await
+            .unwrap();
+        assert_eq!(ids.len(), 3);
+        assert_eq!(memory.search(MemoryLayer::LongTerm, "chess", 10).await.unwrap().len(), 4);
+    }
+
+    #[tokio::test]
+    async fn test_metadata_filters() {
+        let (memory, _dir) = test_memory().await;
+        memory
+            .store_with(
+                MemoryLayer::Episodic,
+                NewMemory::new("Phoenix won the spelling bee")
+                    .with_tags(["school", "milestone"])
+                    .with_entities(["Phoenix"])
+                    .with_source("cli")
+                    .with_importance(0.9)
+                    .with_emotional_tags(["joy"]),
+            )
+            .await
+            .unwrap();
+        memory
+            .store_with(
+                MemoryLayer::Episodic,
+                NewMemory::new("Phoenix lost a tooth").with_tags(["school"]),
+            )
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+
+        let by_entity = SearchOptions::new(10).with_filter(MemoryFilter::default().with_entity("phoenix"));
+        let results = memory.search_with(MemoryLayer::Episodic, "", &by_entity).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].entities, vec!["Phoenix".to_string()]);
+        assert_eq!(results[0].source.as_deref(), Some("cli"));
+        assert_eq!(results[0].emotional_tags, vec!["joy".to_string()]);
+
+        let school = SearchOptions::new(10).with_filter(MemoryFilter::default().with_tag("School"));
+        assert_eq!(memory.search_with(MemoryLayer::Episodic, "phoenix", &school).await.unwrap().len(), 2);
+
+        let important = SearchOptions::new(10).with_filter(
+            MemoryFilter::default().with_tag("school").with_min_importance(0.8),
+        );
+        let results = memory.search_with(MemoryLayer::Episodic, "phoenix", &important).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].importance, 0.9);
+    }
+
+    #[tokio::test]
+    async fn test_structured_queries() {
+        let (memory, _dir) = test_memory().await;
+        memory
+            .store_with(
+                MemoryLayer::Episodic,
+                NewMemory::new("Phoenix played at the piano recital")
+                    .with_tags(["music"])
+                    .with_importance(0.9),
+            )
+            .await
+            .unwrap();
+        memory
+            .store_with(
+                MemoryLayer::Episodic,
+                NewMemory::new("The recital piano needs tuning").with_tags(["chores"]),
+            )
+            .await
+            .unwrap();
+        memory
+            .store(MemoryLayer::Semantic, "Piano lessons are on Tuesdays".to_string())
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+
+        let all = SearchAllOptions::new(10);
+        let contents = |results: Vec<MemoryRecord>| results.into_iter().map(|r| r.content).collect::<Vec<_>>();
+        assert_eq!(
+            contents(memory.search_all("\"piano recital\"", &all).await.unwrap()),
+            vec!["Phoenix played at the piano recital"]
+        );
+        assert_eq!(memory.search_all("piano layer:episodic", &all).await.unwrap().len(), 2);
+        assert_eq!(
+            contents(memory.search_all("piano -tag:music -layer:semantic", &all).await.unwrap()),
+            vec!["The recital piano needs tuning"]
+        );
+        assert_eq!(
+            contents(memory.search_all("importance:>0.5", &all).await.unwrap()),
+            vec!["Phoenix played at the piano recital"]
+        );
+        assert_eq!(memory.search_all("piano -recital", &all).await.unwrap().len(), 1);
+        assert!(memory.search_all("piano before:2000-01-01", &all).await.unwrap().is_empty());
+        assert!(memory
+            .search(MemoryLayer::Semantic, "piano layer:episodic", 10)
+            .await
+            .unwrap()
+            .is_empty());
+
+        let error = memory.search_all("piano mood:happy", &all).await.unwrap_err();
+        assert!(error.downcast_ref::<QueryError>().is_some());
+    }
+
+    #[tokio::test]
+    async fn test_namespaces_scope_search_and_recent() {
+        let (memory, _dir) = test_memory().await;
+        let alice = memory
+            .store_with(MemoryLayer::Episodic, NewMemory::new("Alice's piano lesson").with_namespace("alice"))
+            .await
+            .unwrap();
+        memory
+            .store_with(MemoryLayer::Episodic, NewMemory::new("Bob's piano lesson").with_namespace("bob"))
+            .await
+            .unwrap();
+        memory
+            .store(MemoryLayer::Episodic, "Piano tuner visits on Monday".to_string())
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+
+        let as_alice = SearchOptions::new(10).with_filter(MemoryFilter::visible_to("alice"));
+        let results = memory.search_with(MemoryLayer::Episodic, "piano", &as_alice).await.unwrap();
+        let mut namespaces: Vec<&str> = results.iter().map(|record| record.namespace.as_str()).collect();
+        namespaces.sort();
+        assert_eq!(namespaces, vec!["alice", SHARED_NAMESPACE]);
+
+        let recent = memory
+            .recent_with(MemoryLayer::Episodic, 10, &MemoryFilter::visible_to("bob"))
+            .await
+            .unwrap();
+        assert_eq!(recent.len(), 2);
+        assert!(recent.iter().all(|record| record.visible_to("bob")));
+
+        let record = memory.get(&alice).await.unwrap().unwrap();
+        assert!(record.visible_to("alice"));
+        assert!(!record.visible_to("bob"));
+        assert_eq!(memory.recent(MemoryLayer::Episodic, 10).await.unwrap().len(), 3);
+    }
+
+    #[tokio::test]
+    async fn test_opens_index_with_old_schema() {
+        let temp_dir = TempDir::new().unwrap();
+        let layer_dir = temp_dir.path().join(MemoryLayer::LongTerm.as_str());
+        std::fs::create_dir_all(&layer_dir).unwrap();
+
+        let mut schema_builder = Schema::builder();
+        let id_field = schema_builder.add_text_field("id", STRING | STORED);
+        let content_field = schema_builder.add_text_field("content", TEXT | STORED);
+        let timestamp_field = schema_builder.add_date_field("timestamp", INDEXED | STORED);
+        let old_index = Index::create_in_dir(&layer_dir, schema_builder.build()).unwrap();
+        let mut writer: IndexWriter = old_index.writer(15_000_000).unwrap();
+        let mut doc = TantivyDocument::default();
+        doc.add_text(id_field, "legacy-1");
+        doc.add_text(content_field, "Phoenix started piano lessons");
+        doc.add_date(timestamp_field, tantivy::DateTime::from_timestamp_secs(1_700_000_000));
+        writer.add_document(doc).unwrap();
+        writer.commit().unwrap();
+        drop(writer);
+
+        let memory = MemorySystem::new(temp_dir.path().to_path_buf()).await.unwrap();
+        let record = memory.get("legacy-1").await.unwrap().expect("legacy record survives");
+        assert_eq!(record.content, "Phoenix started piano lessons");
+        assert_eq!(record.timestamp.timestamp(), 1_700_000_000);
+        assert_eq!(record.importance, DEFAULT_IMPORTANCE);
+        assert_eq!(record.namespace, SHARED_NAMESPACE);
+        assert!(record.tags.is_empty());
+
+        memory
+            .store_with(MemoryLayer::LongTerm, NewMemory::new("Piano recital").with_tags(["music"]))
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+        let music = SearchOptions::new(10).with_filter(MemoryFilter::default().with_tag("music"));
+        assert_eq!(memory.search_with(MemoryLayer::LongTerm, "piano", &music).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_time_range_and_recency() {
+        let (memory, _dir) = test_memory().await;
+        let now = Utc::now();
+        let old = NewMemory::new("Phoenix planted tomatoes in the garden")
+            .into_record(MemoryLayer::LongTerm, now - chrono::Duration::days(30));
+        let fresh = NewMemory::new("Phoenix planted tomatoes in the garden")
+            .into_record(MemoryLayer::LongTerm, now - chrono::Duration::minutes(5));
+        memory.write_record(&old).await.unwrap();
+        memory.write_record(&fresh).await.unwrap();
+        memory.flush().await.unwrap();
+
+        let last_week = SearchOptions::new(10)
+            .with_filter(MemoryFilter::default().with_since(now - chrono::Duration::days(7)));
+        let results = memory.search_with(MemoryLayer::LongTerm, "tomatoes", &last_week).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, fresh.id);
+
+        let before = SearchOptions::new(10)
+            .with_filter(MemoryFilter::default().with_until(now - chrono::Duration::days(7)));
+        let results = memory.search_with(MemoryLayer::LongTerm, "tomatoes", &before).await.unwrap();
+        assert_eq!(results.len(), 1);
+        assert_eq!(results[0].id, old.id);
+
+        let decayed = SearchOptions::new(10)
+            .with_recency(RecencyDecay::new(Duration::from_secs(86_400), 1.0));
+        let results = memory.search_with(MemoryLayer::LongTerm, "tomatoes", &decayed).await.unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].id, fresh.id);
+        assert!(results[1].score.unwrap() < results[0].score.unwrap() * 0.01);
+    }
+
+    #[tokio::test]
+    async fn test_recent_lists_newest_first() {
+        let (memory, _dir) = test_memory().await;
+        let now = Utc::now();
+        for (content, minutes_ago) in [("first", 30), ("third", 1), ("second", 10)] {
+            let record = NewMemory::new(content)
+                .into_record(MemoryLayer::ShortTerm, now - chrono::Duration::minutes(minutes_ago));
+            memory.write_record(&record).await.unwrap();
+        }
+        memory.flush().await.unwrap();
+
+        let recent = memory.recent(MemoryLayer::ShortTerm, 2).await.unwrap();
+        let contents: Vec<&str> = recent.iter().map(|r| r.content.as_str()).collect();
+        assert_eq!(contents, vec!["third", "second"]);
+    }
+
+    #[test]
+    fn test_recency_decay_factor() {
+        let decay = RecencyDecay::new(Duration::from_secs(3600), 0.5);
+        assert_eq!(decay.factor(Duration::ZERO), 1.0);
+        assert!((decay.factor(Duration::from_secs(3600)) - 0.75).abs() < 1e-6);
+    }
+
+    /// Maps words onto hand-picked concepts so that paraphrases share a vector
+    struct ConceptEmbedder;
+
+    impl Embedder for ConceptEmbedder {
+        fn dimensions(&self) -> usize {
+            3
+        }
+
+        fn embed<'a>(&'a self, texts: &'a [String]) -> futures::future::BoxFuture<'a, anyhow::Result<Vec<Vec<f32>>>> {
+            let concepts = [
+                ["recital", "performance", "concert"],
+                ["daughter", "phoenix", "kid"],
+                ["router", "firmware", "network"],
+            ];
+            let vectors = texts
+                .iter()
+                .map(|text| {
+                    let text = text.to_lowercase();
+                    concepts
+                        .iter()
+                        .map(|words| words.iter().filter(|w| text.contains(*w)).count() as f32)
+                        .collect()
+                })
+                .collect();
+            Box::pin(async move { Ok(vectors) })
+        }
+    }
+
+    #[tokio::test]
+    async fn test_hybrid_search_matches_paraphrases() {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_embedder(Arc::new(ConceptEmbedder));
+        memory
+            .store(MemoryLayer::Episodic, "Phoenix gave a wonderful performance".to_string())
+            .await
+            .unwrap();
+        memory
+            .store(MemoryLayer::Episodic, "Router firmware needs an update".to_string())
+            .await
+            .unwrap();
+        memory.flush().await.unwrap();
+
+        let keyword = memory
+            .search_with(MemoryLayer::Episodic, "daughter recital", &SearchOptions::new(5))
+            .await
+            .unwrap();
+        assert!(keyword.is_empty());
+
+        let hybrid = SearchOptions::new(5).with_mode(SearchMode::Hybrid);
+        let results = memory
+            .search_with(MemoryLayer::Episodic, "daughter recital", &hybrid)
+            .await
+            .unwrap();
+        assert_eq!(results[0].content, "Phoenix gave a wonderful performance");
+    }
+
+    #[tokio::test]
+    async fn test_embed_missing_backfills_vectors() {
+        let (memory, _dir) = test_memory().await;
+        let legacy = NewMemory::new("Phoenix's piano recital").into_record(MemoryLayer::LongTerm, Utc::now());
+        memory.write_record(&legacy).await.unwrap();
+        memory
+            .store(MemoryLayer::LongTerm, "Router firmware update".to_string())
+            .await
+            .unwrap();
+
+        assert_eq!(memory.embed_missing().await.unwrap(), 1);
+        let record = memory.get(&legacy.id).await.unwrap().unwrap();
+        assert_eq!(record.embedding.map(|v| v.len()), Some(256));
+
+        // "recitals" shares no BM25 term with "recital" but most of its trigrams
+        let hybrid = SearchOptions::new(5).with_mode(SearchMode::Hybrid);
+        let results = memory.search_with(MemoryLayer::LongTerm, "recitals", &hybrid).await.unwrap();
+        assert_eq!(results[0].id, legacy.id);
+    }
+
+    #[tokio::test]
+    async fn test_database_write_through_and_rebuild() {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_database(store::test_pool().await);
+
+        let kept = memory
+            .store_with(MemoryLayer::LongTerm, NewMemory::new("Phoenix loves the piano").with_tags(["music"]))
+            .await
+            .unwrap();
+        memory
+            .store_batch(MemoryLayer::LongTerm, vec!["Router firmware update", "Garden needs water"])
+            .await
+            .unwrap();
+        let moved = memory.store(MemoryLayer::ShortTerm, "Recital is on Friday".to_string()).await.unwrap();
+        memory.move_layer(&moved, MemoryLayer::Episodic).await.unwrap();
+        memory.update(&kept, "Phoenix loves the cello".to_string()).await.unwrap();
+
+        let report = memory.check_consistency().await.unwrap();
+        assert!(report.is_consistent(), "{:?}", report);
+
+        // Drop a document from the index behind the database's back
+        memory.delete_term(MemoryLayer::LongTerm, &kept).unwrap();
+        memory.flush().await.unwrap();
+        let report = memory.check_consistency().await.unwrap();
+        let long_term = report.layers.iter().find(|l| l.layer == MemoryLayer::LongTerm).unwrap();
+        assert_eq!(long_term.missing_from_index, vec![kept.clone()]);
+        assert_eq!(long_term.indexed_records, 2);
+
+        assert_eq!(memory.rebuild_index(MemoryLayer::LongTerm).await.unwrap(), 3);
+        assert!(memory.check_consistency().await.unwrap().is_consistent());
+        let record = memory.get(&kept).await.unwrap().unwrap();
+        assert_eq!(record.content, "Phoenix loves the cello");
+        assert_eq!(record.tags, vec!["music"]);
+
+        assert!(memory.delete(&moved).await.unwrap());
+        assert!(memory.check_consistency().await.unwrap().is_consistent());
+    }
+
+    #[tokio::test]
+    async fn test_delete_unlinks_soul_memories() {
+        let temp_dir = TempDir::new().unwrap();
+        let pool = store::test_pool().await;
+        let memory = MemorySystem::new(temp_dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_database(pool.clone());
+        let souls = SoulStorage::new(pool);
+        let entity_id = souls
+            .upsert_entity(&crate::soul::SoulEntity::new("Phoenix.Marie".to_string()))
+            .await
+            .unwrap();
+
+        let id = memory.store(MemoryLayer::ShortTerm, "Phoenix won the spelling bee".to_string()).await.unwrap();
+        let memory_id = Uuid::parse_str(&id).unwrap();
+        souls.link_memory(entity_id, memory_id).await.unwrap();
+
+        // Moving keeps the id, so the link still points at the record
+        memory.move_layer(&id, MemoryLayer::Episodic).await.unwrap();
+        assert_eq!(souls.get_entity_memories(entity_id).await.unwrap(), vec![memory_id]);
+
+        assert!(memory.delete(&id).await.unwrap());
+        assert!(souls.get_entity_memories(entity_id).await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_quarantines_unreadable_index() {
+        let temp_dir = TempDir::new().unwrap();
+        drop(MemorySystem::new(temp_dir.path().to_path_buf()).await.unwrap());
+        let layer_dir = temp_dir.path().join("semantic");
+        std::fs::write(layer_dir.join("meta.json"), "not json").unwrap();
+
+        assert!(MemorySystem::new(temp_dir.path().to_path_buf()).await.is_err());
+        let quarantined = MemorySystem::quarantine_unreadable_indices(temp_dir.path()).unwrap();
+        assert_eq!(quarantined, vec![MemoryLayer::Semantic]);
+        assert!(temp_dir.path().join("semantic.corrupt").exists());
+        assert!(MemorySystem::new(temp_dir.path().to_path_buf()).await.is_ok());
+    }
+}
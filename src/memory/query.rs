@@ -0,0 +1,412 @@
+//! Structured query syntax for memory search
+//!
+//! A query is a whitespace-separated list of clauses:
+//!
+//! - `recital piano` - records mentioning any of the words
+//! - `"piano recital"` - records containing the exact phrase
+//! - `layer:episodic` - only records in a layer
+//! - `tag:family`, `entity:Phoenix`, `source:cli`, `emotion:joy` - records
+//!   carrying a label; values with spaces are quoted: `entity:"New York"`
+//! - `before:2024-05-01`, `after:2024-04-01T12:00:00Z` - records stored
+//!   before (exclusive) or on or after (inclusive) a date or RFC 3339 time
+//! - `importance:>0.5` - records whose importance compares with a number
+//!   (`>`, `>=`, `<`, `<=`; a bare number means `>=`)
+//!
+//! A leading `-` excludes matches of a word, phrase, layer or label:
+//! `-draft`, `-"school run"`, `-layer:working`, `-tag:archived`. Label
+//! values match whole labels regardless of case. Clauses other than words
+//! and phrases must all hold; words and phrases rank records, and at least
+//! one of them must match. A query of only filters matches every record
+//! that passes them.
+
+use super::{MemoryFilter, MemoryLayer};
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use std::ops::Bound;
+use std::str::FromStr;
+use tantivy::query::{AllQuery, BooleanQuery, ConstScoreQuery, Occur, PhraseQuery, Query, RangeQuery, TermQuery};
+use tantivy::schema::{Field, IndexRecordOption};
+use tantivy::tokenizer::{TextAnalyzer, TokenStream};
+use tantivy::{Index, Term};
+use thiserror::Error;
+
+/// Filters a query can use, in the order error messages list them
+const FIELDS: [&str; 8] = ["layer", "tag", "entity", "source", "emotion", "before", "after", "importance"];
+
+/// A query that could not be parsed
+#[derive(Debug, Clone, PartialEq, Eq, Error)]
+#[error("{message} (column {column})")]
+pub struct QueryError {
+    /// 1-based character position of the offending clause
+    pub column: usize,
+    pub message: String,
+}
+
+impl QueryError {
+    fn at(input: &str, offset: usize, message: impl Into<String>) -> Self {
+        Self {
+            column: input[..offset].chars().count() + 1,
+            message: message.into(),
+        }
+    }
+}
+
+/// A word or quoted phrase of a query
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TextTerm {
+    pub text: String,
+    pub phrase: bool,
+    /// Records matching the term are excluded
+    pub negated: bool,
+}
+
+/// A parsed memory search query
+#[derive(Debug, Clone, Default)]
+pub struct MemoryQuery {
+    pub terms: Vec<TextTerm>,
+    /// Only records in these layers; any layer when empty
+    pub layers: Vec<MemoryLayer>,
+    pub excluded_layers: Vec<MemoryLayer>,
+    /// Labels and time range every record must match
+    pub filter: MemoryFilter,
+    /// Ranges every record's importance must fall in
+    pub importance: Vec<(Bound<f64>, Bound<f64>)>,
+    /// Labels no record may carry, as (index field, value)
+    excluded_labels: Vec<(&'static str, String)>,
+}
+
+impl MemoryQuery {
+    /// Whether records in `layer` can match
+    pub fn allows(&self, layer: MemoryLayer) -> bool {
+        (self.layers.is_empty() || self.layers.contains(&layer)) && !self.excluded_layers.contains(&layer)
+    }
+
+    /// The words and phrases records should match, for semantic ranking
+    pub fn text(&self) -> String {
+        self.terms
+            .iter()
+            .filter(|term| !term.negated)
+            .map(|term| term.text.as_str())
+            .collect::<Vec<_>>()
+            .join(" ")
+    }
+
+    /// The same query without the words and phrases to rank by, so it
+    /// matches every record that passes its conditions
+    pub fn without_text(&self) -> Self {
+        let mut query = self.clone();
+        query.terms.retain(|term| term.negated);
+        query
+    }
+
+    /// Index query clauses for every condition
+    pub(crate) fn clauses(&self, index: &Index) -> anyhow::Result<Vec<(Occur, Box<dyn Query>)>> {
+        let schema = index.schema();
+        let content_field = schema.get_field("content")?;
+        let mut analyzer = index.tokenizer_for_field(content_field)?;
+
+        let mut any_text: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        let mut clauses: Vec<(Occur, Box<dyn Query>)> = Vec::new();
+        for term in &self.terms {
+            let Some(query) = text_query(&mut analyzer, content_field, &term.text) else {
+                continue;
+            };
+            if term.negated {
+                clauses.push((Occur::MustNot, query));
+            } else {
+                any_text.push((Occur::Should, query));
+            }
+        }
+        let text_query: Box<dyn Query> = if self.terms.iter().any(|term| !term.negated) {
+            Box::new(BooleanQuery::new(any_text))
+        } else {
+            Box::new(AllQuery)
+        };
+        clauses.insert(0, (Occur::Must, text_query));
+
+        clauses.extend(self.filter.clauses(&schema)?);
+        for (field_name, value) in &self.excluded_labels {
+            let term = Term::from_field_text(schema.get_field(field_name)?, &value.to_lowercase());
+            clauses.push((Occur::MustNot, Box::new(TermQuery::new(term, IndexRecordOption::Basic))));
+        }
+        for (lower, upper) in &self.importance {
+            let query = RangeQuery::new_f64_bounds("importance".to_string(), *lower, *upper);
+            clauses.push((Occur::Must, Box::new(ConstScoreQuery::new(Box::new(query), 0.0))));
+        }
+
+        Ok(clauses)
+    }
+
+    /// Apply a `name:value` clause
+    ///
+    /// `at` and `value_at` are the offsets of the clause and its value in
+    /// `input`, for error positions.
+    fn add_condition(
+        &mut self,
+        input: &str,
+        at: usize,
+        name: &str,
+        value: String,
+        value_at: usize,
+        negated: bool,
+    ) -> Result<(), QueryError> {
+        let name = name.to_lowercase();
+        if !FIELDS.contains(&name.as_str()) {
+            return Err(QueryError::at(
+                input,
+                at,
+                format!(
+                    "Unknown filter `{}:`; use one of {}, or quote the term to search for it as text",
+                    name,
+                    FIELDS.map(|field| format!("`{}:`", field)).join(", ")
+                ),
+            ));
+        }
+        if value.trim().is_empty() {
+            return Err(QueryError::at(input, at, format!("`{}:` needs a value", name)));
+        }
+        let value = value.trim().to_string();
+
+        let label_field = match name.as_str() {
+            "tag" => Some("tags"),
+            "entity" => Some("entities"),
+            "source" => Some("source"),
+            "emotion" => Some("emotional_tags"),
+            _ => None,
+        };
+        if let Some(field_name) = label_field {
+            if negated {
+                self.excluded_labels.push((field_name, value));
+                return Ok(());
+            }
+            match field_name {
+                "tags" => self.filter.tags.push(value),
+                "entities" => self.filter.entities.push(value),
+                "emotional_tags" => self.filter.emotional_tags.push(value),
+                _ => match &self.filter.source {
+                    Some(source) if !source.eq_ignore_ascii_case(&value) => {
+                        return Err(QueryError::at(input, at, "A record has only one `source:`"));
+                    }
+                    _ => self.filter.source = Some(value),
+                },
+            }
+            return Ok(());
+        }
+
+        if name == "layer" {
+            let layer: MemoryLayer = value.to_lowercase().parse().map_err(|_| {
+                QueryError::at(
+                    input,
+                    value_at,
+                    format!(
+                        "Unknown layer `{}`; use one of {}",
+                        value,
+                        MemoryLayer::ALL.map(|layer| layer.as_str()).join(", ")
+                    ),
+                )
+            })?;
+            if negated {
+                self.excluded_layers.push(layer);
+            } else {
+                self.layers.push(layer);
+            }
+            return Ok(());
+        }
+
+        if negated {
+            let hint = match name.as_str() {
+                "before" => "use `after:` instead",
+                "after" => "use `before:` instead",
+                _ => "flip the comparison instead",
+            };
+            return Err(QueryError::at(
+                input,
+                at,
+                format!("`{}:` cannot be negated; {}", name, hint),
+            ));
+        }
+
+        match name.as_str() {
+            "before" => {
+                let time = parse_time(&value).ok_or_else(|| date_error(input, value_at, &name))?;
+                let until = time - Duration::microseconds(1);
+                self.filter.until = Some(self.filter.until.map_or(until, |current| current.min(until)));
+            }
+            "after" => {
+                let since = parse_time(&value).ok_or_else(|| date_error(input, value_at, &name))?;
+                self.filter.since = Some(self.filter.since.map_or(since, |current| current.max(since)));
+            }
+            _ => {
+                let range = parse_comparison(&value).ok_or_else(|| {
+                    QueryError::at(
+                        input,
+                        value_at,
+                        "`importance:` needs a comparison with a number, like `>0.5` or `<=0.2`",
+                    )
+                })?;
+                self.importance.push(range);
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for MemoryQuery {
+    type Err = QueryError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut query = Self::default();
+        let mut pos = 0;
+
+        while let Some(skipped) = s[pos..].find(|c: char| !c.is_whitespace()) {
+            let start = pos + skipped;
+            let negated = s[start..].starts_with('-') && s[start + 1..].starts_with(|c: char| !c.is_whitespace());
+            let at = if negated { start + 1 } else { start };
+
+            if s[at..].starts_with('"') {
+                let (phrase, end) = quoted(s, at)?;
+                if !phrase.trim().is_empty() {
+                    query.terms.push(TextTerm {
+                        text: phrase,
+                        phrase: true,
+                        negated,
+                    });
+                }
+                pos = end;
+                continue;
+            }
+
+            let word_end = s[at..].find(char::is_whitespace).map_or(s.len(), |i| at + i);
+            let word = &s[at..word_end];
+            match filter_name(word) {
+                Some(name) => {
+                    let value_at = at + name.len() + 1;
+                    let (value, end) = if s[value_at..].starts_with('"') {
+                        quoted(s, value_at)?
+                    } else {
+                        (s[value_at..word_end].to_string(), word_end)
+                    };
+                    query.add_condition(s, start, name, value, value_at, negated)?;
+                    pos = end;
+                }
+                None => {
+                    query.terms.push(TextTerm {
+                        text: word.to_string(),
+                        phrase: false,
+                        negated,
+                    });
+                    pos = word_end;
+                }
+            }
+        }
+
+        Ok(query)
+    }
+}
+
+/// The name of a `name:value` clause. Colons after anything but letters,
+/// as in `5:30`, and URLs are plain text.
+fn filter_name(word: &str) -> Option<&str> {
+    let (name, rest) = word.split_once(':')?;
+    let is_name = !name.is_empty() && name.chars().all(|c| c.is_ascii_alphabetic());
+    (is_name && !rest.starts_with("//")).then_some(name)
+}
+
+/// Read the quoted text starting at `at`, returning it and the offset just
+/// past the closing quote
+fn quoted(input: &str, at: usize) -> Result<(String, usize), QueryError> {
+    let body = &input[at + 1..];
+    match body.find('"') {
+        Some(len) => Ok((body[..len].to_string(), at + len + 2)),
+        None => Err(QueryError::at(input, at, "Unterminated quote; close it with `\"`")),
+    }
+}
+
+/// A date is the start of that day in UTC
+fn parse_time(value: &str) -> Option<DateTime<Utc>> {
+    if let Ok(date) = NaiveDate::parse_from_str(value, "%Y-%m-%d") {
+        return Some(date.and_hms_opt(0, 0, 0)?.and_utc());
+    }
+    DateTime::parse_from_rfc3339(value)
+        .ok()
+        .map(|time| time.with_timezone(&Utc))
+}
+
+fn date_error(input: &str, value_at: usize, name: &str) -> QueryError {
+    QueryError::at(
+        input,
+        value_at,
+        format!("`{}:` needs a date like 2024-05-01 or an RFC 3339 time like 2024-05-01T12:00:00Z", name),
+    )
+}
+
+fn parse_comparison(value: &str) -> Option<(Bound<f64>, Bound<f64>)> {
+    let operator_len = value.find(|c: char| !matches!(c, '<' | '>' | '=')).unwrap_or(value.len());
+    let (operator, number) = value.split_at(operator_len);
+    let number: f64 = number.trim().parse().ok().filter(|n: &f64| n.is_finite())?;
+    match operator {
+        ">" => Some((Bound::Excluded(number), Bound::Unbounded)),
+        ">=" | "" => Some((Bound::Included(number), Bound::Unbounded)),
+        "<" => Some((Bound::Unbounded, Bound::Excluded(number))),
+        "<=" => Some((Bound::Unbounded, Bound::Included(number))),
+        _ => None,
+    }
+}
+
+/// Match the tokens of `text` in the content field: a single token as a
+/// term, several as a phrase. `None` if the text has no tokens.
+fn text_query(analyzer: &mut TextAnalyzer, field: Field, text: &str) -> Option<Box<dyn Query>> {
+    let mut terms = Vec::new();
+    analyzer
+        .token_stream(text)
+        .process(&mut |token| terms.push(Term::from_field_text(field, &token.text)));
+
+    match terms.len() {
+        0 => None,
+        1 => Some(Box::new(TermQuery::new(terms.remove(0), IndexRecordOption::WithFreqs))),
+        _ => Some(Box::new(PhraseQuery::new(terms))),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parses_clauses() {
+        let query: MemoryQuery =
+            "recital \"piano lesson\" -draft layer:episodic -layer:working tag:Family -entity:\"New York\" after:2024-04-01 before:2024-05-01 importance:>0.5 at 5:30"
+                .parse()
+                .unwrap();
+
+        assert_eq!(query.text(), "recital piano lesson at 5:30");
+        assert!(query.terms.iter().any(|t| t.text == "piano lesson" && t.phrase));
+        assert!(query.terms.iter().any(|t| t.text == "draft" && t.negated));
+        assert!(query.allows(MemoryLayer::Episodic));
+        assert!(!query.allows(MemoryLayer::Working));
+        assert!(!query.allows(MemoryLayer::Semantic));
+        assert_eq!(query.filter.tags, vec!["Family"]);
+        assert_eq!(query.excluded_labels, vec![("entities", "New York".to_string())]);
+        assert_eq!(query.filter.since.unwrap().to_rfc3339(), "2024-04-01T00:00:00+00:00");
+        assert!(query.filter.until.unwrap() < parse_time("2024-05-01").unwrap());
+        assert_eq!(query.importance, vec![(Bound::Excluded(0.5), Bound::Unbounded)]);
+        assert_eq!(query.without_text().text(), "");
+    }
+
+    #[test]
+    fn test_reports_errors_with_their_column() {
+        let error = "recital colour:red".parse::<MemoryQuery>().unwrap_err();
+        assert_eq!(error.column, 9);
+        assert!(error.message.contains("`colour:`"));
+
+        assert_eq!("say \"hello".parse::<MemoryQuery>().unwrap_err().column, 5);
+        assert_eq!("layer:dreams".parse::<MemoryQuery>().unwrap_err().column, 7);
+        assert!("tag:".parse::<MemoryQuery>().is_err());
+        assert!("before:yesterday".parse::<MemoryQuery>().is_err());
+        assert!("importance:high".parse::<MemoryQuery>().is_err());
+        assert!("-after:2024-01-01".parse::<MemoryQuery>().is_err());
+
+        // Colons that don't follow a name are text
+        assert!("https://example.com 12:45".parse::<MemoryQuery>().is_ok());
+        assert!("\"colour:red\"".parse::<MemoryQuery>().is_ok());
+    }
+}
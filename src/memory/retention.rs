@@ -0,0 +1,375 @@
+//! Memory Retention
+//!
+//! Periodically forgets records according to per-layer policies: LongTerm
+//! and Episodic records follow an Ebbinghaus forgetting curve whose stability
+//! grows each time the record is retrieved. Semantic records are kept,
+//! ShortTerm records are left to consolidation, which promotes or expires
+//! them, and Working records to their slots, whose eviction spills or
+//! forgets them. Pinned records never expire.
+
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{error, info};
+
+use crate::config::env_or;
+use super::access::AccessStats;
+use super::{MemoryLayer, MemoryRecord, MemorySystem};
+
+/// How records in a layer are forgotten
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RetentionPolicy {
+    /// Never forget
+    Keep,
+    /// Forget records older than this
+    Ttl(Duration),
+    /// Forget records whose Ebbinghaus strength falls below the threshold
+    Decay {
+        /// Time for an unreinforced record of default importance to fall to 1/e
+        stability: Duration,
+    },
+}
+
+/// Retention policies and sweep schedule
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionConfig {
+    /// How often the sweeper runs, in seconds
+    pub interval_secs: u64,
+    /// Base stability for LongTerm records, in seconds
+    pub long_term_stability_secs: u64,
+    /// Base stability for Episodic records, in seconds
+    pub episodic_stability_secs: u64,
+    /// Stability gained per retrieval, as a multiple of the base stability
+    pub reinforcement_factor: f64,
+    /// Records whose strength drops below this are forgotten (0.0 to 1.0)
+    pub forget_threshold: f64,
+}
+
+impl Default for RetentionConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3600,                // 1 hour
+            long_term_stability_secs: 2592000,  // 30 days
+            episodic_stability_secs: 1209600,   // 14 days
+            reinforcement_factor: 1.0,
+            forget_threshold: 0.05,
+        }
+    }
+}
+
+impl RetentionConfig {
+    /// Load retention configuration from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            interval_secs: env_or("MEMORY_RETENTION_INTERVAL_SECS", defaults.interval_secs),
+            long_term_stability_secs: env_or("MEMORY_RETENTION_LONG_TERM_STABILITY_SECS", defaults.long_term_stability_secs),
+            episodic_stability_secs: env_or("MEMORY_RETENTION_EPISODIC_STABILITY_SECS", defaults.episodic_stability_secs),
+            reinforcement_factor: env_or("MEMORY_RETENTION_REINFORCEMENT_FACTOR", defaults.reinforcement_factor),
+            forget_threshold: env_or("MEMORY_RETENTION_FORGET_THRESHOLD", defaults.forget_threshold),
+        }
+    }
+
+    /// Get the run interval as Duration
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+
+    /// Retention policy for a layer
+    pub fn policy(&self, layer: MemoryLayer) -> RetentionPolicy {
+        match layer {
+            MemoryLayer::LongTerm => RetentionPolicy::Decay {
+                stability: Duration::from_secs(self.long_term_stability_secs),
+            },
+            MemoryLayer::Episodic => RetentionPolicy::Decay {
+                stability: Duration::from_secs(self.episodic_stability_secs),
+            },
+            // ShortTerm expiry belongs to consolidation, and Working records
+            // leave through slot eviction while they may still be held
+            MemoryLayer::ShortTerm | MemoryLayer::Working | MemoryLayer::Semantic => RetentionPolicy::Keep,
+        }
+    }
+
+    /// Ebbinghaus retention `exp(-t / S)` of a record
+    ///
+    /// `t` is the time since the record was stored or last retrieved. The
+    /// stability `S` grows by `reinforcement_factor` times the base for each
+    /// retrieval and is scaled by importance (x0.5 at 0.0, x1.5 at 1.0).
+    /// Retrievals come from the record's access statistics, if it has any.
+    pub fn strength(
+        &self,
+        record: &MemoryRecord,
+        access: Option<&AccessStats>,
+        stability: Duration,
+        now: DateTime<Utc>,
+    ) -> f64 {
+        let (retrievals, last_seen) = match access {
+            Some(stats) => (stats.retrievals, stats.last_accessed.max(record.timestamp)),
+            None => (0, record.timestamp),
+        };
+        let elapsed = (now - last_seen).num_seconds().max(0) as f64;
+
+        let reinforcement = 1.0 + self.reinforcement_factor * retrievals as f64;
+        let importance = 0.5 + record.importance.clamp(0.0, 1.0) as f64;
+        let stability = stability.as_secs_f64().max(1.0) * reinforcement * importance;
+
+        (-elapsed / stability).exp()
+    }
+}
+
+/// Why a record was forgotten
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ForgetReason {
+    /// Older than the layer's TTL
+    Expired,
+    /// Strength fell below the forget threshold
+    Faded,
+}
+
+/// A record removed by the sweeper
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ForgottenMemory {
+    pub id: String,
+    pub layer: MemoryLayer,
+    pub reason: ForgetReason,
+    /// Strength at the time it was forgotten, for decaying layers
+    pub strength: Option<f64>,
+}
+
+/// Outcome of a single retention sweep
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RetentionReport {
+    /// When the sweep started
+    pub started_at: DateTime<Utc>,
+    /// How long the sweep took, in milliseconds
+    pub duration_ms: u64,
+    /// Number of records examined
+    pub scanned: usize,
+    /// Records removed by the sweep
+    pub forgotten: Vec<ForgottenMemory>,
+    /// Records in decaying layers kept alive by their retrievals
+    pub reinforced: usize,
+    /// Pinned records that were skipped
+    pub pinned: usize,
+}
+
+/// Periodically forgets expired and faded memories
+pub struct RetentionSweeper {
+    memory: Arc<MemorySystem>,
+    config: RetentionConfig,
+    last_report: Arc<RwLock<Option<RetentionReport>>>,
+}
+
+impl RetentionSweeper {
+    /// Create a new sweeper
+    pub fn new(memory: Arc<MemorySystem>, config: RetentionConfig) -> Self {
+        Self {
+            memory,
+            config,
+            last_report: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Get the report of the most recent sweep, if any
+    pub async fn last_report(&self) -> Option<RetentionReport> {
+        self.last_report.read().await.clone()
+    }
+
+    /// Run a single sweep over every layer
+    ///
+    /// Reinforcement is read from the access statistics, so the sweep only
+    /// ever deletes from the index.
+    pub async fn run_once(&self) -> Result<RetentionReport> {
+        let started_at = Utc::now();
+        let start = std::time::Instant::now();
+        self.memory.flush().await?;
+
+        let mut scanned = 0;
+        let mut forgotten = Vec::new();
+        let mut reinforced = 0;
+        let mut pinned = 0;
+
+        for layer in MemoryLayer::ALL {
+            let policy = self.config.policy(layer);
+            let mut forgotten_in_layer = 0u64;
+
+            let records = self.memory.all_records(layer)?;
+            let access = match policy {
+                RetentionPolicy::Decay { .. } => {
+                    let ids: Vec<String> = records.iter().map(|record| record.id.clone()).collect();
+                    self.memory.access_stats(&ids).await?
+                }
+                _ => HashMap::new(),
+            };
+
+            for record in records {
+                scanned += 1;
+                let stats = access.get(&record.id);
+
+                let verdict = if record.pinned {
+                    pinned += 1;
+                    None
+                } else {
+                    match policy {
+                        RetentionPolicy::Keep => None,
+                        RetentionPolicy::Ttl(ttl) => {
+                            let age = (started_at - record.timestamp).to_std().unwrap_or_default();
+                            (age > ttl).then_some((ForgetReason::Expired, None))
+                        }
+                        RetentionPolicy::Decay { stability } => {
+                            let strength = self.config.strength(&record, stats, stability, started_at);
+                            (strength < self.config.forget_threshold)
+                                .then_some((ForgetReason::Faded, Some(strength)))
+                        }
+                    }
+                };
+
+                match verdict {
+                    Some((reason, strength)) => {
+                        self.memory.forget_record(layer, &record.id).await?;
+                        forgotten_in_layer += 1;
+                        forgotten.push(ForgottenMemory {
+                            id: record.id,
+                            layer,
+                            reason,
+                            strength,
+                        });
+                    }
+                    None if stats.is_some() => reinforced += 1,
+                    None => {}
+                }
+            }
+
+            if forgotten_in_layer > 0 {
+                counter!("memory.retention.forgotten_total", forgotten_in_layer, "layer" => layer.as_str());
+            }
+        }
+
+        self.memory.flush().await?;
+
+        let report = RetentionReport {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as u64,
+            scanned,
+            forgotten,
+            reinforced,
+            pinned,
+        };
+
+        counter!("memory.retention.sweeps_total", 1);
+        counter!("memory.retention.reinforced_total", report.reinforced as u64);
+        gauge!("memory.retention.pinned", report.pinned as f64);
+
+        info!(
+            "Memory retention: scanned {}, forgot {}, reinforced {}, pinned {} in {}ms",
+            report.scanned,
+            report.forgotten.len(),
+            report.reinforced,
+            report.pinned,
+            report.duration_ms
+        );
+
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Spawn the retention loop on the Tokio runtime
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval());
+            // The first tick completes immediately; skip it so startup isn't slowed down
+            ticker.tick().await;
+            info!("Memory retention started (every {:?})", self.config.interval());
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    error!("Memory retention sweep failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::NewMemory;
+    use tempfile::TempDir;
+
+    fn aged(layer: MemoryLayer, content: &str, days: i64) -> MemoryRecord {
+        NewMemory::new(content).into_record(layer, Utc::now() - chrono::Duration::days(days))
+    }
+
+    #[test]
+    fn test_strength_decays_and_is_reinforced() {
+        let config = RetentionConfig::default();
+        let stability = Duration::from_secs(config.long_term_stability_secs);
+        let now = Utc::now();
+
+        let fresh = aged(MemoryLayer::LongTerm, "fresh", 0);
+        assert!(config.strength(&fresh, None, stability, now) > 0.99);
+
+        let old = aged(MemoryLayer::LongTerm, "old", 120);
+        let faded = config.strength(&old, None, stability, now);
+        assert!(faded < config.forget_threshold);
+
+        let access = AccessStats {
+            memory_id: old.id.clone(),
+            retrievals: 3,
+            last_accessed: now - chrono::Duration::days(60),
+            queries: Vec::new(),
+        };
+        assert!(config.strength(&old, Some(&access), stability, now) > faded);
+    }
+
+    #[tokio::test]
+    async fn test_sweep_forgets_faded_but_keeps_pinned_and_working() {
+        let temp_dir = TempDir::new().unwrap();
+        let memory = Arc::new(MemorySystem::new(temp_dir.path().to_path_buf()).await.unwrap());
+
+        // Held in a working memory slot
+        let working = aged(MemoryLayer::Working, "Scratch note", 3);
+        let faded = aged(MemoryLayer::LongTerm, "Old parking spot", 200);
+        let retrieved = aged(MemoryLayer::LongTerm, "Grandmother's soup recipe", 200);
+        let mut pinned = aged(MemoryLayer::LongTerm, "Phoenix's birthday", 200);
+        pinned.pinned = true;
+        let recent = aged(MemoryLayer::Episodic, "Picnic at the lake", 1);
+        let semantic = aged(MemoryLayer::Semantic, "Water boils at 100C", 1000);
+        // Left to consolidation
+        let short_term = aged(MemoryLayer::ShortTerm, "Unconsolidated note", 10);
+        for record in [&working, &faded, &retrieved, &pinned, &recent, &semantic, &short_term] {
+            memory.write_record(record).await.unwrap();
+        }
+        memory.hold_working(std::slice::from_ref(&working), |_| 1.0).await.unwrap();
+        memory.flush().await.unwrap();
+        let results = memory.search(MemoryLayer::LongTerm, "soup recipe", 1).await.unwrap();
+        assert_eq!(results[0].id, retrieved.id);
+
+        let sweeper = RetentionSweeper::new(memory.clone(), RetentionConfig::default());
+        let report = sweeper.run_once().await.unwrap();
+
+        assert_eq!(report.forgotten.len(), 1);
+        assert_eq!(report.forgotten[0].id, faded.id);
+        assert_eq!(report.forgotten[0].reason, ForgetReason::Faded);
+        assert_eq!(report.pinned, 1);
+        assert_eq!(report.reinforced, 1);
+
+        assert!(memory.get(&retrieved.id).await.unwrap().is_some());
+        assert!(memory.get(&pinned.id).await.unwrap().is_some());
+        assert!(memory.get(&recent.id).await.unwrap().is_some());
+        assert!(memory.get(&semantic.id).await.unwrap().is_some());
+        assert!(memory.get(&short_term.id).await.unwrap().is_some());
+        assert!(memory.get(&working.id).await.unwrap().is_some());
+        assert_eq!(memory.working_slots()[0].record.id, working.id);
+        assert!(memory.get(&faded.id).await.unwrap().is_none());
+    }
+}
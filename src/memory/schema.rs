@@ -0,0 +1,290 @@
+//! Tantivy schema shared by all memory layers
+//!
+//! Indices written by an older version of the schema are rebuilt under the
+//! current one when they are opened, so stored memories survive schema
+//! changes.
+
+use super::dedup::{band_terms, simhash};
+use super::embedding::{vector_from_bytes, vector_to_bytes};
+use super::{MemoryLayer, MemoryRecord, DEFAULT_IMPORTANCE, SHARED_NAMESPACE, WRITER_MEMORY_BUDGET};
+use chrono::{DateTime, Utc};
+use std::path::Path;
+use tantivy::collector::DocSetCollector;
+use tantivy::query::AllQuery;
+use tantivy::schema::*;
+use tantivy::tokenizer::{LowerCaser, RawTokenizer, TextAnalyzer};
+use tantivy::{Index, IndexWriter, TantivyDocument};
+use tracing::info;
+
+/// Tokenizer for label fields (tags, entities, source, emotional tags).
+/// Each value is indexed as a single lowercased term, so filters match whole
+/// labels regardless of case.
+pub(crate) const LABEL_TOKENIZER: &str = "label";
+
+/// Build the current memory schema
+pub(crate) fn memory_schema() -> Schema {
+    let label = TextOptions::default()
+        .set_indexing_options(
+            TextFieldIndexing::default()
+                .set_tokenizer(LABEL_TOKENIZER)
+                .set_index_option(IndexRecordOption::Basic),
+        )
+        .set_stored();
+
+    let mut schema_builder = Schema::builder();
+    schema_builder.add_text_field("id", STRING | STORED);
+    schema_builder.add_text_field("content", TEXT | STORED);
+    // Owner's user ID or "shared", matched whole and case-sensitively
+    schema_builder.add_text_field("namespace", STRING | STORED);
+    // Fast with microsecond precision for recency ranking and ordering
+    schema_builder.add_date_field(
+        "timestamp",
+        DateOptions::from(INDEXED | STORED | FAST).set_precision(DateTimePrecision::Microseconds),
+    );
+    schema_builder.add_text_field("tags", label.clone());
+    schema_builder.add_text_field("entities", label.clone());
+    schema_builder.add_text_field("source", label.clone());
+    schema_builder.add_text_field("emotional_tags", label);
+    schema_builder.add_f64_field("importance", INDEXED | STORED | FAST);
+    schema_builder.add_bool_field("pinned", INDEXED | STORED);
+    schema_builder.add_u64_field("reinforcements", STORED);
+    schema_builder.add_date_field("last_reinforced", STORED);
+    // IDs of source records, matched whole
+    schema_builder.add_text_field("derived_from", STRING | STORED);
+    // Fast so vector search can scan embeddings without loading documents
+    schema_builder.add_bytes_field("embedding", STORED | FAST);
+    // SimHash of the content for near-duplicate detection; derived, not stored
+    schema_builder.add_u64_field("fingerprint", FAST);
+    // Bands of the fingerprint as terms, so near-duplicates are looked up
+    // instead of scanned
+    schema_builder.add_text_field("fingerprint_bands", STRING);
+    schema_builder.build()
+}
+
+/// Register the custom tokenizers used by the memory schema.
+/// Tokenizers are not persisted, so this must run every time an index is opened.
+pub(crate) fn register_tokenizers(index: &Index) {
+    index.tokenizers().register(
+        LABEL_TOKENIZER,
+        TextAnalyzer::builder(RawTokenizer::default())
+            .filter(LowerCaser)
+            .build(),
+    );
+}
+
+/// Open the index for a layer, creating it if it doesn't exist and migrating
+/// it if it was written with an older schema
+pub(crate) fn open_index(layer_dir: &Path, layer: MemoryLayer) -> anyhow::Result<Index> {
+    let schema = memory_schema();
+    let previous_dir = layer_dir.with_extension("previous");
+
+    // A migration interrupted between its two renames leaves the original
+    // index in `previous_dir`; put it back and migrate again
+    if !layer_dir.join("meta.json").exists() && previous_dir.join("meta.json").exists() {
+        if layer_dir.exists() {
+            std::fs::remove_dir_all(layer_dir)?;
+        }
+        std::fs::rename(&previous_dir, layer_dir)?;
+    }
+
+    let index = match Index::open_in_dir(layer_dir) {
+        Ok(index) if index.schema() == schema => index,
+        Ok(index) => migrate(&index, layer_dir, &previous_dir, schema, layer)?,
+        Err(_) => {
+            // Index doesn't exist, create it
+            Index::create_in_dir(layer_dir, schema)?
+        }
+    };
+    register_tokenizers(&index);
+
+    Ok(index)
+}
+
+/// Copy every record of `old` into a fresh index with the current schema,
+/// then swap it into place
+fn migrate(
+    old: &Index,
+    layer_dir: &Path,
+    previous_dir: &Path,
+    schema: Schema,
+    layer: MemoryLayer,
+) -> anyhow::Result<Index> {
+    let staging_dir = layer_dir.with_extension("migrating");
+    if staging_dir.exists() {
+        std::fs::remove_dir_all(&staging_dir)?;
+    }
+    std::fs::create_dir_all(&staging_dir)?;
+
+    let old_schema = old.schema();
+    let searcher = old.reader()?.searcher();
+    let doc_addresses = searcher.search(&AllQuery, &DocSetCollector)?;
+
+    let index = Index::create_in_dir(&staging_dir, schema.clone())?;
+    register_tokenizers(&index);
+    let mut writer: IndexWriter = index.writer(WRITER_MEMORY_BUDGET)?;
+    for doc_address in &doc_addresses {
+        let doc: TantivyDocument = searcher.doc(*doc_address)?;
+        let record = record_from_doc(&old_schema, &doc, layer)?;
+        writer.add_document(doc_from_record(&schema, &record)?)?;
+    }
+    writer.commit()?;
+    writer.wait_merging_threads()?;
+
+    std::fs::rename(layer_dir, previous_dir)?;
+    std::fs::rename(&staging_dir, layer_dir)?;
+    std::fs::remove_dir_all(previous_dir)?;
+
+    info!(
+        "Migrated {} {} memories to the current index schema",
+        doc_addresses.len(),
+        layer.as_str()
+    );
+    Ok(Index::open_in_dir(layer_dir)?)
+}
+
+pub(crate) fn doc_from_record(schema: &Schema, record: &MemoryRecord) -> anyhow::Result<TantivyDocument> {
+    let id_field = schema.get_field("id")?;
+    let content_field = schema.get_field("content")?;
+    let timestamp_field = schema.get_field("timestamp")?;
+    let namespace_field = schema.get_field("namespace")?;
+    let tags_field = schema.get_field("tags")?;
+    let entities_field = schema.get_field("entities")?;
+    let source_field = schema.get_field("source")?;
+    let emotional_tags_field = schema.get_field("emotional_tags")?;
+    let importance_field = schema.get_field("importance")?;
+    let embedding_field = schema.get_field("embedding")?;
+    let pinned_field = schema.get_field("pinned")?;
+    let reinforcements_field = schema.get_field("reinforcements")?;
+    let last_reinforced_field = schema.get_field("last_reinforced")?;
+    let derived_from_field = schema.get_field("derived_from")?;
+    let fingerprint_field = schema.get_field("fingerprint")?;
+    let fingerprint_bands_field = schema.get_field("fingerprint_bands")?;
+
+    let mut doc = TantivyDocument::default();
+    doc.add_text(id_field, &record.id);
+    doc.add_text(content_field, &record.content);
+    let tantivy_timestamp = tantivy::DateTime::from_timestamp_micros(record.timestamp.timestamp_micros());
+    doc.add_date(timestamp_field, tantivy_timestamp);
+    doc.add_text(namespace_field, &record.namespace);
+    for tag in &record.tags {
+        doc.add_text(tags_field, tag);
+    }
+    for entity in &record.entities {
+        doc.add_text(entities_field, entity);
+    }
+    if let Some(source) = &record.source {
+        doc.add_text(source_field, source);
+    }
+    for emotion in &record.emotional_tags {
+        doc.add_text(emotional_tags_field, emotion);
+    }
+    doc.add_f64(importance_field, record.importance as f64);
+    doc.add_bool(pinned_field, record.pinned);
+    doc.add_u64(reinforcements_field, record.reinforcements);
+    if let Some(last_reinforced) = record.last_reinforced {
+        doc.add_date(
+            last_reinforced_field,
+            tantivy::DateTime::from_timestamp_micros(last_reinforced.timestamp_micros()),
+        );
+    }
+    for id in &record.derived_from {
+        doc.add_text(derived_from_field, id);
+    }
+    if let Some(embedding) = &record.embedding {
+        doc.add_bytes(embedding_field, vector_to_bytes(embedding));
+    }
+    let fingerprint = simhash(&record.content);
+    doc.add_u64(fingerprint_field, fingerprint);
+    for band in band_terms(fingerprint) {
+        doc.add_text(fingerprint_bands_field, band);
+    }
+
+    Ok(doc)
+}
+
+/// Read a record back from a stored document. Fields missing from `schema`
+/// (indices written by an older version) fall back to their defaults.
+pub(crate) fn record_from_doc(
+    schema: &Schema,
+    doc: &TantivyDocument,
+    layer: MemoryLayer,
+) -> anyhow::Result<MemoryRecord> {
+    let id_field = schema.get_field("id")?;
+    let content_field = schema.get_field("content")?;
+    let timestamp_field = schema.get_field("timestamp")?;
+
+    let id = doc
+        .get_first(id_field)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let content = doc
+        .get_first(content_field)
+        .and_then(|v| v.as_str())
+        .unwrap_or("")
+        .to_string();
+
+    let timestamp = doc
+        .get_first(timestamp_field)
+        .and_then(|v| v.as_datetime())
+        .map(|dt| DateTime::from_timestamp_micros(dt.into_timestamp_micros()).unwrap_or_else(Utc::now))
+        .unwrap_or_else(Utc::now);
+
+    let importance = schema
+        .get_field("importance")
+        .ok()
+        .and_then(|field| doc.get_first(field))
+        .and_then(|v| v.as_f64())
+        .map(|v| v as f32)
+        .unwrap_or(DEFAULT_IMPORTANCE);
+
+    let embedding = schema
+        .get_field("embedding")
+        .ok()
+        .and_then(|field| doc.get_first(field))
+        .and_then(|v| v.as_bytes())
+        .map(vector_from_bytes);
+
+    let field_value = |name: &str| schema.get_field(name).ok().and_then(|field| doc.get_first(field));
+    let pinned = field_value("pinned").and_then(|v| v.as_bool()).unwrap_or(false);
+    let reinforcements = field_value("reinforcements").and_then(|v| v.as_u64()).unwrap_or(0);
+    let last_reinforced = field_value("last_reinforced")
+        .and_then(|v| v.as_datetime())
+        .and_then(|dt| DateTime::from_timestamp_micros(dt.into_timestamp_micros()));
+
+    Ok(MemoryRecord {
+        id,
+        content,
+        timestamp,
+        layer,
+        namespace: labels(schema, doc, "namespace")
+            .into_iter()
+            .next()
+            .unwrap_or_else(|| SHARED_NAMESPACE.to_string()),
+        tags: labels(schema, doc, "tags"),
+        entities: labels(schema, doc, "entities"),
+        source: labels(schema, doc, "source").into_iter().next(),
+        importance,
+        emotional_tags: labels(schema, doc, "emotional_tags"),
+        pinned,
+        reinforcements,
+        last_reinforced,
+        derived_from: labels(schema, doc, "derived_from"),
+        embedding,
+        score: None,
+        snippet: None,
+        explanation: None,
+    })
+}
+
+fn labels(schema: &Schema, doc: &TantivyDocument, field_name: &str) -> Vec<String> {
+    match schema.get_field(field_name) {
+        Ok(field) => doc
+            .get_all(field)
+            .filter_map(|v| v.as_str())
+            .map(str::to_string)
+            .collect(),
+        Err(_) => Vec::new(),
+    }
+}
@@ -0,0 +1,153 @@
+//! SQLite copy of every memory record
+//!
+//! When a database is attached with [`MemorySystem::with_database`], every
+//! write goes to the `memory_records` table, through [`DatabaseOperations`],
+//! before it is staged in the Tantivy index. The table is the source of
+//! truth: the indices are derived from it and can be regenerated with
+//! [`MemorySystem::rebuild_index`]. This module compares the two copies.
+//!
+//! [`MemorySystem::with_database`]: super::MemorySystem::with_database
+//! [`MemorySystem::rebuild_index`]: super::MemorySystem::rebuild_index
+//! [`DatabaseOperations`]: crate::db::DatabaseOperations
+
+use super::{MemoryLayer, MemoryRecord};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+#[cfg(test)]
+use sqlx::SqlitePool;
+use std::collections::HashMap;
+
+/// Differences between the database and the index of one layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LayerConsistency {
+    pub layer: MemoryLayer,
+    pub database_records: usize,
+    pub indexed_records: usize,
+    /// Ids stored in the database but not in the index
+    pub missing_from_index: Vec<String>,
+    /// Ids indexed but not stored in the database
+    pub missing_from_database: Vec<String>,
+    /// Ids present in both whose fields differ
+    pub mismatched: Vec<String>,
+}
+
+impl LayerConsistency {
+    pub fn is_consistent(&self) -> bool {
+        self.missing_from_index.is_empty()
+            && self.missing_from_database.is_empty()
+            && self.mismatched.is_empty()
+    }
+}
+
+/// Result of comparing every layer index against the database
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ConsistencyReport {
+    pub checked_at: DateTime<Utc>,
+    pub layers: Vec<LayerConsistency>,
+}
+
+impl ConsistencyReport {
+    pub fn is_consistent(&self) -> bool {
+        self.layers.iter().all(LayerConsistency::is_consistent)
+    }
+}
+
+/// Compare the database and index copies of one layer
+pub(crate) fn compare_layer(
+    layer: MemoryLayer,
+    database: Vec<MemoryRecord>,
+    indexed: Vec<MemoryRecord>,
+) -> LayerConsistency {
+    let database_records = database.len();
+    let indexed_records = indexed.len();
+    let mut indexed: HashMap<String, MemoryRecord> = indexed
+        .into_iter()
+        .map(|record| (record.id.clone(), record))
+        .collect();
+
+    let mut missing_from_index = Vec::new();
+    let mut mismatched = Vec::new();
+    for record in database {
+        match indexed.remove(&record.id) {
+            Some(copy) if same_record(&record, &copy) => {}
+            Some(_) => mismatched.push(record.id),
+            None => missing_from_index.push(record.id),
+        }
+    }
+
+    let mut missing_from_database: Vec<String> = indexed.into_keys().collect();
+    missing_from_database.sort();
+
+    LayerConsistency {
+        layer,
+        database_records,
+        indexed_records,
+        missing_from_index,
+        missing_from_database,
+        mismatched,
+    }
+}
+
+/// Field-by-field comparison; timestamps are compared at the microsecond
+/// precision the index keeps
+fn same_record(a: &MemoryRecord, b: &MemoryRecord) -> bool {
+    a.content == b.content
+        && a.namespace == b.namespace
+        && a.timestamp.timestamp_micros() == b.timestamp.timestamp_micros()
+        && a.tags == b.tags
+        && a.entities == b.entities
+        && a.source == b.source
+        && a.importance == b.importance
+        && a.emotional_tags == b.emotional_tags
+        && a.pinned == b.pinned
+        && a.reinforcements == b.reinforcements
+        && a.last_reinforced.map(|t| t.timestamp_micros()) == b.last_reinforced.map(|t| t.timestamp_micros())
+        && a.derived_from == b.derived_from
+        && a.embedding == b.embedding
+}
+
+#[cfg(test)]
+pub(crate) async fn test_pool() -> SqlitePool {
+    let pool = sqlx::sqlite::SqlitePoolOptions::new()
+        .max_connections(1)
+        .connect("sqlite::memory:")
+        .await
+        .unwrap();
+    sqlx::migrate!("./migrations").run(&pool).await.unwrap();
+    pool
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{DatabaseOperations, DbMetrics};
+    use crate::memory::NewMemory;
+
+    #[tokio::test]
+    async fn test_round_trips_records() {
+        let store = DatabaseOperations::new(test_pool().await, DbMetrics::new());
+        let mut record = NewMemory::new("Phoenix's recital moved to Saturday")
+            .with_namespace("phoenix")
+            .with_tags(["family"])
+            .with_entities(["Phoenix"])
+            .with_source("cli")
+            .with_importance(0.8)
+            .with_emotional_tags(["joy"])
+            .pinned(true)
+            .into_record(MemoryLayer::Episodic, Utc::now());
+        record.reinforcements = 2;
+        record.last_reinforced = Some(Utc::now());
+        record.embedding = Some(vec![0.5, -0.25]);
+
+        store.upsert_memory_records(std::slice::from_ref(&record)).await.unwrap();
+        let stored = store.memory_records_in_layer(MemoryLayer::Episodic).await.unwrap();
+        assert_eq!(stored.len(), 1);
+        assert!(same_record(&stored[0], &record));
+        assert!(store.memory_records_in_layer(MemoryLayer::ShortTerm).await.unwrap().is_empty());
+
+        // Deleting from another layer leaves the row alone
+        assert!(!store.delete_memory_record(MemoryLayer::ShortTerm, &record.id).await.unwrap());
+        assert!(store.delete_memory_record(MemoryLayer::Episodic, &record.id).await.unwrap());
+        assert!(store.memory_records_in_layer(MemoryLayer::Episodic).await.unwrap().is_empty());
+    }
+}
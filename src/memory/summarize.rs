@@ -0,0 +1,577 @@
+//! Summarization of episodic memories into semantic facts
+//!
+//! Periodically groups recent Episodic and ShortTerm records by time window,
+//! namespace and entity, asks a language model to extract the durable facts
+//! in each group, and stores those facts in the Semantic layer of the same
+//! namespace. Every fact keeps the IDs of the records it came from in
+//! `derived_from`. With a knowledge graph attached, the triples the model
+//! extracts from each fact are asserted into it too, with the fact as their
+//! source.
+
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use chrono::{DateTime, Utc};
+use futures::future::BoxFuture;
+use metrics::counter;
+use serde::{Deserialize, Serialize};
+use tokio::sync::RwLock;
+use tokio::task::JoinHandle;
+use tracing::{error, info, warn};
+
+use crate::config::env_or;
+use super::graph::{KnowledgeGraph, NewTriple, TripleObject};
+use super::{MemoryLayer, MemoryRecord, MemorySystem, NewMemory};
+
+/// Value of `source` on every summarized fact
+pub const SUMMARIZER_SOURCE: &str = "summarizer";
+
+/// Confidence of triples extracted by the language model
+const EXTRACTED_TRIPLE_CONFIDENCE: f32 = 0.8;
+
+/// Answers a single prompt with text
+pub trait LanguageModel: Send + Sync {
+    fn complete<'a>(&'a self, prompt: &'a str) -> BoxFuture<'a, Result<String>>;
+}
+
+/// Summarizer schedule and grouping
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizerConfig {
+    /// How often the background task runs, in seconds
+    pub interval_secs: u64,
+    /// How far back records are considered, in seconds
+    pub lookback_secs: u64,
+    /// Length of the time windows records are grouped by, in seconds
+    pub window_secs: u64,
+    /// Groups smaller than this are left for a later run
+    pub min_group_size: usize,
+    /// Larger groups are split into several prompts
+    pub max_group_size: usize,
+}
+
+impl Default for SummarizerConfig {
+    fn default() -> Self {
+        Self {
+            interval_secs: 3600,   // 1 hour
+            lookback_secs: 86400,  // 24 hours
+            window_secs: 3600,     // 1 hour
+            min_group_size: 2,
+            max_group_size: 40,
+        }
+    }
+}
+
+impl SummarizerConfig {
+    /// Load summarizer configuration from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            interval_secs: env_or("MEMORY_SUMMARIZER_INTERVAL_SECS", defaults.interval_secs),
+            lookback_secs: env_or("MEMORY_SUMMARIZER_LOOKBACK_SECS", defaults.lookback_secs),
+            window_secs: env_or("MEMORY_SUMMARIZER_WINDOW_SECS", defaults.window_secs),
+            min_group_size: env_or("MEMORY_SUMMARIZER_MIN_GROUP_SIZE", defaults.min_group_size),
+            max_group_size: env_or("MEMORY_SUMMARIZER_MAX_GROUP_SIZE", defaults.max_group_size),
+        }
+    }
+
+    /// Get the run interval as Duration
+    pub fn interval(&self) -> Duration {
+        Duration::from_secs(self.interval_secs)
+    }
+}
+
+/// A fact stored in the Semantic layer
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummarizedFact {
+    /// Id of the Semantic record, which may be an existing record the fact
+    /// was merged into
+    pub id: String,
+    pub content: String,
+    /// Ids of the records the fact was extracted from
+    pub derived_from: Vec<String>,
+    /// Ids of the knowledge graph triples asserted from the fact
+    #[serde(default)]
+    pub triples: Vec<String>,
+}
+
+/// Outcome of a single summarization run
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SummaryReport {
+    /// When the run started
+    pub started_at: DateTime<Utc>,
+    /// How long the run took, in milliseconds
+    pub duration_ms: u64,
+    /// Number of records not summarized before
+    pub candidates: usize,
+    /// Number of groups sent to the language model
+    pub groups: usize,
+    /// Groups whose completion failed or couldn't be parsed; they are
+    /// retried on the next run
+    pub failed_groups: usize,
+    pub facts: Vec<SummarizedFact>,
+    /// Number of asserted triples that contradicted stored ones
+    #[serde(default)]
+    pub contradictions: usize,
+}
+
+/// A fact as returned by the language model
+#[derive(Debug, Deserialize)]
+struct ExtractedFact {
+    fact: String,
+    #[serde(default)]
+    entities: Vec<String>,
+    /// 1-based positions of the source records in the prompt
+    #[serde(default)]
+    sources: Vec<usize>,
+    #[serde(default)]
+    triples: Vec<ExtractedTriple>,
+}
+
+/// A triple as returned by the language model
+#[derive(Debug, Deserialize)]
+struct ExtractedTriple {
+    subject: String,
+    predicate: String,
+    object: String,
+    /// The object is a value rather than an entity
+    #[serde(default)]
+    literal: bool,
+}
+
+/// Runs summarization passes over a memory system
+pub struct Summarizer {
+    memory: Arc<MemorySystem>,
+    model: Arc<dyn LanguageModel>,
+    config: SummarizerConfig,
+    knowledge: Option<Arc<KnowledgeGraph>>,
+    /// Records already sent to the model, with their timestamps so entries
+    /// can be dropped once they leave the lookback window
+    settled: Mutex<HashMap<String, DateTime<Utc>>>,
+    last_report: Arc<RwLock<Option<SummaryReport>>>,
+}
+
+impl Summarizer {
+    /// Create a new summarizer
+    pub fn new(memory: Arc<MemorySystem>, model: Arc<dyn LanguageModel>, config: SummarizerConfig) -> Self {
+        Self {
+            memory,
+            model,
+            config,
+            knowledge: None,
+            settled: Mutex::new(HashMap::new()),
+            last_report: Arc::new(RwLock::new(None)),
+        }
+    }
+
+    /// Assert the triples extracted from each fact into a knowledge graph
+    pub fn with_knowledge_graph(mut self, knowledge: Arc<KnowledgeGraph>) -> Self {
+        self.knowledge = Some(knowledge);
+        self
+    }
+
+    /// Get the report of the most recent run, if any
+    pub async fn last_report(&self) -> Option<SummaryReport> {
+        self.last_report.read().await.clone()
+    }
+
+    /// Run a single summarization pass
+    ///
+    /// Records cited by a Semantic record, or already sent to the model by
+    /// this summarizer, are skipped.
+    pub async fn run_once(&self) -> Result<SummaryReport> {
+        let started_at = Utc::now();
+        let start = std::time::Instant::now();
+        let since = started_at - chrono::Duration::seconds(self.config.lookback_secs as i64);
+
+        // Make every acknowledged store visible before scanning
+        self.memory.flush().await?;
+        let mut summarized: HashSet<String> = self
+            .memory
+            .all_records(MemoryLayer::Semantic)?
+            .into_iter()
+            .flat_map(|record| record.derived_from)
+            .collect();
+        {
+            let mut settled = self.settled.lock().unwrap();
+            settled.retain(|_, timestamp| *timestamp >= since);
+            summarized.extend(settled.keys().cloned());
+        }
+
+        let mut candidates = Vec::new();
+        for layer in [MemoryLayer::Episodic, MemoryLayer::ShortTerm] {
+            candidates.extend(
+                self.memory
+                    .all_records(layer)?
+                    .into_iter()
+                    .filter(|record| record.timestamp >= since && !summarized.contains(&record.id)),
+            );
+        }
+        let candidate_count = candidates.len();
+
+        let mut groups = 0;
+        let mut failed_groups = 0;
+        let mut facts = Vec::new();
+        let mut contradictions = 0;
+        for group in self.group(candidates) {
+            groups += 1;
+            match self.summarize_group(&group).await {
+                Ok((group_facts, group_contradictions)) => {
+                    let mut settled = self.settled.lock().unwrap();
+                    for record in &group {
+                        settled.insert(record.id.clone(), record.timestamp);
+                    }
+                    facts.extend(group_facts);
+                    contradictions += group_contradictions;
+                }
+                Err(e) => {
+                    warn!("Failed to summarize {} memories: {}", group.len(), e);
+                    failed_groups += 1;
+                }
+            }
+        }
+
+        let report = SummaryReport {
+            started_at,
+            duration_ms: start.elapsed().as_millis() as u64,
+            candidates: candidate_count,
+            groups,
+            failed_groups,
+            facts,
+            contradictions,
+        };
+
+        counter!("memory.summarizer.runs_total", 1);
+        counter!("memory.summarizer.facts_total", report.facts.len() as u64);
+        counter!("memory.summarizer.failed_groups_total", report.failed_groups as u64);
+
+        info!(
+            "Memory summarization: {} candidates in {} groups, {} facts, {} failed groups in {}ms",
+            report.candidates,
+            report.groups,
+            report.facts.len(),
+            report.failed_groups,
+            report.duration_ms
+        );
+
+        *self.last_report.write().await = Some(report.clone());
+        Ok(report)
+    }
+
+    /// Group records by time window, namespace and first entity, oldest
+    /// first, keeping only groups of at least `min_group_size` records
+    fn group(&self, records: Vec<MemoryRecord>) -> Vec<Vec<MemoryRecord>> {
+        let window_secs = self.config.window_secs.max(1) as i64;
+        let mut grouped: BTreeMap<(i64, String, String), Vec<MemoryRecord>> = BTreeMap::new();
+        for record in records {
+            let window = record.timestamp.timestamp().div_euclid(window_secs);
+            let entity = record
+                .entities
+                .first()
+                .map(|entity| entity.to_lowercase())
+                .unwrap_or_default();
+            grouped
+                .entry((window, record.namespace.clone(), entity))
+                .or_default()
+                .push(record);
+        }
+
+        let max_group_size = self.config.max_group_size.max(1);
+        let mut groups = Vec::new();
+        for mut group in grouped.into_values() {
+            if group.len() < self.config.min_group_size {
+                continue;
+            }
+            group.sort_by_key(|record| record.timestamp);
+            groups.extend(group.chunks(max_group_size).map(<[MemoryRecord]>::to_vec));
+        }
+        groups
+    }
+
+    /// Extract facts from one group and store them in Semantic, and their
+    /// triples in the knowledge graph, in the group's namespace
+    ///
+    /// Returns the facts and the number of contradictions their triples
+    /// raised.
+    async fn summarize_group(&self, group: &[MemoryRecord]) -> Result<(Vec<SummarizedFact>, usize)> {
+        let completion = self.model.complete(&summary_prompt(group, self.knowledge.is_some())).await?;
+        let extracted = parse_facts(&completion)?;
+        let importance = group
+            .iter()
+            .map(|record| record.importance)
+            .fold(0.0f32, f32::max);
+
+        let mut facts = Vec::new();
+        let mut contradictions = 0;
+        for fact in extracted {
+            let content = fact.fact.trim();
+            if content.is_empty() {
+                continue;
+            }
+
+            let mut derived_from: Vec<String> = fact
+                .sources
+                .iter()
+                .filter_map(|&position| group.get(position.checked_sub(1)?))
+                .map(|record| record.id.clone())
+                .collect();
+            let mut seen = HashSet::new();
+            derived_from.retain(|id| seen.insert(id.clone()));
+            if derived_from.is_empty() {
+                derived_from = group.iter().map(|record| record.id.clone()).collect();
+            }
+            let entities = if fact.entities.is_empty() {
+                group[0].entities.iter().take(1).cloned().collect()
+            } else {
+                fact.entities
+            };
+
+            let id = self
+                .memory
+                .store_with(
+                    MemoryLayer::Semantic,
+                    NewMemory::new(content)
+                        .with_namespace(group[0].namespace.clone())
+                        .with_entities(entities)
+                        .with_source(SUMMARIZER_SOURCE)
+                        .with_importance(importance)
+                        .with_derived_from(derived_from.clone()),
+                )
+                .await?;
+
+            let mut triples = Vec::new();
+            if let Some(knowledge) = &self.knowledge {
+                for triple in fact.triples {
+                    let object = if triple.literal {
+                        TripleObject::Literal(triple.object)
+                    } else {
+                        TripleObject::Entity(triple.object)
+                    };
+                    let new_triple = NewTriple::new(triple.subject, triple.predicate, object)
+                        .with_namespace(group[0].namespace.clone())
+                        .with_confidence(EXTRACTED_TRIPLE_CONFIDENCE)
+                        .with_source(id.clone());
+                    // The fact itself is stored; a bad triple only loses the triple
+                    match knowledge.assert(new_triple).await {
+                        Ok(assertion) => {
+                            contradictions += assertion.contradictions.len();
+                            triples.push(assertion.triple.id);
+                        }
+                        Err(e) => warn!("Failed to assert a triple from fact {}: {}", id, e),
+                    }
+                }
+            }
+
+            facts.push(SummarizedFact {
+                id,
+                content: content.to_string(),
+                derived_from,
+                triples,
+            });
+        }
+        Ok((facts, contradictions))
+    }
+
+    /// Spawn the summarization loop on the Tokio runtime
+    pub fn spawn(self: Arc<Self>) -> JoinHandle<()> {
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(self.config.interval());
+            // The first tick completes immediately; skip it so startup isn't slowed down
+            ticker.tick().await;
+            info!("Memory summarization started (every {:?})", self.config.interval());
+
+            loop {
+                ticker.tick().await;
+                if let Err(e) = self.run_once().await {
+                    error!("Memory summarization failed: {}", e);
+                }
+            }
+        })
+    }
+}
+
+/// Prompt asking for the durable facts in a group, with numbered records,
+/// and for the triples in each fact when `with_triples` is set
+fn summary_prompt(group: &[MemoryRecord], with_triples: bool) -> String {
+    let mut prompt = String::from(
+        "Below are numbered memories recorded by an AI assistant. Extract the durable facts \
+         they contain: lasting information about people, places, preferences, plans and \
+         relationships that will still be true later. Ignore small talk and one-off events \
+         that don't teach anything lasting.\n\n",
+    );
+    for (position, record) in group.iter().enumerate() {
+        prompt.push_str(&format!(
+            "[{}] {}: {}\n",
+            position + 1,
+            record.timestamp.format("%Y-%m-%d %H:%M UTC"),
+            record.content
+        ));
+    }
+    if with_triples {
+        prompt.push_str(
+            "\nReply with only a JSON array, one object per fact: \
+             {\"fact\": \"...\", \"entities\": [\"...\"], \"sources\": [1, 2], \"triples\": \
+             [{\"subject\": \"...\", \"predicate\": \"...\", \"object\": \"...\", \"literal\": false}]} \
+             where sources are the numbers of the memories the fact comes from and triples \
+             restate the fact as subject-predicate-object relations. Use short snake_case \
+             predicates such as lives_in or plays, prefix negated ones with not_, and set \
+             literal to true when the object is a value such as a date or a number rather \
+             than a person, place or thing. Reply with [] if there are none.",
+        );
+    } else {
+        prompt.push_str(
+            "\nReply with only a JSON array, one object per fact: \
+             {\"fact\": \"...\", \"entities\": [\"...\"], \"sources\": [1, 2]} where sources are \
+             the numbers of the memories the fact comes from. Reply with [] if there are none.",
+        );
+    }
+    prompt
+}
+
+/// Parse the JSON array of facts out of a completion, ignoring any text or
+/// code fences around it
+fn parse_facts(completion: &str) -> Result<Vec<ExtractedFact>> {
+    let start = completion.find('[').context("Completion contains no JSON array")?;
+    let end = completion.rfind(']').context("Completion contains no JSON array")?;
+    if end < start {
+        anyhow::bail!("Completion contains no JSON array");
+    }
+    serde_json::from_str(&completion[start..=end]).context("Completion is not a JSON array of facts")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::config::{Config, ConsciousnessConfig, DatabaseConfig, SoulConfig};
+    use crate::llm::OpenRouterClient;
+    use axum::{extract::State, routing::post, Json, Router};
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use tempfile::TempDir;
+
+    /// Serve a fixed completion from an OpenAI-compatible endpoint and count
+    /// the requests
+    async fn mock_completion_server(content: &'static str) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let app = Router::new()
+            .route(
+                "/chat/completions",
+                post(move |State(requests): State<Arc<AtomicUsize>>| async move {
+                    requests.fetch_add(1, Ordering::SeqCst);
+                    Json(serde_json::json!({
+                        "choices": [{ "message": { "role": "assistant", "content": content } }]
+                    }))
+                }),
+            )
+            .with_state(requests.clone());
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(async move { axum::serve(listener, app).await.unwrap() });
+        (url, requests)
+    }
+
+    fn client(url: String) -> Arc<OpenRouterClient> {
+        Arc::new(OpenRouterClient::new(Arc::new(Config {
+            openrouter_api_key: "test".to_string(),
+            openrouter_model: "test-model".to_string(),
+            openrouter_api_url: url,
+            database_url: None,
+            mqtt: None,
+            soul: SoulConfig::default(),
+            consciousness: ConsciousnessConfig::default(),
+            database: DatabaseConfig::default(),
+        })))
+    }
+
+    #[test]
+    fn test_parse_facts() {
+        let facts = parse_facts(
+            "Here you go:\n```json\n[{\"fact\": \"Phoenix plays piano\", \"sources\": [2]}]\n```",
+        )
+        .unwrap();
+        assert_eq!(facts.len(), 1);
+        assert_eq!(facts[0].fact, "Phoenix plays piano");
+        assert_eq!(facts[0].sources, vec![2]);
+        assert!(facts[0].triples.is_empty());
+        assert!(parse_facts("No facts today").is_err());
+
+        let facts = parse_facts(
+            r#"[{"fact": "Phoenix was born in 2015", "triples": [{"subject": "Phoenix", "predicate": "born_on", "object": "2015", "literal": true}]}]"#,
+        )
+        .unwrap();
+        assert_eq!(facts[0].triples.len(), 1);
+        assert!(facts[0].triples[0].literal);
+    }
+
+    #[tokio::test]
+    async fn test_summarizes_groups_into_semantic_facts() {
+        let (url, requests) = mock_completion_server(
+            r#"[{"fact": "Phoenix plays the piano and has a recital on Friday", "entities": ["Phoenix"], "sources": [1, 2]}]"#,
+        )
+        .await;
+        let dir = TempDir::new().unwrap();
+        let memory = Arc::new(MemorySystem::new(dir.path().to_path_buf()).await.unwrap());
+
+        // Two records about Phoenix in the same window, one on its own
+        let window_start = (Utc::now().timestamp() / 3600 - 1) * 3600;
+        let at = |offset: i64| DateTime::from_timestamp(window_start + offset, 0).unwrap();
+        let recital = NewMemory::new("Phoenix practiced piano for her recital")
+            .with_entities(["Phoenix"])
+            .into_record(MemoryLayer::Episodic, at(60));
+        let friday = NewMemory::new("User: Phoenix's recital is on Friday")
+            .with_entities(["Phoenix"])
+            .into_record(MemoryLayer::ShortTerm, at(120));
+        let router = NewMemory::new("Router firmware updated").into_record(MemoryLayer::Episodic, at(180));
+        for record in [&recital, &friday, &router] {
+            memory.write_record(record).await.unwrap();
+        }
+
+        let summarizer = Summarizer::new(memory.clone(), client(url), SummarizerConfig::default());
+        let report = summarizer.run_once().await.unwrap();
+        assert_eq!((report.candidates, report.groups, report.failed_groups), (3, 1, 0));
+        assert_eq!(report.facts.len(), 1);
+        assert_eq!(requests.load(Ordering::SeqCst), 1);
+
+        memory.flush().await.unwrap();
+        let semantic = memory.all_records(MemoryLayer::Semantic).unwrap();
+        assert_eq!(semantic.len(), 1);
+        assert_eq!(semantic[0].source.as_deref(), Some(SUMMARIZER_SOURCE));
+        assert_eq!(semantic[0].entities, vec!["Phoenix"]);
+        assert_eq!(semantic[0].derived_from, vec![recital.id.clone(), friday.id.clone()]);
+
+        // Summarized records aren't sent again, even by a fresh summarizer
+        let summarizer = Summarizer::new(memory.clone(), client("http://127.0.0.1:9".to_string()), SummarizerConfig::default());
+        let report = summarizer.run_once().await.unwrap();
+        assert_eq!((report.candidates, report.groups), (1, 0));
+    }
+
+    #[tokio::test]
+    async fn test_derived_from_lists_each_source_once() {
+        let (url, _) = mock_completion_server(
+            r#"[{"fact": "Phoenix plays the piano", "entities": ["Phoenix"], "sources": [1, 2, 1, 2, 1]}]"#,
+        )
+        .await;
+        let dir = TempDir::new().unwrap();
+        let memory = Arc::new(MemorySystem::new(dir.path().to_path_buf()).await.unwrap());
+
+        let window_start = (Utc::now().timestamp() / 3600 - 1) * 3600;
+        let at = |offset: i64| DateTime::from_timestamp(window_start + offset, 0).unwrap();
+        let practice = NewMemory::new("Phoenix practiced piano")
+            .with_entities(["Phoenix"])
+            .into_record(MemoryLayer::Episodic, at(60));
+        let lesson = NewMemory::new("Phoenix had a piano lesson")
+            .with_entities(["Phoenix"])
+            .into_record(MemoryLayer::Episodic, at(120));
+        for record in [&practice, &lesson] {
+            memory.write_record(record).await.unwrap();
+        }
+
+        let summarizer = Summarizer::new(memory.clone(), client(url), SummarizerConfig::default());
+        summarizer.run_once().await.unwrap();
+
+        memory.flush().await.unwrap();
+        let semantic = memory.all_records(MemoryLayer::Semantic).unwrap();
+        assert_eq!(semantic.len(), 1);
+        assert_eq!(semantic[0].derived_from, vec![practice.id.clone(), lesson.id.clone()]);
+    }
+}
@@ -0,0 +1,499 @@
+//! Capacity-limited Working memory
+//!
+//! Working memory holds a handful of slots, seven by default. Every slot
+//! carries an activation level between 0.0 and 1.0 that decays on each tick.
+//! A new item takes the slot of the least active one when every slot is
+//! taken; items offered by the consciousness engine only do so if they are
+//! more active than that slot. Attending to a slot's content refreshes it.
+//!
+//! The slots are the source of truth for the Working layer: its index (and
+//! database rows) only ever hold the records currently in a slot. Evicted
+//! records are moved to ShortTerm when `spill_to_short_term` is set and
+//! deleted otherwise.
+
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::Result;
+use chrono::{DateTime, Utc};
+use metrics::{counter, gauge};
+use serde::{Deserialize, Serialize};
+use tokio::task::JoinHandle;
+use tracing::{debug, error};
+
+use crate::config::env_or;
+use super::{MemoryLayer, MemoryRecord, MemorySystem, NewMemory};
+
+/// Value of `source` on records held for the consciousness engine
+pub const CONSCIOUSNESS_SOURCE: &str = "consciousness";
+
+/// Working memory capacity and activation dynamics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingMemoryConfig {
+    /// Number of slots
+    pub capacity: usize,
+    /// Fraction of its activation a slot loses on every tick
+    pub decay_rate: f64,
+    /// How often activation decays, in seconds
+    pub tick_secs: u64,
+    /// Slots that decay below this activation are evicted
+    pub min_activation: f64,
+    /// Activation added to slots matching the current attention focus
+    pub focus_boost: f64,
+    /// Move evicted records to ShortTerm instead of deleting them
+    pub spill_to_short_term: bool,
+}
+
+impl Default for WorkingMemoryConfig {
+    fn default() -> Self {
+        Self {
+            capacity: 7,
+            decay_rate: 0.05,
+            tick_secs: 10,
+            min_activation: 0.05,
+            focus_boost: 0.3,
+            spill_to_short_term: true,
+        }
+    }
+}
+
+impl WorkingMemoryConfig {
+    /// Load working memory configuration from environment variables
+    pub fn from_env() -> Self {
+        let defaults = Self::default();
+        Self {
+            capacity: env_or("MEMORY_WORKING_CAPACITY", defaults.capacity),
+            decay_rate: env_or("MEMORY_WORKING_DECAY_RATE", defaults.decay_rate),
+            tick_secs: env_or("MEMORY_WORKING_TICK_SECS", defaults.tick_secs),
+            min_activation: env_or("MEMORY_WORKING_MIN_ACTIVATION", defaults.min_activation),
+            focus_boost: env_or("MEMORY_WORKING_FOCUS_BOOST", defaults.focus_boost),
+            spill_to_short_term: env_or("MEMORY_WORKING_SPILL", defaults.spill_to_short_term),
+        }
+    }
+
+    /// Get the decay tick as Duration
+    pub fn tick(&self) -> Duration {
+        Duration::from_secs(self.tick_secs)
+    }
+}
+
+/// A record held in Working memory
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkingSlot {
+    pub record: MemoryRecord,
+    /// From 0.0 to 1.0; the least active slot is replaced first
+    pub activation: f64,
+    /// When the record entered its slot
+    pub entered_at: DateTime<Utc>,
+    /// When the record was last stored, offered or attended to
+    pub last_activated: DateTime<Utc>,
+}
+
+/// The slots themselves, without any storage
+#[derive(Debug)]
+pub struct WorkingMemory {
+    slots: Mutex<Vec<WorkingSlot>>,
+    config: WorkingMemoryConfig,
+}
+
+impl WorkingMemory {
+    /// Create empty working memory
+    pub fn new(config: WorkingMemoryConfig) -> Self {
+        Self {
+            slots: Mutex::new(Vec::with_capacity(config.capacity)),
+            config,
+        }
+    }
+
+    pub fn config(&self) -> &WorkingMemoryConfig {
+        &self.config
+    }
+
+    /// The current slots, most active first
+    pub fn slots(&self) -> Vec<WorkingSlot> {
+        let mut slots = self.slots.lock().unwrap().clone();
+        slots.sort_by(|a, b| b.activation.total_cmp(&a.activation));
+        slots
+    }
+
+    pub fn len(&self) -> usize {
+        self.slots.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn contains(&self, id: &str) -> bool {
+        self.slots.lock().unwrap().iter().any(|slot| slot.record.id == id)
+    }
+
+    /// Whether an item with this activation would get a slot: there is a
+    /// free slot, or it is more active than the least active slot
+    pub fn admits(&self, activation: f64) -> bool {
+        let slots = self.slots.lock().unwrap();
+        if self.config.capacity == 0 {
+            return false;
+        }
+        slots.len() < self.config.capacity
+            || slots.iter().any(|slot| slot.activation < activation)
+    }
+
+    /// Put a record in a slot, evicting the least active slot if every slot
+    /// is taken
+    ///
+    /// A record already held keeps its slot and takes the higher of the two
+    /// activations. Returns the evicted slots, which include the record
+    /// itself when it is the least active.
+    pub fn admit(&self, record: MemoryRecord, activation: f64) -> Vec<WorkingSlot> {
+        let now = Utc::now();
+        let activation = activation.clamp(0.0, 1.0);
+        let mut slots = self.slots.lock().unwrap();
+
+        if let Some(slot) = slots.iter_mut().find(|slot| slot.record.id == record.id) {
+            slot.record = record;
+            slot.activation = slot.activation.max(activation);
+            slot.last_activated = now;
+            return Vec::new();
+        }
+
+        slots.push(WorkingSlot {
+            record,
+            activation,
+            entered_at: now,
+            last_activated: now,
+        });
+        let mut evicted = Vec::new();
+        while slots.len() > self.config.capacity {
+            // Ties go to the newest slot, so the oldest one leaves
+            let (index, _) = slots
+                .iter()
+                .enumerate()
+                .min_by(|(_, a), (_, b)| {
+                    a.activation
+                        .total_cmp(&b.activation)
+                        .then(a.last_activated.cmp(&b.last_activated))
+                })
+                .expect("slots is not empty");
+            evicted.push(slots.swap_remove(index));
+        }
+        gauge!("memory.working.slots", slots.len() as f64);
+        evicted
+    }
+
+    /// Raise the activation of the slot holding `content` that `namespace`
+    /// can see, if any, and return its record ID
+    pub fn refresh(&self, content: &str, namespace: &str, activation: f64) -> Option<String> {
+        let mut slots = self.slots.lock().unwrap();
+        let slot = slots.iter_mut().find(|slot| {
+            slot.record.visible_to(namespace) && slot.record.content.eq_ignore_ascii_case(content)
+        })?;
+        slot.activation = slot.activation.max(activation.clamp(0.0, 1.0));
+        slot.last_activated = Utc::now();
+        Some(slot.record.id.clone())
+    }
+
+    /// Boost every slot `namespace` can see whose content mentions `focus`
+    ///
+    /// Returns the number of slots boosted.
+    pub fn attend(&self, focus: &str, namespace: &str) -> usize {
+        let focus = focus.trim().to_lowercase();
+        if focus.is_empty() {
+            return 0;
+        }
+        let now = Utc::now();
+        let mut boosted = 0;
+        for slot in self.slots.lock().unwrap().iter_mut() {
+            if slot.record.visible_to(namespace) && slot.record.content.to_lowercase().contains(&focus) {
+                slot.activation = (slot.activation + self.config.focus_boost).min(1.0);
+                slot.last_activated = now;
+                boosted += 1;
+            }
+        }
+        boosted
+    }
+
+    /// Replace the record held in a slot, e.g. after its content changed
+    pub fn update_record(&self, record: &MemoryRecord) {
+        if let Some(slot) = self
+            .slots
+            .lock()
+            .unwrap()
+            .iter_mut()
+            .find(|slot| slot.record.id == record.id)
+        {
+            slot.record = record.clone();
+        }
+    }
+
+    /// Empty the slot holding a record
+    pub fn remove(&self, id: &str) -> Option<WorkingSlot> {
+        let mut slots = self.slots.lock().unwrap();
+        let index = slots.iter().position(|slot| slot.record.id == id)?;
+        let slot = slots.swap_remove(index);
+        gauge!("memory.working.slots", slots.len() as f64);
+        Some(slot)
+    }
+
+    /// Decay every slot by `decay_rate` and evict the slots that fall below
+    /// `min_activation`; pinned records are never evicted by decay
+    pub fn decay(&self) -> Vec<WorkingSlot> {
+        let mut slots = self.slots.lock().unwrap();
+        let retained = (1.0 - self.config.decay_rate).clamp(0.0, 1.0);
+        for slot in slots.iter_mut() {
+            slot.activation *= retained;
+        }
+
+        let mut evicted = Vec::new();
+        let mut index = 0;
+        while index < slots.len() {
+            if slots[index].activation < self.config.min_activation && !slots[index].record.pinned {
+                evicted.push(slots.swap_remove(index));
+            } else {
+                index += 1;
+            }
+        }
+        gauge!("memory.working.slots", slots.len() as f64);
+        evicted
+    }
+}
+
+impl MemorySystem {
+    /// Replace the default working memory configuration
+    ///
+    /// Records already held are dropped from the slots; call
+    /// `restore_working_memory` to reload them.
+    pub fn with_working_memory(mut self, config: WorkingMemoryConfig) -> Self {
+        self.working = WorkingMemory::new(config);
+        self
+    }
+
+    /// The current Working memory slots, most active first
+    pub fn working_slots(&self) -> Vec<WorkingSlot> {
+        self.working.slots()
+    }
+
+    /// Offer content broadcast by the consciousness engine on behalf of
+    /// `namespace` to Working memory
+    ///
+    /// Slots `namespace` can see that mention `focus` are boosted first.
+    /// Content it already sees held is refreshed with `priority`; new content
+    /// is stored in `namespace` only if `priority` (boosted when it mentions
+    /// `focus`) beats the least active slot. Returns the ID of the record
+    /// holding the content, or `None` if it didn't get a slot.
+    pub async fn attend_working(
+        &self,
+        content: &str,
+        priority: f64,
+        focus: &str,
+        namespace: &str,
+    ) -> Result<Option<String>> {
+        self.working.attend(focus, namespace);
+        if let Some(id) = self.working.refresh(content, namespace, priority) {
+            return Ok(Some(id));
+        }
+
+        let focus = focus.trim().to_lowercase();
+        let mut activation = priority;
+        if !focus.is_empty() && content.to_lowercase().contains(&focus) {
+            activation += self.working.config().focus_boost;
+        }
+        if !self.working.admits(activation) {
+            counter!("memory.working.rejected_total", 1);
+            return Ok(None);
+        }
+
+        let record = NewMemory::new(content)
+            .with_namespace(namespace)
+            .with_source(CONSCIOUSNESS_SOURCE)
+            .into_record(MemoryLayer::Working, Utc::now());
+        let mut records = [record];
+        self.embed_records(&mut records).await;
+        let [record] = records;
+        self.write_record(&record).await?;
+        self.hold_working(std::slice::from_ref(&record), |_| activation).await?;
+        Ok(Some(record.id))
+    }
+
+    /// Give stored Working records a slot, evicting the least active slots
+    pub(crate) async fn hold_working(
+        &self,
+        records: &[MemoryRecord],
+        activation: impl Fn(&MemoryRecord) -> f64,
+    ) -> Result<()> {
+        let mut evicted = Vec::new();
+        for record in records {
+            evicted.extend(self.working.admit(record.clone(), activation(record)));
+        }
+        for slot in evicted {
+            self.evict_working(slot.record).await?;
+        }
+        Ok(())
+    }
+
+    /// Decay Working memory activation once and evict the faded slots
+    ///
+    /// Returns the number of records evicted.
+    pub async fn decay_working(&self) -> Result<usize> {
+        let evicted = self.working.decay();
+        let count = evicted.len();
+        for slot in evicted {
+            self.evict_working(slot.record).await?;
+        }
+        Ok(count)
+    }
+
+    /// Fill the slots from the Working index, e.g. after a restart
+    ///
+    /// The newest records get the slots, with their importance as
+    /// activation; the rest are evicted. Returns the number of records held.
+    pub async fn restore_working_memory(&self) -> Result<usize> {
+        self.flush().await?;
+        let mut records = self.all_records(MemoryLayer::Working)?;
+        records.sort_by_key(|record| std::cmp::Reverse(record.timestamp));
+        let capacity = self.working.config().capacity;
+        let overflow = records.split_off(capacity.min(records.len()));
+
+        let held = records.len();
+        self.hold_working(&records, |record| record.importance as f64).await?;
+        for record in overflow {
+            self.evict_working(record).await?;
+        }
+        self.flush().await?;
+        Ok(held)
+    }
+
+    /// Spawn the activation decay loop on the Tokio runtime
+    ///
+    /// The task stops once the memory system is dropped.
+    pub fn spawn_working_decay(self: &Arc<Self>) -> JoinHandle<()> {
+        let memory = Arc::downgrade(self);
+        let period = self.working.config().tick().max(Duration::from_millis(100));
+
+        tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(period);
+            loop {
+                ticker.tick().await;
+                let Some(memory) = memory.upgrade() else {
+                    break;
+                };
+                if let Err(e) = memory.decay_working().await {
+                    error!("Working memory decay failed: {}", e);
+                }
+            }
+        })
+    }
+
+    /// Take an evicted record out of the Working layer, moving it to
+    /// ShortTerm when spilling is enabled
+    async fn evict_working(&self, mut record: MemoryRecord) -> Result<()> {
+        counter!("memory.working.evicted_total", 1);
+        if !self.working.config().spill_to_short_term {
+            debug!("Evicted {} from working memory", record.id);
+            return self.forget_record(MemoryLayer::Working, &record.id).await;
+        }
+
+        record.layer = MemoryLayer::ShortTerm;
+        self.write_record(&record).await?;
+        self.remove_record(MemoryLayer::Working, &record.id).await?;
+        counter!("memory.working.spilled_total", 1);
+        debug!("Spilled {} from working memory into short-term", record.id);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::memory::SHARED_NAMESPACE;
+    use tempfile::TempDir;
+
+    fn record(content: &str) -> MemoryRecord {
+        NewMemory::new(content).into_record(MemoryLayer::Working, Utc::now())
+    }
+
+    #[test]
+    fn test_least_active_slot_is_replaced() {
+        let working = WorkingMemory::new(WorkingMemoryConfig {
+            capacity: 2,
+            ..WorkingMemoryConfig::default()
+        });
+        assert!(working.admit(record("Phoenix's recital"), 0.9).is_empty());
+        assert!(working.admit(record("Router firmware"), 0.2).is_empty());
+
+        assert!(!working.admits(0.1));
+        assert!(working.admits(0.5));
+        let evicted = working.admit(record("Grocery list"), 0.5);
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].record.content, "Router firmware");
+
+        // Attending to the focus protects a slot from replacement
+        assert!(working.admits(0.6));
+        assert_eq!(working.attend("grocery", SHARED_NAMESPACE), 1);
+        assert!(!working.admits(0.6));
+        let evicted = working.admit(record("Weather"), 0.95);
+        assert_eq!(evicted[0].record.content, "Grocery list");
+        let contents: Vec<String> = working.slots().into_iter().map(|slot| slot.record.content).collect();
+        assert_eq!(contents, vec!["Weather", "Phoenix's recital"]);
+    }
+
+    #[test]
+    fn test_decay_evicts_faded_slots() {
+        let working = WorkingMemory::new(WorkingMemoryConfig {
+            decay_rate: 0.5,
+            min_activation: 0.1,
+            ..WorkingMemoryConfig::default()
+        });
+        working.admit(record("Strong"), 0.8);
+        working.admit(record("Weak"), 0.3);
+
+        assert!(working.decay().is_empty());
+        let evicted = working.decay();
+        assert_eq!(evicted.len(), 1);
+        assert_eq!(evicted[0].record.content, "Weak");
+        assert!((working.slots()[0].activation - 0.2).abs() < 1e-9);
+    }
+
+    #[tokio::test]
+    async fn test_evicted_records_spill_into_short_term() {
+        let dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf())
+            .await
+            .unwrap()
+            .with_working_memory(WorkingMemoryConfig {
+                capacity: 2,
+                ..WorkingMemoryConfig::default()
+            });
+
+        let first = memory
+            .store_with(MemoryLayer::Working, NewMemory::new("Pick up Phoenix at 5").with_importance(0.2))
+            .await
+            .unwrap();
+        memory.store(MemoryLayer::Working, "Call the plumber".to_string()).await.unwrap();
+        memory.store(MemoryLayer::Working, "Buy milk".to_string()).await.unwrap();
+
+        assert_eq!(memory.working_slots().len(), 2);
+        memory.flush().await.unwrap();
+        assert_eq!(memory.recent(MemoryLayer::Working, 10).await.unwrap().len(), 2);
+        assert_eq!(memory.get(&first).await.unwrap().unwrap().layer, MemoryLayer::ShortTerm);
+
+        // A low-priority broadcast loses the competition, a high one wins
+        let attend = |content: &'static str, priority: f64, namespace: &'static str| {
+            memory.attend_working(content, priority, "", namespace)
+        };
+        assert!(attend("Background hum", 0.1, "alice").await.unwrap().is_none());
+        let id = attend("Smoke alarm", 0.9, "alice").await.unwrap().unwrap();
+        assert_eq!(memory.working_slots()[0].record.id, id);
+        assert_eq!(memory.working_slots()[0].record.namespace, "alice");
+        assert_eq!(attend("smoke alarm", 0.4, "alice").await.unwrap(), Some(id.clone()));
+
+        // Other users don't refresh it, they get a slot of their own
+        let other = attend("Smoke alarm", 0.95, "bob").await.unwrap().unwrap();
+        assert_ne!(other, id);
+        assert_eq!(memory.get(&other).await.unwrap().unwrap().namespace, "bob");
+
+        // Deleting a record frees its slot
+        memory.delete(&memory.working_slots()[1].record.id).await.unwrap();
+        assert_eq!(memory.working_slots().len(), 1);
+    }
+}
@@ -60,7 +60,7 @@ pub enum ConnectionState {
 }
 
 /// Type alias for message handlers
-type MessageHandler = Arc<dyn Fn(String, Vec<u8>) -> () + Send + Sync>;
+type MessageHandler = Arc<dyn Fn(String, Vec<u8>) + Send + Sync>;
 
 /// MQTT async client wrapper
 pub struct MqttClient {
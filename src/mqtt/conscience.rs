@@ -0,0 +1,91 @@
+//! Conscience evaluations over MQTT
+//!
+//! Serves `jamey/conscience/evaluate`, answering each request on its
+//! `reply_to` topic with the evaluation broken down by rule.
+
+use std::sync::Arc;
+
+use rumqttc::QoS;
+use tracing::{info, warn};
+
+use super::client::{MqttClient, MqttError};
+use super::memory::reply;
+use super::messages::{ConscienceEvaluationRequest, ConscienceEvaluationResult, MqttMessage, RuleBreakdown};
+use crate::conscience::ConscienceEngine;
+
+/// Topic of conscience evaluation requests
+pub const CONSCIENCE_EVALUATE_TOPIC: &str = "jamey/conscience/evaluate";
+
+/// Evaluate the action of a request
+pub fn evaluate_request(conscience: &ConscienceEngine, request: &ConscienceEvaluationRequest) -> ConscienceEvaluationResult {
+    let evaluation = conscience.evaluate_detailed(&request.action);
+    ConscienceEvaluationResult {
+        applied_rules: evaluation.applied_rules(),
+        action: evaluation.action,
+        moral_score: evaluation.normalized_score as f64,
+        explanation: evaluation.explanation,
+        approved: evaluation.approved,
+        breakdown: evaluation
+            .contributions
+            .into_iter()
+            .map(|c| RuleBreakdown {
+                rule: c.rule,
+                weight: c.weight as f64,
+                polarity: c.polarity.as_str().to_string(),
+                matched_terms: c.matched_terms,
+                contribution: c.contribution as f64,
+            })
+            .collect(),
+    }
+}
+
+/// Subscribe to conscience evaluation requests and answer them from
+/// `conscience`
+///
+/// The handler holds the client weakly, so it stops replying once it is
+/// dropped.
+pub async fn serve_conscience(client: &Arc<MqttClient>, conscience: Arc<ConscienceEngine>) -> Result<(), MqttError> {
+    let replies = Arc::downgrade(client);
+    client
+        .subscribe_typed(
+            CONSCIENCE_EVALUATE_TOPIC,
+            QoS::AtLeastOnce,
+            move |message: MqttMessage<ConscienceEvaluationRequest>| {
+                let conscience = conscience.clone();
+                let replies = replies.clone();
+                tokio::spawn(async move {
+                    let reply_to = message.metadata.reply_to;
+                    if reply_to.is_none() {
+                        warn!("Dropping MQTT conscience evaluation without a reply-to topic");
+                        return;
+                    }
+                    let result = evaluate_request(&conscience, &message.payload);
+                    info!("Evaluated action from MQTT with score: {:.2}", result.moral_score);
+                    reply(&replies, reply_to, &result).await;
+                });
+            },
+        )
+        .await
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_result_carries_breakdown() {
+        let request = ConscienceEvaluationRequest {
+            action: "Be honest with them, then attack them".to_string(),
+            context: "chat".to_string(),
+            user_id: None,
+        };
+        let result = evaluate_request(&ConscienceEngine::new(), &request);
+
+        assert_eq!(result.applied_rules, ["no-harm", "truth"]);
+        assert_eq!(result.breakdown[0].polarity, "prohibition");
+        assert_eq!(result.breakdown[0].matched_terms, ["attack"]);
+        assert!((result.moral_score + 2.0 / 18.0).abs() < 1e-6);
+        assert!(!result.approved);
+        assert!(result.explanation.contains("no-harm (-10.00 from \"attack\")"));
+    }
+}
@@ -0,0 +1,214 @@
+//! Memory requests over MQTT
+//!
+//! Serves `jamey/memory/store` and `jamey/memory/query`. Memories are stored
+//! in the namespace of the request's `user_id`, and queries only see that
+//! namespace and the shared one. Requests without a `user_id` act on the
+//! shared namespace alone. Results are published to the request's
+//! `reply_to` topic, if any; a query that doesn't parse is answered there
+//! with an `invalid_query` error notification.
+
+use std::sync::{Arc, Weak};
+
+use anyhow::Result;
+use tracing::{error, info, warn};
+use uuid::Uuid;
+
+use super::client::{MqttClient, MqttError};
+use super::messages::{
+    ErrorNotification, MemoryEntry, MemoryQueryRequest, MemoryQueryResult, MemoryStoreRequest, MqttMessage,
+};
+use crate::memory::{
+    MemoryFilter, MemoryLayer, MemoryRecord, MemorySystem, NewMemory, QueryError, SearchAllOptions,
+    SearchOptions, SHARED_NAMESPACE,
+};
+use rumqttc::QoS;
+
+/// Topic of memory store requests
+pub const MEMORY_STORE_TOPIC: &str = "jamey/memory/store";
+
+/// Topic of memory query requests
+pub const MEMORY_QUERY_TOPIC: &str = "jamey/memory/query";
+
+/// Value of `source` on memories stored over MQTT
+pub const MQTT_SOURCE: &str = "mqtt";
+
+/// Most results a query returns, whatever its `limit`
+const MAX_QUERY_LIMIT: usize = 100;
+
+/// Namespace a request acts on: its user's, or the shared one
+fn namespace(user_id: &Option<String>) -> &str {
+    match user_id.as_deref().map(str::trim) {
+        Some(user_id) if !user_id.is_empty() => user_id,
+        _ => SHARED_NAMESPACE,
+    }
+}
+
+/// Store the memory of a request in its user's namespace
+pub async fn store_request(memory: &MemorySystem, request: MemoryStoreRequest) -> Result<MemoryEntry> {
+    let layer: MemoryLayer = request.layer.parse()?;
+    let namespace = namespace(&request.user_id).to_string();
+    let memory_to_store = NewMemory::new(request.content)
+        .with_namespace(namespace)
+        .with_tags(request.tags)
+        .with_source(MQTT_SOURCE);
+
+    let id = memory.store_with(layer, memory_to_store).await?;
+    let record = memory
+        .get(&id)
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("Memory {} vanished after it was stored", id))?;
+    Ok(entry(record))
+}
+
+/// Search the memories the request's user can see
+pub async fn query_request(memory: &MemorySystem, request: MemoryQueryRequest) -> Result<MemoryQueryResult> {
+    let filter = match namespace(&request.user_id) {
+        SHARED_NAMESPACE => MemoryFilter::default().with_namespace(SHARED_NAMESPACE),
+        owner => MemoryFilter::visible_to(owner),
+    };
+    let limit = request.limit.clamp(1, MAX_QUERY_LIMIT);
+
+    let records = match request.layer {
+        Some(layer) => {
+            let options = SearchOptions::new(limit).with_filter(filter);
+            memory.search_with(layer.parse()?, &request.query, &options).await?
+        }
+        None => {
+            let options = SearchAllOptions::new(limit).with_filter(filter);
+            memory.search_all(&request.query, &options).await?
+        }
+    };
+
+    let memories: Vec<MemoryEntry> = records.into_iter().map(entry).collect();
+    Ok(MemoryQueryResult {
+        total: memories.len(),
+        memories,
+    })
+}
+
+fn entry(record: MemoryRecord) -> MemoryEntry {
+    MemoryEntry {
+        id: Uuid::parse_str(&record.id).unwrap_or_default(),
+        layer: record.layer.as_str().to_string(),
+        content: record.content,
+        tags: record.tags,
+        score: record.score.unwrap_or(0.0) as f64,
+        created_at: record.timestamp,
+    }
+}
+
+/// Subscribe to the memory request topics and serve them from `memory`
+///
+/// The handlers hold the client weakly, so they stop replying once it is
+/// dropped.
+pub async fn serve_memory(client: &Arc<MqttClient>, memory: Arc<MemorySystem>) -> Result<(), MqttError> {
+    let replies = Arc::downgrade(client);
+    let store_memory = memory.clone();
+    client
+        .subscribe_typed(MEMORY_STORE_TOPIC, QoS::AtLeastOnce, move |message: MqttMessage<MemoryStoreRequest>| {
+            let memory = store_memory.clone();
+            let replies = replies.clone();
+            tokio::spawn(async move {
+                let reply_to = message.metadata.reply_to;
+                match store_request(&memory, message.payload).await {
+                    Ok(entry) => {
+                        info!("Stored memory {} from MQTT", entry.id);
+                        reply(&replies, reply_to, &entry).await;
+                    }
+                    Err(e) => error!("Failed to store memory from MQTT: {}", e),
+                }
+            });
+        })
+        .await?;
+
+    let replies = Arc::downgrade(client);
+    client
+        .subscribe_typed(MEMORY_QUERY_TOPIC, QoS::AtLeastOnce, move |message: MqttMessage<MemoryQueryRequest>| {
+            let memory = memory.clone();
+            let replies = replies.clone();
+            tokio::spawn(async move {
+                let reply_to = message.metadata.reply_to;
+                if reply_to.is_none() {
+                    warn!("Dropping MQTT memory query without a reply-to topic");
+                    return;
+                }
+                match query_request(&memory, message.payload).await {
+                    Ok(result) => reply(&replies, reply_to, &result).await,
+                    Err(e) => match e.downcast_ref::<QueryError>() {
+                        Some(query_error) => {
+                            warn!("Invalid MQTT memory query: {}", query_error);
+                            reply(&replies, reply_to, &invalid_query(query_error)).await;
+                        }
+                        None => error!("Failed to query memories from MQTT: {}", e),
+                    },
+                }
+            });
+        })
+        .await?;
+
+    Ok(())
+}
+
+fn invalid_query(error: &QueryError) -> ErrorNotification {
+    ErrorNotification {
+        code: "invalid_query".to_string(),
+        message: error.to_string(),
+        component: "memory".to_string(),
+        details: None,
+        severity: "warning".to_string(),
+    }
+}
+
+pub(super) async fn reply<T: serde::Serialize>(client: &Weak<MqttClient>, reply_to: Option<String>, payload: &T) {
+    let (Some(client), Some(topic)) = (client.upgrade(), reply_to) else {
+        return;
+    };
+    if let Err(e) = client.publish(&topic, payload, QoS::AtLeastOnce).await {
+        error!("Failed to publish reply to {}: {}", topic, e);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn test_requests_are_scoped_to_their_user() {
+        let dir = TempDir::new().unwrap();
+        let memory = MemorySystem::new(dir.path().to_path_buf()).await.unwrap();
+        let store = |content: &str, user_id: Option<&str>| MemoryStoreRequest {
+            layer: "episodic".to_string(),
+            content: content.to_string(),
+            tags: vec!["sensor".to_string()],
+            user_id: user_id.map(String::from),
+        };
+        store_request(&memory, store("Alice's door opened", Some("alice"))).await.unwrap();
+        store_request(&memory, store("Bob's door opened", Some("bob"))).await.unwrap();
+        store_request(&memory, store("Front door opened", None)).await.unwrap();
+        assert!(store_request(&memory, MemoryStoreRequest {
+            layer: "sensory".to_string(),
+            ..store("Nowhere", None)
+        })
+        .await
+        .is_err());
+        memory.flush().await.unwrap();
+
+        let query = |user_id: Option<&str>| MemoryQueryRequest {
+            query: "door".to_string(),
+            layer: Some("episodic".to_string()),
+            limit: 10,
+            user_id: user_id.map(String::from),
+        };
+        let mut contents: Vec<String> = query_request(&memory, query(Some("alice")))
+            .await
+            .unwrap()
+            .memories
+            .into_iter()
+            .map(|entry| entry.content)
+            .collect();
+        contents.sort();
+        assert_eq!(contents, vec!["Alice's door opened", "Front door opened"]);
+        assert_eq!(query_request(&memory, query(None)).await.unwrap().total, 1);
+    }
+}
@@ -115,6 +115,29 @@ pub struct ConscienceEvaluationResult {
     
     /// Whether the action was approved
     pub approved: bool,
+
+    /// What each moral rule contributed, largest first
+    #[serde(default)]
+    pub breakdown: Vec<RuleBreakdown>,
+}
+
+/// One moral rule's part in a conscience evaluation
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RuleBreakdown {
+    /// Rule name
+    pub rule: String,
+
+    /// Rule weight
+    pub weight: f64,
+
+    /// "virtue" or "prohibition"
+    pub polarity: String,
+
+    /// Terms of the rule found in the action
+    pub matched_terms: Vec<String>,
+
+    /// What the rule added to the raw score
+    pub contribution: f64,
 }
 
 /// Memory store request payload
@@ -7,6 +7,8 @@
 //! - Automatic token refresh
 //! - Topic-based permissions
 //! - Typed message handling
+//! - Memory store and query requests, scoped to the requesting user
+//! - Conscience evaluations, broken down by moral rule
 //!
 //! # Example Usage
 //!
@@ -37,17 +39,21 @@
 mod auth;
 mod client;
 mod config;
+mod conscience;
+mod memory;
 mod messages;
 
 // Re-export public API
 pub use auth::{AuthError, JwtManager, MqttClaims};
 pub use client::{ConnectionState, MqttClient, MqttError};
 pub use config::{ConfigError, MqttConfig, ReconnectConfig};
+pub use conscience::{evaluate_request, serve_conscience, CONSCIENCE_EVALUATE_TOPIC};
+pub use memory::{query_request, serve_memory, store_request, MEMORY_QUERY_TOPIC, MEMORY_STORE_TOPIC};
 pub use messages::{
     deserialize_message, serialize_message, ApiRequestProxy, ApiResponse,
     ConscienceEvaluationRequest, ConscienceEvaluationResult, ErrorNotification, Heartbeat,
     HeartbeatMetrics, MemoryEntry, MemoryQueryRequest, MemoryQueryResult, MemoryStoreRequest,
-    MessageMetadata, MqttMessage, SystemStatus,
+    MessageMetadata, MqttMessage, RuleBreakdown, SystemStatus,
 };
 
 // Re-export QoS from rumqttc for convenience
@@ -12,11 +12,12 @@ use axum::{
 use jsonwebtoken::{decode, encode, DecodingKey, EncodingKey, Header, Validation};
 use serde::{Deserialize, Serialize};
 use std::env;
+use std::sync::Arc;
 use time::{Duration, OffsetDateTime};
 use tracing::{error, info, warn};
 
 /// JWT claims structure
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct JwtClaims {
     /// Subject (user identifier)
     pub sub: String,
@@ -109,17 +110,13 @@ impl JwtAuth {
 
     /// Extract token from authorization header
     fn extract_token_from_header(auth_header: &str) -> Option<&str> {
-        if auth_header.starts_with("Bearer ") {
-            Some(&auth_header[7..])
-        } else {
-            None
-        }
+        auth_header.strip_prefix("Bearer ")
     }
 }
 
 /// Axum middleware function for JWT authentication
 pub async fn jwt_auth_middleware(
-    State(auth): State<JwtAuth>,
+    State(auth): State<Arc<JwtAuth>>,
     mut request: Request,
     next: Next,
 ) -> Result<Response, StatusCode> {
@@ -134,7 +131,7 @@ pub async fn jwt_auth_middleware(
         })?;
 
     // Extract token from header
-    let token = Self::extract_token_from_header(auth_header).ok_or_else(|| {
+    let token = JwtAuth::extract_token_from_header(auth_header).ok_or_else(|| {
         warn!("Invalid authorization header format for path: {}", request.uri().path());
         StatusCode::UNAUTHORIZED
     })?;
@@ -174,7 +171,7 @@ pub struct LoginResponse {
 
 /// Login endpoint handler
 pub async fn login(
-    State(auth): State<JwtAuth>,
+    State(auth): State<Arc<JwtAuth>>,
     Json(request): Json<LoginRequest>,
 ) -> Result<Json<LoginResponse>, StatusCode> {
     // TODO: Implement proper user authentication against database
@@ -4,15 +4,26 @@
 
 use axum::{
     extract::Request,
-    http::{header, HeaderValue},
+    http::{header, HeaderMap, HeaderValue},
     middleware::Next,
     response::Response,
 };
+use futures::future::BoxFuture;
 use std::env;
+use std::task::{Context, Poll};
+use tower::{Layer, Service};
 use tracing::{info, warn};
 
 /// Security headers middleware function
 pub async fn security_headers_middleware(request: Request, next: Next) -> Response {
+    let path = request.uri().path().to_string();
+    let mut response = next.run(request).await;
+    apply_security_headers(response.headers_mut(), &path);
+    response
+}
+
+/// Insert the security headers for a response to `path`
+fn apply_security_headers(headers: &mut HeaderMap, path: &str) {
     let environment = env::var("RUST_ENV").unwrap_or_else(|_| "development".to_string());
     let is_production = environment == "production";
     
@@ -20,9 +31,6 @@ pub async fn security_headers_middleware(request: Request, next: Next) -> Respon
         warn!("Security headers configured for development environment");
     }
 
-    let mut response = next.run(request).await;
-    let headers = response.headers_mut();
-
     // Content Security Policy (CSP)
     let csp = if is_production {
         // Production: restrictive CSP
@@ -83,7 +91,7 @@ pub async fn security_headers_middleware(request: Request, next: Next) -> Respon
     }
 
     // Cache control for API endpoints
-    if request.uri().path().starts_with("/api") {
+    if path.starts_with("/api") {
         let cache_control = "no-store, no-cache, must-revalidate, proxy-revalidate";
         if let Ok(cache_header) = HeaderValue::from_str(cache_control) {
             headers.insert(header::CACHE_CONTROL, cache_header);
@@ -94,11 +102,11 @@ pub async fn security_headers_middleware(request: Request, next: Next) -> Respon
         }
     }
 
-    info!("Applied security headers to response for path: {}", request.uri().path());
-    response
+    info!("Applied security headers to response for path: {}", path);
 }
 
 /// Security headers layer for convenience
+#[derive(Debug, Clone, Copy)]
 pub struct SecurityHeadersLayer;
 
 impl SecurityHeadersLayer {
@@ -117,6 +125,43 @@ impl Default for SecurityHeadersLayer {
     }
 }
 
+impl<S> Layer<S> for SecurityHeadersLayer {
+    type Service = SecurityHeadersService<S>;
+
+    fn layer(&self, inner: S) -> Self::Service {
+        SecurityHeadersService { inner }
+    }
+}
+
+/// Service applying the security headers to every response of `inner`
+#[derive(Debug, Clone)]
+pub struct SecurityHeadersService<S> {
+    inner: S,
+}
+
+impl<S> Service<Request> for SecurityHeadersService<S>
+where
+    S: Service<Request, Response = Response>,
+    S::Future: Send + 'static,
+{
+    type Response = Response;
+    type Error = S::Error;
+    type Future = BoxFuture<'static, Result<Response, S::Error>>;
+
+    fn poll_ready(&mut self, cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        self.inner.poll_ready(cx)
+    }
+
+    fn call(&mut self, request: Request) -> Self::Future {
+        let path = request.uri().path().to_string();
+        let future = self.inner.call(request);
+        Box::pin(async move {
+            let mut response = future.await?;
+            apply_security_headers(response.headers_mut(), &path);
+            Ok(response)
+        })
+    }
+}
 
 
 #[cfg(test)]
@@ -124,8 +169,7 @@ mod tests {
     use super::*;
     use axum::{
         body::Body,
-        http::{Request, StatusCode},
-        response::Response,
+        http::Request,
         routing::get,
         Router,
     };
@@ -14,5 +14,5 @@ pub mod validation;
 
 pub use auth::{JwtAuth, JwtClaims, AuthError};
 pub use headers::SecurityHeadersLayer;
-pub use rate_limit::RateLimitLayer;
+pub use rate_limit::{rate_limit_middleware, RateLimitConfig};
 pub use validation::validate_input;
\ No newline at end of file
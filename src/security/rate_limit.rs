@@ -6,7 +6,7 @@ use axum::{
     extract::Request,
     http::StatusCode,
     middleware::Next,
-    response::Response,
+    response::{IntoResponse, Response},
 };
 use governor::{
     clock::QuantaClock,
@@ -2,27 +2,42 @@
 //!
 //! Provides comprehensive input validation and sanitization for API endpoints.
 
+use chrono::{DateTime, Utc};
 use serde::{Deserialize, Serialize};
 use validator::{Validate, ValidationError, ValidationErrors};
 use tracing::{error, warn};
+use crate::conscience::RulePolarity;
+use crate::memory::{MemoryLayer, SearchMode};
 
 /// Maximum allowed string lengths
 pub mod limits {
-    pub const MAX_ACTION_LENGTH: usize = 1000;
-    pub const MAX_RULE_NAME_LENGTH: usize = 100;
-    pub const MAX_RULE_DESCRIPTION_LENGTH: usize = 500;
-    pub const MAX_CONTENT_LENGTH: usize = 10000;
-    pub const MAX_USERNAME_LENGTH: usize = 50;
-    pub const MAX_PASSWORD_LENGTH: usize = 128;
-    pub const MIN_PASSWORD_LENGTH: usize = 8;
+    pub const MAX_ACTION_LENGTH: u64 = 1000;
+    pub const MAX_RULE_NAME_LENGTH: u64 = 100;
+    pub const MAX_RULE_DESCRIPTION_LENGTH: u64 = 500;
+    pub const MAX_CONTENT_LENGTH: u64 = 10000;
+    pub const MAX_USERNAME_LENGTH: u64 = 50;
+    pub const MAX_PASSWORD_LENGTH: u64 = 128;
+    pub const MIN_PASSWORD_LENGTH: u64 = 8;
+    pub const MAX_QUERY_LENGTH: u64 = 1000;
+    pub const MAX_LABEL_LENGTH: u64 = 100;
+    pub const MAX_LABELS: u64 = 32;
 }
 
 /// Common validation patterns
 pub mod patterns {
+    use regex::Regex;
+    use std::sync::LazyLock;
+
     pub const ALPHANUMERIC: &str = r"^[a-zA-Z0-9]*$";
     pub const SAFE_STRING: &str = r"^[a-zA-Z0-9\s\-_.,!?@#$%^&*()]*$";
     pub const USERNAME: &str = r"^[a-zA-Z0-9_-]{3,50}$";
     pub const EMAIL: &str = r"^[a-zA-Z0-9._%+-]+@[a-zA-Z0-9.-]+\.[a-zA-Z]{2,}$";
+
+    /// Compiled patterns for `#[validate(regex(...))]`
+    pub static SAFE_STRING_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(SAFE_STRING).expect("SAFE_STRING is a valid regex"));
+    pub static USERNAME_RE: LazyLock<Regex> =
+        LazyLock::new(|| Regex::new(USERNAME).expect("USERNAME is a valid regex"));
 }
 
 /// Validate and sanitize input string
@@ -53,7 +68,7 @@ pub fn validate_string(input: &str, max_length: usize, pattern: Option<&str>) ->
 #[derive(Debug, Deserialize, Validate)]
 pub struct ActionInput {
     #[validate(length(max = "limits::MAX_ACTION_LENGTH", message = "Action too long"))]
-    #[validate(regex(path = "patterns::SAFE_STRING", message = "Invalid characters in action"))]
+    #[validate(regex(path = *patterns::SAFE_STRING_RE, message = "Invalid characters in action"))]
     pub action: String,
 }
 
@@ -61,30 +76,258 @@ pub struct ActionInput {
 #[derive(Debug, Deserialize, Validate)]
 pub struct RuleInput {
     #[validate(length(max = "limits::MAX_RULE_NAME_LENGTH", message = "Rule name too long"))]
-    #[validate(regex(path = "patterns::SAFE_STRING", message = "Invalid characters in rule name"))]
+    #[validate(regex(path = *patterns::SAFE_STRING_RE, message = "Invalid characters in rule name"))]
     pub name: String,
 
     #[validate(length(max = "limits::MAX_RULE_DESCRIPTION_LENGTH", message = "Description too long"))]
-    #[validate(regex(path = "patterns::SAFE_STRING", message = "Invalid characters in description"))]
+    #[validate(regex(path = *patterns::SAFE_STRING_RE, message = "Invalid characters in description"))]
     pub description: String,
 
     #[validate(range(min = 0.0, max = 1.0, message = "Weight must be between 0.0 and 1.0"))]
     pub weight: f32,
+
+    #[serde(default)]
+    pub polarity: RulePolarity,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many triggers"), custom(function = "validate_labels"))]
+    pub triggers: Vec<String>,
+
+    #[serde(default)]
+    pub veto: bool,
 }
 
 /// Validate content input for consciousness processing
 #[derive(Debug, Deserialize, Validate)]
 pub struct ContentInput {
     #[validate(length(max = "limits::MAX_CONTENT_LENGTH", message = "Content too long"))]
-    #[validate(regex(path = "patterns::SAFE_STRING", message = "Invalid characters in content"))]
+    #[validate(regex(path = *patterns::SAFE_STRING_RE, message = "Invalid characters in content"))]
+    pub content: String,
+}
+
+/// Validate memory input for storing a record
+#[derive(Debug, Deserialize, Validate)]
+pub struct MemoryInput {
+    pub layer: MemoryLayer,
+
+    #[validate(length(min = 1, max = limits::MAX_CONTENT_LENGTH, message = "Content must be 1 to 10000 characters"))]
     pub content: String,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many tags"), custom(function = "validate_labels"))]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many entities"), custom(function = "validate_labels"))]
+    pub entities: Vec<String>,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Invalid source"))]
+    pub source: Option<String>,
+
+    #[validate(range(min = 0.0, max = 1.0, message = "Importance must be between 0.0 and 1.0"))]
+    pub importance: Option<f32>,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many emotional tags"), custom(function = "validate_labels"))]
+    pub emotional_tags: Vec<String>,
+
+    #[serde(default)]
+    pub pinned: bool,
+}
+
+/// Validate memory input for replacing a record's content
+#[derive(Debug, Deserialize, Validate)]
+pub struct MemoryUpdateInput {
+    #[validate(length(min = 1, max = limits::MAX_CONTENT_LENGTH, message = "Content must be 1 to 10000 characters"))]
+    pub content: String,
+}
+
+/// Validate memory search query parameters
+///
+/// Searches every layer when `layer` is not given.
+#[derive(Debug, Deserialize, Validate)]
+pub struct MemorySearchQuery {
+    #[validate(length(max = limits::MAX_QUERY_LENGTH, message = "Query too long"))]
+    pub q: String,
+
+    #[serde(default)]
+    pub layer: Option<MemoryLayer>,
+
+    #[serde(default)]
+    pub mode: SearchMode,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Invalid tag"))]
+    pub tag: Option<String>,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Invalid entity"))]
+    pub entity: Option<String>,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Invalid source"))]
+    pub source: Option<String>,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Invalid emotional tag"))]
+    pub emotion: Option<String>,
+
+    #[validate(range(min = 0.0, max = 1.0, message = "Importance must be between 0.0 and 1.0"))]
+    pub min_importance: Option<f32>,
+
+    pub since: Option<DateTime<Utc>>,
+    pub until: Option<DateTime<Utc>>,
+
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<usize>,
+
+    #[validate(range(max = 1000, message = "Offset must be at most 1000"))]
+    pub offset: Option<usize>,
+
+    /// Attach a highlighted snippet of its content to each result
+    #[serde(default)]
+    pub highlight: bool,
+
+    /// Attach the explanation of its keyword score to each result
+    #[serde(default)]
+    pub debug: bool,
+}
+
+/// Validate query parameters for listing recent memories in a layer
+#[derive(Debug, Deserialize, Validate)]
+pub struct MemoryRecentQuery {
+    pub layer: MemoryLayer,
+
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<usize>,
+
+    #[validate(range(max = 1000, message = "Offset must be at most 1000"))]
+    pub offset: Option<usize>,
+}
+
+/// Validate query parameters for memory access statistics
+#[derive(Debug, Deserialize, Validate)]
+pub struct MemoryStatsQuery {
+    /// Every layer when not given
+    #[serde(default)]
+    pub layer: Option<MemoryLayer>,
+
+    #[validate(range(min = 1, max = 100, message = "Limit must be between 1 and 100"))]
+    pub limit: Option<usize>,
+}
+
+/// Validate input for opening an episode
+#[derive(Debug, Deserialize, Validate)]
+pub struct EpisodeOpenInput {
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Title must be 1 to 100 characters"))]
+    pub title: String,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many participants"), custom(function = "validate_labels"))]
+    pub participants: Vec<String>,
+}
+
+/// Validate input for appending a record to the current episode
+#[derive(Debug, Deserialize, Validate)]
+pub struct EpisodeAppendInput {
+    #[validate(length(min = 1, max = limits::MAX_CONTENT_LENGTH, message = "Content must be 1 to 10000 characters"))]
+    pub content: String,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many tags"), custom(function = "validate_labels"))]
+    pub tags: Vec<String>,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many entities"), custom(function = "validate_labels"))]
+    pub entities: Vec<String>,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Invalid source"))]
+    pub source: Option<String>,
+
+    #[validate(range(min = 0.0, max = 1.0, message = "Importance must be between 0.0 and 1.0"))]
+    pub importance: Option<f32>,
+
+    #[serde(default)]
+    #[validate(length(max = limits::MAX_LABELS, message = "Too many emotional tags"), custom(function = "validate_labels"))]
+    pub emotional_tags: Vec<String>,
+}
+
+/// Validate input for closing the current episode
+#[derive(Debug, Default, Deserialize, Validate)]
+pub struct EpisodeCloseInput {
+    #[validate(length(min = 1, max = limits::MAX_CONTENT_LENGTH, message = "Summary must be 1 to 10000 characters"))]
+    pub summary: Option<String>,
+}
+
+/// Validate query parameters for consciousness metrics history
+///
+/// `resolution` is the bucket length in seconds.
+#[derive(Debug, Deserialize, Validate)]
+pub struct ConsciousnessHistoryQuery {
+    pub from: Option<DateTime<Utc>>,
+    pub to: Option<DateTime<Utc>>,
+
+    #[validate(range(min = 1, max = 86400, message = "Resolution must be between 1 and 86400 seconds"))]
+    pub resolution: Option<u64>,
+}
+
+/// Validate input for asserting a knowledge graph triple
+#[derive(Debug, Deserialize, Validate)]
+pub struct TripleInput {
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Subject must be 1 to 100 characters"))]
+    pub subject: String,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Predicate must be 1 to 100 characters"))]
+    pub predicate: String,
+
+    #[validate(length(min = 1, max = limits::MAX_CONTENT_LENGTH, message = "Object must be 1 to 10000 characters"))]
+    pub object: String,
+
+    /// The object is a value rather than an entity
+    #[serde(default)]
+    pub literal: bool,
+
+    #[validate(range(min = 0.0, max = 1.0, message = "Confidence must be between 0.0 and 1.0"))]
+    pub confidence: Option<f32>,
+
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Invalid source memory ID"))]
+    pub source_memory_id: Option<String>,
+}
+
+/// Validate query parameters for matching knowledge graph triples
+///
+/// `pattern` is three terms, each `?` for any value: `Phoenix.Marie ? ?`.
+#[derive(Debug, Deserialize, Validate)]
+pub struct TripleQuery {
+    #[validate(length(min = 1, max = limits::MAX_QUERY_LENGTH, message = "Pattern must be 1 to 1000 characters"))]
+    pub pattern: String,
+
+    #[validate(range(min = 1, max = 1000, message = "Limit must be between 1 and 1000"))]
+    pub limit: Option<usize>,
+}
+
+/// Validate query parameters for a knowledge graph neighborhood
+#[derive(Debug, Deserialize, Validate)]
+pub struct NeighborhoodQuery {
+    #[validate(length(min = 1, max = limits::MAX_LABEL_LENGTH, message = "Entity must be 1 to 100 characters"))]
+    pub entity: String,
+
+    #[validate(range(min = 1, max = 3, message = "Hops must be between 1 and 3"))]
+    pub hops: Option<usize>,
+}
+
+/// Every label must be non-empty and at most `MAX_LABEL_LENGTH` long
+fn validate_labels(labels: &[String]) -> Result<(), ValidationError> {
+    if labels
+        .iter()
+        .any(|label| label.trim().is_empty() || label.len() as u64 > limits::MAX_LABEL_LENGTH)
+    {
+        return Err(ValidationError::new("invalid_label"));
+    }
+    Ok(())
 }
 
 /// Validate login input
 #[derive(Debug, Deserialize, Validate)]
 pub struct LoginInput {
     #[validate(length(max = "limits::MAX_USERNAME_LENGTH", message = "Username too long"))]
-    #[validate(regex(path = "patterns::USERNAME", message = "Invalid username format"))]
+    #[validate(regex(path = *patterns::USERNAME_RE, message = "Invalid username format"))]
     pub username: String,
 
     #[validate(length(min = "limits::MIN_PASSWORD_LENGTH", max = "limits::MAX_PASSWORD_LENGTH", message = "Invalid password length"))]
@@ -167,10 +410,10 @@ impl SecurityValidationResult {
 /// Comprehensive security validation for API requests
 pub fn validate_request_security(
     user_agent: Option<&str>,
-    ip_address: Option<&str>,
+    _ip_address: Option<&str>,
     content_type: Option<&str>,
 ) -> SecurityValidationResult {
-    let mut errors = Vec::new();
+    let errors: Vec<String> = Vec::new();
     let mut warnings = Vec::new();
 
     // Check user agent
@@ -286,6 +529,49 @@ mod tests {
         assert!(validate_input(&invalid_input).is_err());
     }
 
+    #[test]
+    fn test_memory_input_validation() {
+        let valid_input: MemoryInput = serde_json::from_value(serde_json::json!({
+            "layer": "long_term",
+            "content": "Phoenix's recital is on Friday",
+            "tags": ["family"],
+            "importance": 0.8
+        }))
+        .unwrap();
+        assert!(validate_input(&valid_input).is_ok());
+
+        let invalid_input = MemoryInput {
+            tags: vec![" ".to_string()],
+            importance: Some(1.5),
+            ..valid_input
+        };
+        assert!(validate_input(&invalid_input).is_err());
+
+        let invalid_query: MemorySearchQuery =
+            serde_json::from_value(serde_json::json!({ "q": "recital", "limit": 500 })).unwrap();
+        assert!(validate_input(&invalid_query).is_err());
+
+        let query: MemorySearchQuery =
+            serde_json::from_value(serde_json::json!({ "q": "recital", "layer": "short_term" })).unwrap();
+        assert_eq!(query.layer, Some(MemoryLayer::ShortTerm));
+
+        // Layers are accepted as they appear in responses and older exports
+        for name in ["long_term", "LongTerm"] {
+            let input: MemoryInput = serde_json::from_value(serde_json::json!({
+                "layer": name,
+                "content": "Phoenix's recital is on Friday"
+            }))
+            .unwrap();
+            assert_eq!(input.layer, MemoryLayer::LongTerm);
+        }
+        assert!(serde_json::from_value::<MemoryInput>(serde_json::json!({
+            "layer": "long-term",
+            "content": "Phoenix's recital is on Friday"
+        }))
+        .is_err());
+        assert_eq!(serde_json::to_value(MemoryLayer::LongTerm).unwrap(), "long_term");
+    }
+
     #[test]
     fn test_login_input_validation() {
         let valid_input = LoginInput {
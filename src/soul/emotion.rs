@@ -301,7 +301,13 @@ impl EmotionManager {
             (intensity_variance * 0.5)
         );
 
-        stability.max(0.0).min(1.0)
+        stability.clamp(0.0, 1.0)
+    }
+}
+
+impl Default for EmotionManager {
+    fn default() -> Self {
+        Self::new()
     }
 }
 
@@ -157,6 +157,16 @@ impl SoulStorage {
         Ok(())
     }
     
+    /// Remove every link to a memory that no longer exists
+    pub async fn unlink_memory(&self, memory_id: Uuid) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM soul_memory_links WHERE memory_id = ?")
+            .bind(memory_id.to_string())
+            .execute(&self.pool)
+            .await?;
+        
+        Ok(result.rows_affected())
+    }
+    
     /// Get all memory IDs linked to an entity
     pub async fn get_entity_memories(&self, entity_id: i64) -> Result<Vec<Uuid>> {
         let rows = sqlx::query(
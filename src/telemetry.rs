@@ -1,8 +1,7 @@
 use anyhow::Result;
-use opentelemetry::sdk::propagation::TraceContextPropagator;
-use opentelemetry::sdk::{trace, Resource};
 use opentelemetry::{global, KeyValue};
-use opentelemetry_otlp::WithExportConfig;
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::{trace, Resource};
 use tracing::subscriber::set_global_default;
 use tracing_bunyan_formatter::{BunyanFormattingLayer, JsonStorageLayer};
 use tracing_log::LogTracer;
@@ -15,8 +14,9 @@ pub fn init_telemetry() -> Result<()> {
     // Set the global propagator to be a TraceContextPropagator
     global::set_text_map_propagator(TraceContextPropagator::new());
 
-    // Create a new OpenTelemetry pipeline
-    let otlp_exporter = opentelemetry_otlp::new_exporter().tonic().with_env();
+    // Create a new OpenTelemetry pipeline; the endpoint comes from the
+    // standard OTEL_EXPORTER_OTLP_* environment variables
+    let otlp_exporter = opentelemetry_otlp::new_exporter().tonic();
 
     let tracer = opentelemetry_otlp::new_pipeline()
         .tracing()
@@ -27,7 +27,7 @@ pub fn init_telemetry() -> Result<()> {
                 "jamey-3-backend",
             )])),
         )
-        .install_batch(opentelemetry::runtime::Tokio)?;
+        .install_batch(opentelemetry_sdk::runtime::Tokio)?;
 
     // Create a tracing layer with the configured tracer
     let telemetry_layer = tracing_opentelemetry::layer().with_tracer(tracer);